@@ -0,0 +1,338 @@
+use wit_bindgen_core::visitor::{priority, AnnotationSchema, AnnotationValue, ArgKind, ItemKind};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, ContributionTypes, VisitContext, Visitor};
+
+/// Contributions for type definitions
+#[derive(Default, Debug, Clone)]
+pub struct CTypeContribution {
+    /// Attributes to add to the typedef, e.g. `packed` or `aligned(16)`,
+    /// rendered as `__attribute__((...))`
+    pub attributes: Vec<String>,
+}
+
+impl CTypeContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for struct fields
+#[derive(Default, Debug, Clone)]
+pub struct CFieldContribution {
+    /// Field-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl CFieldContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for the union members of variant cases
+#[derive(Default, Debug, Clone)]
+pub struct CVariantCaseContribution {
+    /// Case-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl CVariantCaseContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for functions
+#[derive(Default, Debug, Clone)]
+pub struct CFunctionContribution {
+    /// Attributes to add to the function's declaration in the header
+    pub attributes: Vec<String>,
+
+    /// Statements to run at the start of the import or export wrapper, after
+    /// parameters have been adapted but before the call
+    pub prologue: Vec<String>,
+
+    /// Statements to run at the end of the import or export wrapper, just
+    /// before it returns
+    pub epilogue: Vec<String>,
+}
+
+impl CFunctionContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add a statement to the start of the wrapper
+    pub fn add_prologue(&mut self, code: impl Into<String>) {
+        self.prologue.push(code.into());
+    }
+
+    /// Add a statement to the end of the wrapper
+    pub fn add_epilogue(&mut self, code: impl Into<String>) {
+        self.epilogue.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.prologue.is_empty() && self.epilogue.is_empty()
+    }
+}
+
+/// Contributions for the generated header, from interfaces and worlds
+#[derive(Default, Debug, Clone)]
+pub struct CModuleContribution {
+    /// Headers to `#include`, e.g. `<assert.h>` or `"my_header.h"`
+    pub includes: Vec<String>,
+
+    /// `#pragma` directives to add after the includes, without the
+    /// leading `#pragma`
+    pub pragmas: Vec<String>,
+}
+
+impl CModuleContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an include
+    pub fn add_include(&mut self, include: impl Into<String>) {
+        self.includes.push(include.into());
+    }
+
+    /// Add a pragma
+    pub fn add_pragma(&mut self, pragma: impl Into<String>) {
+        self.pragmas.push(pragma.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.pragmas.is_empty()
+    }
+}
+
+/// The family of C contribution types.
+pub struct CContributions;
+
+impl ContributionTypes for CContributions {
+    type Type = CTypeContribution;
+    type Field = CFieldContribution;
+    type VariantCase = CVariantCaseContribution;
+    type Function = CFunctionContribution;
+    type Module = CModuleContribution;
+    // Annotations naming a parameter are only offered to `visit_function`.
+    type Param = ();
+}
+
+/// C-specific visitor type alias.
+pub type CVisitor = dyn Visitor<Contributions = CContributions>;
+
+/// Renders `attributes` as a single `__attribute__((...))` specifier, or
+/// nothing if there are none.
+pub(crate) fn attribute_specifier(attributes: &[String]) -> String {
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!("__attribute__(({}))", attributes.join(", "))
+    }
+}
+
+/// The names of all built-in annotation visitors.
+pub const BUILTIN_ANNOTATIONS: &[&str] = &["deprecated", "assert"];
+
+/// Returns the built-in visitor for the annotation target `name`, if any.
+pub fn builtin_visitor(name: &str) -> Option<Box<CVisitor>> {
+    Some(match name {
+        "deprecated" => Box::new(DeprecatedVisitor),
+        "assert" => Box::new(AssertVisitor),
+        _ => return None,
+    })
+}
+
+/// `#deprecated` or `#deprecated("use x instead")` marks types, fields and
+/// functions as `deprecated` in the header.
+pub struct DeprecatedVisitor;
+
+impl DeprecatedVisitor {
+    fn attribute(annotation: &AnnotationArgs) -> String {
+        match annotation.positional().next() {
+            Some(msg @ AnnotationValue::String(_)) => format!("deprecated({msg})"),
+            _ => "deprecated".to_string(),
+        }
+    }
+
+    fn type_attribute(annotation: &AnnotationArgs) -> Option<CTypeContribution> {
+        let mut contrib = CTypeContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = CContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Field,
+            ItemKind::Case,
+            ItemKind::Function,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().optional_arg(ArgKind::String))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CFieldContribution> {
+        let mut contrib = CFieldContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CVariantCaseContribution> {
+        let mut contrib = CVariantCaseContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CFunctionContribution> {
+        let mut contrib = CFunctionContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+/// `#assert(n < 100)` checks a condition on the parameters of a function
+/// before the call, in import and export wrappers alike, and aborts if it
+/// doesn't hold. Unlike `assert` it's kept in `NDEBUG` builds.
+pub struct AssertVisitor;
+
+impl Visitor for AssertVisitor {
+    type Contributions = CContributions;
+
+    fn target(&self) -> &str {
+        "assert"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().arg(ArgKind::Any))
+    }
+
+    fn priority(&self) -> i32 {
+        priority::VALIDATION
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CFunctionContribution> {
+        let mut contrib = CFunctionContribution::new();
+        contrib.add_prologue(format!("if (!({})) abort();", annotation.trim()));
+        Some(contrib)
+    }
+}
@@ -1,5 +1,9 @@
+pub mod annotation_visitor;
 pub mod component_type_object;
 
+use annotation_visitor::{
+    attribute_specifier, CFunctionContribution, CModuleContribution, CTypeContribution, CVisitor,
+};
 use anyhow::Result;
 use heck::*;
 use indexmap::IndexSet;
@@ -9,9 +13,13 @@ use std::mem;
 use wit_bindgen_core::abi::{
     self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmSignature, WasmType,
 };
+use wit_bindgen_core::visitor::{
+    check_world_annotations, register_builtins, VisitAnnotations, VisitorList, VisitorMap,
+};
 use wit_bindgen_core::{
-    dealias, uwrite, uwriteln, wit_parser::*, AnonymousTypeGenerator, AsyncFilterSet, Direction,
-    Files, InterfaceGenerator as _, Ns, WorldGenerator,
+    dealias, uwrite, uwriteln, wit_parser::*, AnnotationArgs, AnonymousTypeGenerator,
+    AsyncFilterSet, Diagnostic, Diagnostics, Direction, Files, InterfaceGenerator as _, Location,
+    Ns, VisitContext, WorldGenerator,
 };
 use wit_component::StringEncoding;
 
@@ -40,6 +48,11 @@ struct C {
     type_names: HashMap<TypeId, String>,
     resources: HashMap<TypeId, ResourceInfo>,
     futures: IndexSet<TypeId>,
+
+    visitor_map: VisitorMap<CVisitor>,
+    diagnostics: Diagnostics,
+    h_pragmas: Vec<String>,
+    visited_interfaces: HashSet<InterfaceId>,
 }
 
 #[derive(Default)]
@@ -121,6 +134,20 @@ pub struct Opts {
 
     #[cfg_attr(feature = "clap", clap(flatten))]
     pub async_: AsyncFilterSet,
+
+    /// Fail generation on annotation problems, such as annotations without a
+    /// registered visitor, instead of reporting them as warnings.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub strict_annotations: bool,
+
+    /// Built-in annotation visitors to enable, out of `deprecated` and
+    /// `assert`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_annotation, value_delimiter = ','))]
+    pub annotations: Vec<String>,
+
+    /// Visitors for the annotations in the WIT, see [`VisitorList`].
+    #[cfg_attr(feature = "clap", clap(skip))]
+    pub visitors: VisitorList<CVisitor>,
 }
 
 #[cfg(feature = "clap")]
@@ -133,9 +160,29 @@ fn parse_rename(name: &str) -> Result<(String, String)> {
     }
 }
 
+#[cfg(feature = "clap")]
+fn parse_annotation(s: &str) -> Result<String, String> {
+    wit_bindgen_core::visitor::parse_builtin(
+        s,
+        annotation_visitor::BUILTIN_ANNOTATIONS,
+        annotation_visitor::builtin_visitor,
+    )
+}
+
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
         let mut r = C::default();
+        for visitor in self.visitors.create() {
+            r.visitor_map.register(visitor);
+        }
+        r.diagnostics = Diagnostics::new(self.strict_annotations);
+        register_builtins(
+            &mut r.visitor_map,
+            &mut r.diagnostics,
+            &self.annotations,
+            annotation_visitor::BUILTIN_ANNOTATIONS,
+            annotation_visitor::builtin_visitor,
+        );
         r.opts = self.clone();
         Box::new(r)
     }
@@ -164,7 +211,19 @@ enum Scalar {
     Type(Type),
 }
 
+impl VisitAnnotations for C {
+    type Visitor = CVisitor;
+
+    fn visitors_and_diagnostics(&mut self) -> (&mut VisitorMap<CVisitor>, &mut Diagnostics) {
+        (&mut self.visitor_map, &mut self.diagnostics)
+    }
+}
+
 impl WorldGenerator for C {
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        check_world_annotations(&self.visitor_map, &mut self.diagnostics, resolve, world)
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         self.world = self
             .opts
@@ -200,6 +259,7 @@ impl WorldGenerator for C {
         id: InterfaceId,
         _files: &mut Files,
     ) -> Result<()> {
+        self.visit_interface(resolve, id, Direction::Import);
         let wasm_import_module = resolve.name_world_key(name);
         let mut r#gen = self.interface(resolve, true, Some(&wasm_import_module));
         r#gen.interface = Some((id, name));
@@ -248,6 +308,7 @@ impl WorldGenerator for C {
         id: InterfaceId,
         _files: &mut Files,
     ) -> Result<()> {
+        self.visit_interface(resolve, id, Direction::Export);
         let mut r#gen = self.interface(resolve, false, None);
         r#gen.interface = Some((id, name));
         r#gen.define_interface_types(id);
@@ -305,6 +366,19 @@ impl WorldGenerator for C {
     }
 
     fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        let world = &resolve.worlds[id];
+        let location = Location::for_world(resolve, id);
+        let cx = VisitContext::new(resolve, id);
+        for contrib in self.visit_annotations(
+            &location,
+            world.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_world(args, world, &cx),
+        ) {
+            self.add_module_contribution(contrib);
+        }
+        self.diagnostics.bail_on_errors()?;
+
         let linking_symbol = component_type_object::linking_symbol(&self.world);
         self.c_include("<stdlib.h>");
         let snake = self.world.to_snake_case();
@@ -448,6 +522,9 @@ impl WorldGenerator for C {
         for include in self.h_includes.iter() {
             uwriteln!(h_str, "#include {include}");
         }
+        for pragma in self.h_pragmas.iter() {
+            uwriteln!(h_str, "#pragma {pragma}");
+        }
 
         let mut c_str = wit_bindgen_core::Source::default();
         wit_bindgen_core::generated_preamble(&mut c_str, version);
@@ -554,6 +631,10 @@ impl WorldGenerator for C {
         self.remove_types_redefined_by_exports(resolve, self.world_id.unwrap());
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
 
 impl C {
@@ -577,6 +658,39 @@ impl C {
         self.h_includes.push(s.to_string());
     }
 
+    /// Visits the annotations of the interface `id`, in the `direction` it's
+    /// first generated in: their contributions apply to the whole header, so
+    /// an interface that is both imported and exported is visited once.
+    fn visit_interface(&mut self, resolve: &Resolve, id: InterfaceId, direction: Direction) {
+        if !self.visited_interfaces.insert(id) {
+            return;
+        }
+        let interface = &resolve.interfaces[id];
+        let location = Location::for_interface(resolve, id);
+        let cx = VisitContext {
+            interface: Some(id),
+            direction: Some(direction),
+            ..VisitContext::new(resolve, self.world_id.unwrap())
+        };
+        for contrib in self.visit_annotations(
+            &location,
+            interface.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_interface(args, Some(interface), &cx),
+        ) {
+            self.add_module_contribution(contrib);
+        }
+    }
+
+    fn add_module_contribution(&mut self, contrib: CModuleContribution) {
+        for include in contrib.includes {
+            if !self.h_includes.contains(&include) {
+                self.h_includes.push(include);
+            }
+        }
+        self.h_pragmas.extend(contrib.pragmas);
+    }
+
     fn c_include(&mut self, s: &str) {
         self.c_includes.push(s.to_string());
     }
@@ -1258,17 +1372,32 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
     }
 
     fn type_record(&mut self, id: TypeId, _name: &str, record: &Record, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_record(args, record, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
-        for field in record.fields.iter() {
+        for (i, field) in record.fields.iter().enumerate() {
+            let location = Location::for_type(self.resolve, id).member(&field.name);
+            let cx = self.visit_context(Some(to_c_ident(&field.name)));
+            let mut attributes = Vec::new();
+            for contrib in self.r#gen.visit_annotations(
+                &location,
+                field.annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_field(args, field, i, &cx),
+            ) {
+                attributes.extend(contrib.attributes);
+            }
             self.docs(&field.docs, SourceType::HDefs);
             self.print_ty(SourceType::HDefs, &field.ty);
             self.src.h_defs(" ");
             self.src.h_defs(&to_c_ident(&field.name));
+            self.print_member_attributes(&attributes);
             self.src.h_defs(";\n");
         }
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
     }
 
     fn type_resource(&mut self, id: TypeId, name: &str, _docs: &Docs) {
@@ -1438,6 +1567,9 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
     }
 
     fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_tuple(args, tuple, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
@@ -1445,17 +1577,20 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
             self.print_ty(SourceType::HDefs, ty);
             uwriteln!(self.src.h_defs, " f{i};");
         }
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
     }
 
     fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_flags(args, flags, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.src.h_defs("typedef ");
         let repr = flags_repr(flags);
         self.src.h_defs(int_repr(repr));
         self.src.h_defs(" ");
-        self.print_typedef_target(id);
+        self.print_typedef_target_with_attributes(id, &contribution.attributes);
 
         if flags.flags.len() > 0 {
             self.src.h_defs("\n");
@@ -1473,6 +1608,9 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
     }
 
     fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_variant(args, variant, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
@@ -1483,20 +1621,33 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
             variant
                 .cases
                 .iter()
-                .filter_map(|case| case.ty.as_ref().map(|ty| (&case.name, ty))),
+                .enumerate()
+                .filter_map(|(i, case)| case.ty.as_ref().map(|ty| (i, case, ty))),
         );
 
         if !cases_with_data.is_empty() {
             self.src.h_defs("union {\n");
-            for (name, ty) in cases_with_data {
+            for (i, case, ty) in cases_with_data {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(to_c_ident(&case.name)));
+                let mut attributes = Vec::new();
+                for contrib in self.r#gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 self.print_ty(SourceType::HDefs, ty);
                 self.src.h_defs(" ");
-                self.src.h_defs(&to_c_ident(name));
+                self.src.h_defs(&to_c_ident(&case.name));
+                self.print_member_attributes(&attributes);
                 self.src.h_defs(";\n");
             }
             self.src.h_defs("} val;\n");
         }
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
 
         if variant.cases.len() > 0 {
             self.src.h_defs("\n");
@@ -1514,16 +1665,22 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
     }
 
     fn type_option(&mut self, id: TypeId, _name: &str, payload: &Type, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_option(args, payload, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
         self.src.h_defs("bool is_some;\n");
         self.print_ty(SourceType::HDefs, payload);
         self.src.h_defs(" val;\n");
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
     }
 
     fn type_result(&mut self, id: TypeId, _name: &str, result: &Result_, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_result(args, result, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
@@ -1540,15 +1697,18 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
             }
             self.src.h_defs("} val;\n");
         }
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
     }
 
     fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_enum(args, enum_, id, cx)
+        });
         uwrite!(self.src.h_defs, "\n");
         self.docs(docs, SourceType::HDefs);
         let int_t = int_repr(enum_.tag());
         uwrite!(self.src.h_defs, "typedef {int_t} ");
-        self.print_typedef_target(id);
+        self.print_typedef_target_with_attributes(id, &contribution.attributes);
 
         if enum_.cases.len() > 0 {
             self.src.h_defs("\n");
@@ -1574,22 +1734,27 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
             return;
         }
 
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_alias(args, ty, id, cx)
+        });
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.src.h_defs("typedef ");
         self.print_ty(SourceType::HDefs, ty);
         self.src.h_defs(" ");
-        self.print_typedef_target(id);
+        self.print_typedef_target_with_attributes(id, &contribution.attributes);
     }
 
     fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        let contribution =
+            self.type_contribution(id, |visitor, args, cx| visitor.visit_list(args, ty, id, cx));
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
         self.start_typedef_struct(id);
         self.print_ty(SourceType::HDefs, ty);
         self.src.h_defs(" *ptr;\n");
         self.src.h_defs("size_t len;\n");
-        self.finish_typedef_struct(id);
+        self.finish_typedef_struct_with_attributes(id, &contribution.attributes);
     }
 
     fn type_future(&mut self, id: TypeId, _name: &str, _ty: &Option<Type>, docs: &Docs) {
@@ -1986,6 +2151,14 @@ impl InterfaceGenerator<'_> {
 
         // Print the public facing signature into the header, and since that's
         // what we are defining also print it into the C file.
+        let contribution = self.function_contribution(func);
+        if !contribution.attributes.is_empty() {
+            uwriteln!(
+                self.src.h_fns,
+                "{}",
+                attribute_specifier(&contribution.attributes)
+            );
+        }
         self.src.h_fns("extern ");
         let c_sig = self.print_sig(interface_name, func, &sig, async_);
         self.src.c_adapters("\n");
@@ -1993,9 +2166,9 @@ impl InterfaceGenerator<'_> {
         self.src.c_adapters(" {\n");
 
         if async_ {
-            self.import_body_async(func, c_sig, &sig, &import_name);
+            self.import_body_async(func, c_sig, &sig, &import_name, contribution);
         } else {
-            self.import_body_sync(func, c_sig, &import_name);
+            self.import_body_sync(func, c_sig, &import_name, contribution);
         }
 
         self.src.c_adapters("}\n");
@@ -2003,7 +2176,13 @@ impl InterfaceGenerator<'_> {
         self.generate_async_futures_and_streams("", func, interface_name);
     }
 
-    fn import_body_sync(&mut self, func: &Function, c_sig: CSig, import_name: &str) {
+    fn import_body_sync(
+        &mut self,
+        func: &Function,
+        c_sig: CSig,
+        import_name: &str,
+        contribution: CFunctionContribution,
+    ) {
         // construct optional adapters from maybe pointers to real optional
         // structs internally
         let mut optional_adapters = String::from("");
@@ -2041,6 +2220,10 @@ impl InterfaceGenerator<'_> {
             f.locals.insert(ptr).unwrap();
         }
         f.src.push_str(&optional_adapters);
+        for code in contribution.prologue.iter() {
+            uwriteln!(f.src, "{code}");
+        }
+        f.epilogue = contribution.epilogue;
         abi::call(
             f.r#gen.resolve,
             AbiVariant::GuestImport,
@@ -2077,7 +2260,13 @@ impl InterfaceGenerator<'_> {
         c_sig: CSig,
         wasm_sig: &WasmSignature,
         import_name: &str,
+        contribution: CFunctionContribution,
     ) {
+        // Async imports return as soon as the subtask is started, so only the
+        // prologue applies to them.
+        for code in contribution.prologue.iter() {
+            uwriteln!(self.src.c_adapters, "{code}");
+        }
         let mut params = Vec::new();
         if wasm_sig.indirect_params {
             params.push(format!("(uint8_t*) {}", c_sig.params[0].1));
@@ -2122,6 +2311,14 @@ impl InterfaceGenerator<'_> {
 
         // Print the actual header for this function into the header file, and
         // it's what we'll be calling.
+        let contribution = self.function_contribution(func);
+        if !contribution.attributes.is_empty() {
+            uwriteln!(
+                self.src.h_fns,
+                "{}",
+                attribute_specifier(&contribution.attributes)
+            );
+        }
         let h_sig = self.print_sig(interface_name, func, &sig, async_);
 
         // Generate, in the C source file, the raw wasm signature that has the
@@ -2134,6 +2331,10 @@ impl InterfaceGenerator<'_> {
         let import_name = self.r#gen.names.tmp(&format!("__wasm_export_{name}"));
 
         let mut f = FunctionBindgen::new(self, h_sig, &import_name);
+        f.prologue = contribution.prologue;
+        if !async_ {
+            f.epilogue = contribution.epilogue;
+        }
         match sig.results.len() {
             0 => f.r#gen.src.c_adapters("void"),
             1 => f.r#gen.src.c_adapters(wasm_type(sig.results[0])),
@@ -2472,11 +2673,25 @@ void {name}_return({return_ty}) {{
     }
 
     fn print_typedef_target(&mut self, id: TypeId) {
+        self.print_typedef_target_with_attributes(id, &[]);
+    }
+
+    fn print_typedef_target_with_attributes(&mut self, id: TypeId, attributes: &[String]) {
         let name = &self.r#gen.type_names[&id];
         self.src.h_defs(&name);
+        self.print_member_attributes(attributes);
         self.src.h_defs(";\n");
     }
 
+    /// Prints the `__attribute__((...))` for a declarator, e.g. a field or
+    /// a typedef name, with a leading space.
+    fn print_member_attributes(&mut self, attributes: &[String]) {
+        if !attributes.is_empty() {
+            self.src.h_defs(" ");
+            self.src.h_defs(&attribute_specifier(attributes));
+        }
+    }
+
     fn start_typedef_struct(&mut self, id: TypeId) {
         let name = &self.r#gen.type_names[&id];
         self.src.h_defs("typedef struct ");
@@ -2485,10 +2700,88 @@ void {name}_return({return_ty}) {{
     }
 
     fn finish_typedef_struct(&mut self, id: TypeId) {
+        self.finish_typedef_struct_with_attributes(id, &[]);
+    }
+
+    /// Closes a struct, placing `attributes` after the closing brace so they
+    /// apply to the struct type itself, e.g. `packed`.
+    fn finish_typedef_struct_with_attributes(&mut self, id: TypeId, attributes: &[String]) {
         self.src.h_defs("} ");
+        if !attributes.is_empty() {
+            self.src.h_defs(&attribute_specifier(attributes));
+            self.src.h_defs(" ");
+        }
         self.print_typedef_target(id);
     }
 
+    /// The context visitors get for an item defined by this generator, called
+    /// `name` in the generated code.
+    fn visit_context(&self, name: Option<String>) -> VisitContext<'a> {
+        VisitContext {
+            interface: self.interface.map(|(id, _)| id),
+            direction: Some(if self.in_import {
+                Direction::Import
+            } else {
+                Direction::Export
+            }),
+            name,
+            ..VisitContext::new(self.resolve, self.r#gen.world_id.unwrap())
+        }
+    }
+
+    /// Collects the contributions of the visitors for the annotations on the
+    /// type `id`.
+    fn type_contribution(
+        &mut self,
+        id: TypeId,
+        mut visit: impl FnMut(
+            &mut CVisitor,
+            &AnnotationArgs,
+            &VisitContext<'_>,
+        ) -> Option<CTypeContribution>,
+    ) -> CTypeContribution {
+        let location = Location::for_type(self.resolve, id);
+        let cx = self.visit_context(self.r#gen.type_names.get(&id).cloned());
+        let mut contribution = CTypeContribution::new();
+        for contrib in self.r#gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visit(visitor, args, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+        }
+        contribution
+    }
+
+    /// Collects the contributions of the visitors for the annotations on
+    /// `func`.
+    fn function_contribution(&mut self, func: &Function) -> CFunctionContribution {
+        let location = self.function_location(func);
+        let name = self.c_func_name(self.interface.map(|(_, key)| key), func);
+        let cx = self.visit_context(Some(name));
+        let mut contribution = CFunctionContribution::new();
+        for contrib in self.r#gen.visit_annotations(
+            &location,
+            func.annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_function(args, func, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+            contribution.prologue.extend(contrib.prologue);
+            contribution.epilogue.extend(contrib.epilogue);
+        }
+        contribution
+    }
+
+    fn function_location(&self, func: &Function) -> Location {
+        match self.interface {
+            Some((id, _)) => Location::for_interface(self.resolve, id),
+            None => Location::for_world(self.resolve, self.r#gen.world_id.unwrap()),
+        }
+        .member(&func.name)
+    }
+
     fn owner_namespace(&self, id: TypeId) -> String {
         owner_namespace(
             self.interface,
@@ -2786,6 +3079,14 @@ struct FunctionBindgen<'a, 'b> {
 
     /// Forward declarations for temporary storage of borrow copies.
     borrow_decls: wit_bindgen_core::Source,
+
+    /// Statements contributed by annotation visitors to run just before an
+    /// export's implementation is called.
+    prologue: Vec<String>,
+
+    /// Statements contributed by annotation visitors to run just before the
+    /// function returns.
+    epilogue: Vec<String>,
 }
 
 /// State associated with the generation of the `task.return` intrinsic function
@@ -2861,6 +3162,8 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             borrow_decls: Default::default(),
             borrows: Vec::new(),
             deferred_task_return: DeferredTaskReturn::None,
+            prologue: Vec::new(),
+            epilogue: Vec::new(),
         }
     }
 
@@ -3485,6 +3788,32 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
 
             Instruction::CallInterface { func, async_ } => {
+                // Bind the lifted arguments to their WIT names so the
+                // prologue can refer to them. The names of the signature's
+                // parameters are reserved up front, any other name may
+                // already be taken by a local of the lifting code.
+                if !self.prologue.is_empty() {
+                    for (i, op) in operands.iter_mut().enumerate() {
+                        let (param, ty) = &func.params[i];
+                        let name = to_c_ident(param);
+                        let reserved = self.sig.params.iter().any(|(_, p)| *p == name);
+                        if !reserved {
+                            if let Err(e) = self.locals.insert(&name) {
+                                let location = self.r#gen.function_location(func).member(param);
+                                self.r#gen.r#gen.diagnostics.error(
+                                    &location,
+                                    format!("can't bind parameter for the prologue: {e}"),
+                                );
+                            }
+                        }
+                        let ty = self.r#gen.r#gen.type_name(ty);
+                        uwriteln!(self.src, "{ty} {name} = {op};");
+                        *op = name;
+                    }
+                    for code in mem::take(&mut self.prologue) {
+                        uwriteln!(self.src, "{code}");
+                    }
+                }
                 let mut args = String::new();
                 for (i, (op, (byref, _))) in operands.iter().zip(&self.sig.params).enumerate() {
                     if i > 0 {
@@ -3645,61 +3974,66 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     }
                 }
             }
-            Instruction::Return { .. } if self.r#gen.in_import => match self.sig.ret.scalar {
-                None => {
-                    for op in operands.iter() {
-                        self.store_in_retptr(op);
-                    }
-                }
-                Some(Scalar::Void) => {
-                    assert!(operands.is_empty());
-                }
-                Some(Scalar::Type(_)) => {
-                    assert_eq!(operands.len(), 1);
-                    self.src.push_str("return ");
-                    self.src.push_str(&operands[0]);
-                    self.src.push_str(";\n");
+            Instruction::Return { .. } if self.r#gen.in_import => {
+                for code in mem::take(&mut self.epilogue) {
+                    uwriteln!(self.src, "{code}");
                 }
-                Some(Scalar::OptionBool(_)) => {
-                    assert_eq!(operands.len(), 1);
-                    let variant = &operands[0];
-                    self.store_in_retptr(&format!("{}.val", variant));
-                    self.src.push_str("return ");
-                    self.src.push_str(&variant);
-                    self.src.push_str(".is_some;\n");
-                }
-                Some(Scalar::ResultBool(ok, err)) => {
-                    assert_eq!(operands.len(), 1);
-                    let variant = &operands[0];
-                    assert!(self.sig.retptrs.len() <= 2);
-                    uwriteln!(self.src, "if (!{}.is_err) {{", variant);
-                    if ok.is_some() {
-                        if ok.is_some() {
-                            self.store_in_retptr(&format!("{}.val.ok", variant));
-                        } else {
-                            self.empty_return_value();
+                match self.sig.ret.scalar {
+                    None => {
+                        for op in operands.iter() {
+                            self.store_in_retptr(op);
                         }
                     }
-                    uwriteln!(
-                        self.src,
-                        "   return 1;
+                    Some(Scalar::Void) => {
+                        assert!(operands.is_empty());
+                    }
+                    Some(Scalar::Type(_)) => {
+                        assert_eq!(operands.len(), 1);
+                        self.src.push_str("return ");
+                        self.src.push_str(&operands[0]);
+                        self.src.push_str(";\n");
+                    }
+                    Some(Scalar::OptionBool(_)) => {
+                        assert_eq!(operands.len(), 1);
+                        let variant = &operands[0];
+                        self.store_in_retptr(&format!("{}.val", variant));
+                        self.src.push_str("return ");
+                        self.src.push_str(&variant);
+                        self.src.push_str(".is_some;\n");
+                    }
+                    Some(Scalar::ResultBool(ok, err)) => {
+                        assert_eq!(operands.len(), 1);
+                        let variant = &operands[0];
+                        assert!(self.sig.retptrs.len() <= 2);
+                        uwriteln!(self.src, "if (!{}.is_err) {{", variant);
+                        if ok.is_some() {
+                            if ok.is_some() {
+                                self.store_in_retptr(&format!("{}.val.ok", variant));
+                            } else {
+                                self.empty_return_value();
+                            }
+                        }
+                        uwriteln!(
+                            self.src,
+                            "   return 1;
                             }} else {{"
-                    );
-                    if err.is_some() {
+                        );
                         if err.is_some() {
-                            self.store_in_retptr(&format!("{}.val.err", variant));
-                        } else {
-                            self.empty_return_value();
+                            if err.is_some() {
+                                self.store_in_retptr(&format!("{}.val.err", variant));
+                            } else {
+                                self.empty_return_value();
+                            }
                         }
-                    }
-                    uwriteln!(
-                        self.src,
-                        "   return 0;
+                        uwriteln!(
+                            self.src,
+                            "   return 0;
                             }}"
-                    );
-                    assert_eq!(self.ret_store_cnt, self.sig.retptrs.len());
+                        );
+                        assert_eq!(self.ret_store_cnt, self.sig.retptrs.len());
+                    }
                 }
-            },
+            }
             Instruction::Return { amt, .. } => {
                 // Emit all temporary borrow decls
                 let src = std::mem::replace(&mut self.src, std::mem::take(&mut self.borrow_decls));
@@ -3711,6 +4045,9 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     uwriteln!(self.src, "  {drop_fn}({name});");
                     uwriteln!(self.src, "}}");
                 }
+                for code in mem::take(&mut self.epilogue) {
+                    uwriteln!(self.src, "{code}");
+                }
 
                 assert!(*amt <= 1);
                 if *amt == 1 {
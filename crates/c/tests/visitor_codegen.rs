@@ -0,0 +1,245 @@
+// Runs the C generator with visitors registered and checks that their
+// contributions show up in the generated header and source.
+
+use wit_bindgen_c::annotation_visitor::*;
+use wit_bindgen_c::Opts;
+use wit_bindgen_core::visitor::VisitorList;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, Files, VisitContext, Visitor};
+
+/// Generates bindings for the only world in `wit` and returns the header and
+/// the C source with all whitespace runs collapsed to a single space.
+fn generate(
+    wit: &str,
+    visitors: impl Fn() -> Vec<Box<CVisitor>> + Send + Sync + 'static,
+) -> (String, String) {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        visitors: VisitorList::new(visitors),
+        ..Default::default()
+    };
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let find = |ext: &str| {
+        let (_, src) = files.iter().find(|(name, _)| name.ends_with(ext)).unwrap();
+        std::str::from_utf8(src)
+            .unwrap()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    (find(".h"), find(".c"))
+}
+
+/// Checks the annotation's expression with `assert` from `<assert.h>`,
+/// unlike the built-in `AssertVisitor`, to test includes.
+struct StdAssertVisitor;
+
+impl Visitor for StdAssertVisitor {
+    type Contributions = CContributions;
+
+    fn target(&self) -> &str {
+        "assert"
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CFunctionContribution> {
+        let mut contrib = CFunctionContribution::new();
+        contrib.add_prologue(format!("assert({});", annotation.as_str()));
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CModuleContribution> {
+        let mut contrib = CModuleContribution::new();
+        contrib.add_include("<assert.h>");
+        Some(contrib)
+    }
+}
+
+/// Adds `#pragma`s to the header and a statement before every return.
+struct PackVisitor;
+
+impl Visitor for PackVisitor {
+    type Contributions = CContributions;
+
+    fn target(&self) -> &str {
+        "pack"
+    }
+
+    fn visit_world(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CModuleContribution> {
+        let mut contrib = CModuleContribution::new();
+        contrib.add_pragma(format!("pack({})", annotation.as_str()));
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CModuleContribution> {
+        let mut contrib = CModuleContribution::new();
+        contrib.add_pragma(format!("pack({})", annotation.as_str()));
+        Some(contrib)
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CFunctionContribution> {
+        let mut contrib = CFunctionContribution::new();
+        contrib.add_epilogue(format!("trace_return(\"{}\");", func.name));
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_type_and_function_attributes() {
+    let (h, _) = generate(
+        r#"
+            package test:deprecated;
+
+            interface api {
+                #deprecated("use point3")
+                record point {
+                    x: u32,
+                    #deprecated
+                    y: u32,
+                }
+
+                #deprecated("use distance3")
+                distance: func(a: point, b: point) -> u32;
+            }
+
+            world test {
+                import api;
+            }
+        "#,
+        || vec![Box::new(DeprecatedVisitor)],
+    );
+
+    assert!(
+        h.contains("uint32_t y __attribute__((deprecated));"),
+        "field attribute missing:\n{h}"
+    );
+    assert!(
+        h.contains("} __attribute__((deprecated(\"use point3\"))) test_deprecated_api_point_t;"),
+        "type attribute missing:\n{h}"
+    );
+    assert!(
+        h.contains("__attribute__((deprecated(\"use distance3\"))) extern uint32_t"),
+        "function attribute missing:\n{h}"
+    );
+}
+
+#[test]
+fn test_prologue_and_includes() {
+    let (h, c) = generate(
+        r#"
+            package test:assert;
+
+            #assert
+            interface math {
+                #assert(divisor != 0)
+                divide: func(dividend: u32, divisor: u32) -> u32;
+            }
+
+            world test {
+                import math;
+                export math;
+            }
+        "#,
+        || vec![Box::new(StdAssertVisitor)],
+    );
+
+    assert!(h.contains("#include <assert.h>"), "include missing:\n{h}");
+    assert_eq!(
+        h.matches("#include <assert.h>").count(),
+        1,
+        "include should be deduplicated:\n{h}"
+    );
+    // Once in the import wrapper and once before calling the export.
+    assert_eq!(c.matches("assert(divisor != 0);").count(), 2, "{c}");
+    let binding = c.find("uint32_t divisor = (uint32_t) (").expect(&c);
+    assert!(
+        c[binding..].starts_with("uint32_t divisor = (uint32_t) (arg0); assert(divisor != 0);"),
+        "export arguments should be bound before the prologue:\n{c}"
+    );
+}
+
+#[test]
+fn test_prologue_bindings_avoid_locals() {
+    // `option` is also the name of the local an option is lifted into, and
+    // `ret`, which the bindings use for results, gets escaped.
+    let (_, c) = generate(
+        r#"
+            package test:assert;
+
+            interface api {
+                #assert(ret_ != 0)
+                check: func(option: option<u32>, ret: u32);
+            }
+
+            world test {
+                export api;
+            }
+        "#,
+        || vec![Box::new(StdAssertVisitor)],
+    );
+
+    assert!(c.contains(" option = option"), "{c}");
+    assert!(c.contains("uint32_t ret_ = (uint32_t) ("), "{c}");
+    assert_eq!(c.matches(" option;").count(), 0, "{c}");
+    assert!(c.contains("assert(ret_ != 0);"), "{c}");
+}
+
+#[test]
+fn test_pragmas_and_epilogue() {
+    let (h, c) = generate(
+        r#"
+            package test:pack;
+
+            #pack(4)
+            interface api {
+                #pack
+                ping: func() -> u32;
+            }
+
+            #pack(push, 1)
+            world test {
+                import api;
+                export api;
+            }
+        "#,
+        || vec![Box::new(PackVisitor)],
+    );
+
+    assert!(h.contains("#pragma pack(push, 1)"), "pragma missing:\n{h}");
+    // The interface is both imported and exported, but its annotations
+    // contribute to the header once.
+    assert_eq!(h.matches("#pragma pack(4)").count(), 1, "{h}");
+    let epilogue = c.find("trace_return(\"ping\");").expect(&c);
+    let ret = c[epilogue..].find("return ").expect(&c);
+    assert!(
+        !c[epilogue..epilogue + ret].contains('}'),
+        "epilogue should run just before the return:\n{c}"
+    );
+}
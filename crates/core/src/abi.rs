@@ -678,6 +678,27 @@ pub trait Bindgen {
         results: &mut Vec<Self::Operand>,
     );
 
+    /// Converts `operand`, a value of the named alias `id`, into a value of
+    /// the aliased type before it is lowered.
+    ///
+    /// Most languages render aliases as plain type aliases, so by default the
+    /// operand is returned unchanged. Generators that render an alias as a
+    /// distinct wrapper type can unwrap it here.
+    #[allow(unused)]
+    fn lower_alias(&mut self, resolve: &Resolve, id: TypeId, operand: Self::Operand) -> Self::Operand {
+        operand
+    }
+
+    /// Converts `operand`, a freshly lifted value of the aliased type, into a
+    /// value of the named alias `id`.
+    ///
+    /// This is the inverse of [`Bindgen::lower_alias`] and by default returns
+    /// the operand unchanged.
+    #[allow(unused)]
+    fn lift_alias(&mut self, resolve: &Resolve, id: TypeId, operand: Self::Operand) -> Self::Operand {
+        operand
+    }
+
     /// Gets a operand reference to the return pointer area.
     ///
     /// The provided size and alignment is for the function's return type.
@@ -1404,6 +1425,21 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         self.bindgen.finish_block(&mut self.operands);
     }
 
+    /// Replaces the alias value on top of the stack with the value it wraps.
+    fn lower_alias(&mut self, id: TypeId) {
+        let operand = self.stack.pop().unwrap();
+        let operand = self.bindgen.lower_alias(self.resolve, id, operand);
+        self.stack.push(operand);
+    }
+
+    /// Replaces the lifted value on top of the stack with a value of the
+    /// alias `id`.
+    fn lift_alias(&mut self, id: TypeId) {
+        let operand = self.stack.pop().unwrap();
+        let operand = self.bindgen.lift_alias(self.resolve, id, operand);
+        self.stack.push(operand);
+    }
+
     fn lower(&mut self, ty: &Type) {
         use Instruction::*;
 
@@ -1426,7 +1462,10 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             }
             Type::ErrorContext => self.emit(&ErrorContextLower),
             Type::Id(id) => match &self.resolve.types[id].kind {
-                TypeDefKind::Type(t) => self.lower(t),
+                TypeDefKind::Type(t) => {
+                    self.lower_alias(id);
+                    self.lower(t);
+                }
                 TypeDefKind::List(element) => {
                     let realloc = self.list_realloc();
                     if self.bindgen.is_list_canonical(self.resolve, element) {
@@ -1618,7 +1657,10 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             Type::String => self.emit(&StringLift),
             Type::ErrorContext => self.emit(&ErrorContextLift),
             Type::Id(id) => match &self.resolve.types[id].kind {
-                TypeDefKind::Type(t) => self.lift(t),
+                TypeDefKind::Type(t) => {
+                    self.lift(t);
+                    self.lift_alias(id);
+                }
                 TypeDefKind::List(element) => {
                     if self.bindgen.is_list_canonical(self.resolve, element) {
                         self.emit(&ListCanonLift { element, ty: id });
@@ -1802,7 +1844,10 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             Type::ErrorContext => self.lower_and_emit(ty, addr, &I32Store { offset }),
 
             Type::Id(id) => match &self.resolve.types[id].kind {
-                TypeDefKind::Type(t) => self.write_to_memory(t, addr, offset),
+                TypeDefKind::Type(t) => {
+                    self.lower_alias(id);
+                    self.write_to_memory(t, addr, offset);
+                }
                 TypeDefKind::List(_) => self.write_list_to_memory(ty, addr, offset),
 
                 TypeDefKind::Future(_) | TypeDefKind::Stream(_) | TypeDefKind::Handle(_) => {
@@ -1994,7 +2039,10 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             Type::ErrorContext => self.emit_and_lift(ty, addr, &I32Load { offset }),
 
             Type::Id(id) => match &self.resolve.types[id].kind {
-                TypeDefKind::Type(t) => self.read_from_memory(t, addr, offset),
+                TypeDefKind::Type(t) => {
+                    self.read_from_memory(t, addr, offset);
+                    self.lift_alias(id);
+                }
 
                 TypeDefKind::List(_) => self.read_list_from_memory(ty, addr, offset),
 
@@ -0,0 +1,613 @@
+//! Diagnostics reported while generating bindings.
+//!
+//! Generators collect [`Diagnostic`]s into a [`Diagnostics`] sink instead of
+//! printing them, and frontends such as the CLI or the `generate!` macro
+//! decide how to surface them.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use wit_parser::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// Where in the WIT sources a diagnostic points to.
+///
+/// WIT items don't carry source spans, so items are identified by their
+/// package and their path within it, e.g. `iface.my-record.field`. The file,
+/// line and column are filled in by [`Diagnostics::locate_in_files`] when the
+/// sources are known.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// The package of the item, e.g. `test:pkg@1.0.0`.
+    pub package: Option<String>,
+    /// The path of the item within its package.
+    pub item: String,
+    pub file: Option<PathBuf>,
+    /// 1-based line number within `file`.
+    pub line: Option<usize>,
+    /// 1-based column within `line`, for diagnostics pointing into the body
+    /// of an annotation.
+    pub column: Option<usize>,
+    /// Byte offset within the body of the annotation the diagnostic is about,
+    /// e.g. where its arguments failed to parse.
+    pub offset: Option<usize>,
+}
+
+impl Location {
+    pub fn for_interface(resolve: &Resolve, id: InterfaceId) -> Location {
+        let interface = &resolve.interfaces[id];
+        Location {
+            package: interface
+                .package
+                .map(|pkg| resolve.packages[pkg].name.to_string()),
+            item: interface
+                .name
+                .clone()
+                .unwrap_or_else(|| "<anonymous interface>".to_string()),
+            ..Location::default()
+        }
+    }
+
+    pub fn for_world(resolve: &Resolve, id: WorldId) -> Location {
+        let world = &resolve.worlds[id];
+        Location {
+            package: world
+                .package
+                .map(|pkg| resolve.packages[pkg].name.to_string()),
+            item: world.name.clone(),
+            ..Location::default()
+        }
+    }
+
+    pub fn for_type(resolve: &Resolve, id: TypeId) -> Location {
+        let ty = &resolve.types[id];
+        let name = ty.name.as_deref().unwrap_or("<anonymous type>");
+        match ty.owner {
+            TypeOwner::Interface(owner) => Location::for_interface(resolve, owner).member(name),
+            TypeOwner::World(owner) => Location::for_world(resolve, owner).member(name),
+            TypeOwner::None => Location {
+                item: name.to_string(),
+                ..Location::default()
+            },
+        }
+    }
+
+    /// The location of a whole file rather than an item within it.
+    pub fn for_file(path: &Path) -> Location {
+        Location {
+            file: Some(path.to_path_buf()),
+            ..Location::default()
+        }
+    }
+
+    /// The location of the item `name` nested within this one, e.g. a field
+    /// of a record or a function of an interface.
+    pub fn member(&self, name: &str) -> Location {
+        Location {
+            package: self.package.clone(),
+            item: format!("{}.{name}", self.item),
+            file: self.file.clone(),
+            ..Location::default()
+        }
+    }
+
+    /// This location, pointing `offset` bytes into the annotation body.
+    pub fn at_offset(&self, offset: usize) -> Location {
+        Location {
+            offset: Some(offset),
+            ..self.clone()
+        }
+    }
+
+    /// The unqualified name of the item.
+    fn name(&self) -> &str {
+        self.item.rsplit('.').next().unwrap_or(&self.item)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}", file.display())?;
+            if let Some(line) = self.line {
+                write!(f, ":{line}")?;
+                if let Some(column) = self.column {
+                    write!(f, ":{column}")?;
+                }
+            }
+            // Diagnostics about a whole file, e.g. a malformed config, don't
+            // point at an item.
+            if self.item.is_empty() {
+                return Ok(());
+            }
+            f.write_str(": ")?;
+        }
+        match &self.package {
+            Some(package) => write!(f, "{package}/{}", self.item),
+            None => f.write_str(&self.item),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub location: Location,
+    /// The annotation target this diagnostic is about, if any.
+    pub target: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Problems with the generator's options aren't about any location.
+        if self.location == Location::default() {
+            return write!(f, "{}: {}", self.severity, self.message);
+        }
+        write!(f, "{}: {}: {}", self.severity, self.location, self.message)
+    }
+}
+
+/// A sink for diagnostics collected during generation.
+///
+/// In strict mode problems with annotations, such as unknown targets, are
+/// reported as errors instead of warnings.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    strict: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new(strict: bool) -> Diagnostics {
+        Diagnostics {
+            strict,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn warning(&mut self, location: &Location, message: impl Into<String>) {
+        self.report(Severity::Warning, location, None, message.into());
+    }
+
+    pub fn error(&mut self, location: &Location, message: impl Into<String>) {
+        self.report(Severity::Error, location, None, message.into());
+    }
+
+    /// Reports a problem with the `#target` annotation at `location`: a
+    /// warning normally and an error in strict mode.
+    pub fn annotation(&mut self, location: &Location, target: &str, message: impl Into<String>) {
+        let severity = if self.strict {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        self.report(severity, location, Some(target), message.into());
+    }
+
+    /// Reports an error with the `#target` annotation at `location`, even
+    /// outside of strict mode, for annotations that can't be generated at
+    /// all.
+    pub fn annotation_error(
+        &mut self,
+        location: &Location,
+        target: &str,
+        message: impl Into<String>,
+    ) {
+        self.report(Severity::Error, location, Some(target), message.into());
+    }
+
+    fn report(
+        &mut self,
+        severity: Severity,
+        location: &Location,
+        target: Option<&str>,
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            location: location.clone(),
+            target: target.map(|t| t.to_string()),
+            message,
+        });
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count()
+    }
+
+    /// Fails generation if errors were reported, leaving their details to
+    /// [`Diagnostics::take`].
+    pub fn bail_on_errors(&self) -> anyhow::Result<()> {
+        match self.error_count() {
+            0 => Ok(()),
+            errors => anyhow::bail!("generation failed due to {errors} annotation error(s)"),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter()
+    }
+
+    pub fn take(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Fills in the file and line of annotation diagnostics by searching the
+    /// WIT `files` the bindings were generated from.
+    ///
+    /// The search is scoped to files of the diagnostic's package and to the
+    /// blocks enclosing its item, e.g. the interface and then the record for
+    /// `iface.my-record.field`. Within that scope a diagnostic is located at
+    /// the first `#target` line that is followed, past any other annotations
+    /// and comments, by a line naming its item. Diagnostics with an offset
+    /// into the annotation body also get the column it points to.
+    /// Diagnostics that can't be found are left unchanged.
+    pub fn locate_in_files(diagnostics: &mut [Diagnostic], files: &[PathBuf]) {
+        let sources = files
+            .iter()
+            .filter_map(|path| Some((path, std::fs::read_to_string(path).ok()?)))
+            .collect::<Vec<_>>();
+        for diagnostic in diagnostics.iter_mut() {
+            let Some(target) = &diagnostic.target else {
+                continue;
+            };
+            if diagnostic.location.file.is_some() {
+                continue;
+            }
+            let name = diagnostic.location.name().to_string();
+            let scopes = diagnostic.location.item.split('.').collect::<Vec<_>>();
+            let scopes = &scopes[..scopes.len() - 1];
+            'search: for (path, contents) in sources.iter() {
+                let lines = contents.lines().collect::<Vec<_>>();
+                if let (Some(package), Some(declared)) =
+                    (&diagnostic.location.package, declared_package(&lines))
+                {
+                    if package != declared {
+                        continue;
+                    }
+                }
+                let Some((start, end)) = scope(&lines, scopes) else {
+                    continue;
+                };
+                for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+                    if !is_annotation_line(line, target) {
+                        continue;
+                    }
+                    let item = lines[annotation_end(&lines, i) + 1..end].iter().find(|l| {
+                        let l = l.trim_start();
+                        !(l.is_empty() || l.starts_with('#') || l.starts_with("//"))
+                    });
+                    if item.is_some_and(|l| mentions(l, &name)) {
+                        let (line, column) = match diagnostic.location.offset {
+                            Some(offset) => match body_position(&lines, i, target, offset) {
+                                Some((line, column)) => (line, Some(column)),
+                                None => (i, None),
+                            },
+                            None => (i, None),
+                        };
+                        diagnostic.location.file = Some(path.to_path_buf());
+                        diagnostic.location.line = Some(line + 1);
+                        diagnostic.location.column = column.map(|c| c + 1);
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The package declared by a WIT file, e.g. `a:b@1.0.0` for
+/// `package a:b@1.0.0;`.
+fn declared_package<'a>(lines: &[&'a str]) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let rest = line.trim().strip_prefix("package ")?;
+        Some(rest.trim_end_matches([';', '{']).trim())
+    })
+}
+
+/// The range of lines of the innermost block enclosing an item nested in
+/// `scopes`, e.g. `["iface", "my-record"]`.
+///
+/// The outermost scope, an interface or a world, must be declared in `lines`.
+/// Inner scopes that don't open a block of their own, such as functions, are
+/// skipped.
+fn scope(lines: &[&str], scopes: &[&str]) -> Option<(usize, usize)> {
+    let mut range = (0, lines.len());
+    for (i, name) in scopes.iter().enumerate() {
+        match block(lines, range, name) {
+            Some(block) => range = block,
+            None if i == 0 => return None,
+            None => {}
+        }
+    }
+    Some(range)
+}
+
+/// The lines within `range` of the block declaring `name`, e.g.
+/// `record name { ... }`, excluding its opening and closing lines.
+fn block(lines: &[&str], (start, end): (usize, usize), name: &str) -> Option<(usize, usize)> {
+    let open = (start..end).find(|&i| declares(lines[i], name) && lines[i].contains('{'))?;
+    let mut depth = 0usize;
+    for (i, line) in lines.iter().enumerate().take(end).skip(open) {
+        for c in line.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if depth == 0 {
+            return Some((open + 1, i));
+        }
+    }
+    Some((open + 1, end))
+}
+
+/// Whether `line` declares an item called `name`, e.g. `interface name {` or
+/// `name: func()`.
+fn declares(line: &str, name: &str) -> bool {
+    let mut line = line.trim_start();
+    for keyword in [
+        "interface ",
+        "world ",
+        "record ",
+        "variant ",
+        "enum ",
+        "flags ",
+        "resource ",
+        "type ",
+        "import ",
+        "export ",
+    ] {
+        if let Some(rest) = line.strip_prefix(keyword) {
+            line = rest.trim_start();
+            break;
+        }
+    }
+    let line = line.strip_prefix('%').unwrap_or(line);
+    line.strip_prefix(name)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '-'))
+}
+
+fn is_annotation_line(line: &str, target: &str) -> bool {
+    line.trim_start()
+        .strip_prefix('#')
+        .and_then(|rest| rest.strip_prefix(target))
+        .is_some_and(|rest| {
+            !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+/// The last line of the annotation starting on line `start`, whose body may
+/// span several lines.
+fn annotation_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0usize;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        for c in line.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        if depth == 0 {
+            return i;
+        }
+    }
+    start
+}
+
+/// The 0-based line and column `offset` bytes into the body of the
+/// `#target(...)` annotation starting on line `start`.
+fn body_position(
+    lines: &[&str],
+    start: usize,
+    target: &str,
+    offset: usize,
+) -> Option<(usize, usize)> {
+    let annotation = format!("#{target}(");
+    let mut column = lines[start].find(&annotation)? + annotation.len();
+    let mut remaining = offset;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        let rest = line.len().checked_sub(column)?;
+        if remaining <= rest {
+            return Some((i, column + remaining));
+        }
+        // Skip the rest of the line and its newline.
+        remaining -= rest + 1;
+        column = 0;
+    }
+    None
+}
+
+fn mentions(line: &str, name: &str) -> bool {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .any(|word| word == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visitor::parse_annotation;
+
+    #[test]
+    fn test_locate_in_files() {
+        let dir =
+            std::env::temp_dir().join(format!("wit-bindgen-diagnostics-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.wit");
+        std::fs::write(
+            &path,
+            "package a:b;\n\
+             interface i {\n  \
+               #derive(Debug)\n  \
+               record other { x: u32 }\n\n  \
+               /// docs\n  \
+               #derive(Debug)\n  \
+               // comment\n  \
+               record point { x: u32 }\n\
+             }\n",
+        )
+        .unwrap();
+
+        let mut diagnostics = Diagnostics::new(true);
+        let location = Location {
+            package: Some("a:b".to_string()),
+            item: "i.point".to_string(),
+            ..Location::default()
+        };
+        diagnostics.annotation(&location, "derive", "unknown target");
+        diagnostics.warning(&location, "not about an annotation");
+        let mut diagnostics = diagnostics.take();
+        Diagnostics::locate_in_files(&mut diagnostics, &[path.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].location.file.as_ref(), Some(&path));
+        assert_eq!(diagnostics[0].location.line, Some(7));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            format!("error: {}:7: a:b/i.point: unknown target", path.display())
+        );
+        assert_eq!(diagnostics[1].location.file, None);
+    }
+
+    #[test]
+    fn test_locate_scoped() {
+        let dir = std::env::temp_dir().join(format!(
+            "wit-bindgen-diagnostics-scoped-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ab = dir.join("ab.wit");
+        std::fs::write(
+            &ab,
+            "package a:b;\n\
+             interface i {\n  \
+               record other {\n    \
+                 #range(0, 1)\n    \
+                 x: u32,\n  \
+               }\n  \
+               record point {\n    \
+                 #range(0, 1)\n    \
+                 x: u32,\n  \
+               }\n\
+             }\n",
+        )
+        .unwrap();
+        let cd = dir.join("cd.wit");
+        std::fs::write(
+            &cd,
+            "package c:d;\n\
+             interface i {\n  \
+               record point {\n    \
+                 #range(0, 1)\n    \
+                 x: u32,\n  \
+               }\n\
+             }\n",
+        )
+        .unwrap();
+
+        let mut diagnostics = Diagnostics::new(false);
+        for package in ["c:d", "a:b"] {
+            let location = Location {
+                package: Some(package.to_string()),
+                item: "i.point.x".to_string(),
+                ..Location::default()
+            };
+            diagnostics.annotation(&location, "range", "out of range");
+        }
+        let mut diagnostics = diagnostics.take();
+        Diagnostics::locate_in_files(&mut diagnostics, &[ab.clone(), cd.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Fields with the same name in other records or other packages are
+        // skipped.
+        assert_eq!(diagnostics[0].location.file.as_ref(), Some(&cd));
+        assert_eq!(diagnostics[0].location.line, Some(4));
+        assert_eq!(diagnostics[1].location.file.as_ref(), Some(&ab));
+        assert_eq!(diagnostics[1].location.line, Some(8));
+    }
+
+    #[test]
+    fn test_locate_parse_errors() {
+        let dir = std::env::temp_dir().join(format!(
+            "wit-bindgen-diagnostics-offset-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.wit");
+        std::fs::write(
+            &path,
+            "package a:b;\n\
+             interface i {\n  \
+               #range(0, \"x)\n  \
+               record one { x: u32 }\n  \
+               #range(0,\n    \"x)\n  \
+               record two { x: u32 }\n\
+             }\n",
+        )
+        .unwrap();
+
+        let mut diagnostics = Diagnostics::new(false);
+        let location = Location {
+            package: Some("a:b".to_string()),
+            item: "i".to_string(),
+            ..Location::default()
+        };
+        parse_annotation("range", "0, \"x", &location.member("one"), &mut diagnostics);
+        parse_annotation(
+            "range",
+            "0,\n    \"x",
+            &location.member("two"),
+            &mut diagnostics,
+        );
+        let mut diagnostics = diagnostics.take();
+        Diagnostics::locate_in_files(&mut diagnostics, &[path.clone()]);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Errors point at the offending character, even when the annotation
+        // body spans several lines.
+        assert_eq!(
+            diagnostics[0].to_string(),
+            format!(
+                "warning: {}:3:13: a:b/i.one: invalid arguments for annotation '#range': \
+                 unterminated string literal",
+                path.display()
+            )
+        );
+        assert_eq!(diagnostics[1].location.line, Some(6));
+        assert_eq!(diagnostics[1].location.column, Some(5));
+    }
+}
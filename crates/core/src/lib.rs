@@ -15,8 +15,10 @@ pub use path::name_package_module;
 mod async_;
 pub use async_::AsyncFilterSet;
 
+pub mod diagnostics;
+pub use diagnostics::{Diagnostic, Diagnostics, Location, Severity};
 pub mod visitor;
-pub use visitor::{ContributionTypes, Visitor};
+pub use visitor::{AnnotationArgs, ContributionTypes, VisitContext, Visitor};
 
 #[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Direction {
@@ -28,6 +30,7 @@ pub enum Direction {
 pub trait WorldGenerator {
     fn generate(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
         let world = &resolve.worlds[id];
+        self.check_annotations(resolve, id)?;
         self.preprocess(resolve, id);
 
         fn unwrap_name(key: &WorldKey) -> &str {
@@ -88,6 +91,14 @@ pub trait WorldGenerator {
         let _ = (resolve, world, files);
     }
 
+    /// Checks the annotations of `world` before anything is generated,
+    /// failing if any of them can't be generated, see
+    /// [`visitor::check_world_annotations`].
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        let _ = (resolve, world);
+        Ok(())
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let _ = (resolve, world);
     }
@@ -135,6 +146,14 @@ pub trait WorldGenerator {
         files: &mut Files,
     );
     fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()>;
+
+    /// Takes the diagnostics collected while generating bindings.
+    ///
+    /// This should be called after `generate`, whether or not it succeeded,
+    /// since generation fails in strict mode when errors were reported.
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        Vec::new()
+    }
 }
 
 /// This is a possible replacement for the `Generator` trait above, currently
@@ -1,7 +1,19 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
 use wit_parser::*;
 
+use crate::diagnostics::{Diagnostics, Location};
+use crate::Direction;
+
+mod args;
+pub use args::{AnnotationArg, AnnotationArgs, AnnotationParseError, AnnotationValue};
+mod items;
+pub use items::{annotated_items, world_annotated_items, AnnotatedItem, ItemKind};
+mod schema;
+pub use schema::{AnnotationSchema, ArgKind, ValueKind};
+
 /// Trait that groups related contribution types together.
 ///
 /// This allows language backends to define all their contribution types as a cohesive family,
@@ -19,10 +31,64 @@ pub trait ContributionTypes {
     /// Language-specific contribution type for functions.
     type Function;
 
+    /// Language-specific contribution type for function parameters.
+    type Param;
+
     /// Language-specific contribution type for modules/interfaces.
     type Module;
 }
 
+/// Where an annotated item is being generated, passed to every
+/// [`Visitor`] hook.
+#[derive(Debug, Clone)]
+pub struct VisitContext<'a> {
+    /// The resolve the item belongs to, e.g. to look up the types of fields.
+    pub resolve: &'a Resolve,
+
+    /// The world bindings are being generated for.
+    pub world: WorldId,
+
+    /// The interface the item belongs to, or `None` for items of the world.
+    pub interface: Option<InterfaceId>,
+
+    /// Whether the item is generated for an import or an export, or `None`
+    /// if the generator shares it between both, e.g. the world itself.
+    pub direction: Option<Direction>,
+
+    /// The identifier the generator chose for the item, e.g. `UserInfo` for
+    /// the Rust struct of `record user-info`, if it has one.
+    pub name: Option<String>,
+
+    /// The path of the module, namespace or package the item is generated
+    /// in, in the syntax of the target language, e.g.
+    /// `exports::test::api::api` for Rust.
+    pub module_path: Option<String>,
+}
+
+impl<'a> VisitContext<'a> {
+    /// A context for an item of `world` that is known to nothing else.
+    pub fn new(resolve: &'a Resolve, world: WorldId) -> Self {
+        VisitContext {
+            resolve,
+            world,
+            interface: None,
+            direction: None,
+            name: None,
+            module_path: None,
+        }
+    }
+
+    /// Whether the item is generated for an import.
+    pub fn is_import(&self) -> bool {
+        self.direction == Some(Direction::Import)
+    }
+
+    /// Whether the item is generated for an export.
+    pub fn is_export(&self) -> bool {
+        self.direction == Some(Direction::Export)
+    }
+}
+
 /// Generic visitor trait for all language backends.
 ///
 /// This trait uses nested associated types to allow each language backend to define
@@ -44,13 +110,65 @@ pub trait Visitor {
     /// i.e 'serde' would be the target in the annotations #serde(Serialize, Deserialize)
     fn target(&self) -> &str;
 
+    /// Where this visitor's contributions go relative to those of other
+    /// visitors for the same item, see [`priority`]. Contributions with a
+    /// lower priority come first, and ties keep the order of the item's
+    /// annotations and then the order the visitors were registered in.
+    fn priority(&self) -> i32 {
+        priority::DEFAULT
+    }
+
+    /// Whether annotations for this visitor's target on an interface or a
+    /// world are also offered to the items it contains: its types, fields,
+    /// cases, flags and functions. This lets `#derive(Debug)` on an
+    /// interface apply to all of its types, for example.
+    ///
+    /// An item's own annotation for the target replaces the inherited one,
+    /// and an interface's replaces its world's. Items opt out with
+    /// `#no_inherit(target, ...)`, or `#no_inherit` for all targets.
+    /// Inherited annotations that the visitor declines are skipped silently.
+    fn inherit(&self) -> bool {
+        false
+    }
+
+    /// The kinds of items this visitor's annotations can be placed on, or
+    /// `None` for any kind. Visitors that [`Visitor::inherit`] also accept
+    /// interfaces and worlds.
+    ///
+    /// Like [`Visitor::value_kinds`] and [`Visitor::schema`], this lets
+    /// generators reject misplaced annotations with a precise error before
+    /// generating anything, see [`check_annotations`], rather than the
+    /// visitor declining them.
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        None
+    }
+
+    /// The kinds of values the fields, cases and parameters this visitor's
+    /// annotations are placed on must hold, e.g. strings and lists for
+    /// `#max_length(255)`, or `None` for any kind.
+    fn value_kinds(&self) -> Option<&[ValueKind]> {
+        None
+    }
+
+    /// The arguments this visitor's annotations take, or `None` to accept
+    /// anything. For annotations on a parameter these are the arguments
+    /// after the parameter's name.
+    fn schema(&self) -> Option<AnnotationSchema> {
+        None
+    }
+
+    // Every hook receives the parsed body of the annotation, e.g. the
+    // `Serialize, Deserialize` above, as `AnnotationArgs`, and a
+    // `VisitContext` describing where the item is being generated.
+
     // ==================== Type Definition Hooks ====================
     #[allow(unused)]
     fn visit_record(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         record: &Record,
         type_id: TypeId,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
         None
     }
@@ -58,9 +176,10 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_variant(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         variant: &Variant,
         type_id: TypeId,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
         None
     }
@@ -68,9 +187,10 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_enum(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         enum_: &Enum,
         type_id: TypeId,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
         None
     }
@@ -78,9 +198,10 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_flags(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         flags: &Flags,
         type_id: TypeId,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
         None
     }
@@ -88,8 +209,69 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_resource(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         resource_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
+        None
+    }
+
+    /// Called for a named alias of another type, e.g. `type counter = u32;`.
+    #[allow(unused)]
+    fn visit_alias(
+        &mut self,
+        annotation: &AnnotationArgs,
+        ty: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
+        None
+    }
+
+    /// Called for a named tuple typedef, e.g. `type point = tuple<u32, u32>;`.
+    #[allow(unused)]
+    fn visit_tuple(
+        &mut self,
+        annotation: &AnnotationArgs,
+        tuple: &Tuple,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
+        None
+    }
+
+    /// Called for a named list typedef, e.g. `type bytes = list<u8>;`.
+    #[allow(unused)]
+    fn visit_list(
+        &mut self,
+        annotation: &AnnotationArgs,
+        element: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
+        None
+    }
+
+    /// Called for a named option typedef, e.g. `type maybe-id = option<u64>;`.
+    #[allow(unused)]
+    fn visit_option(
+        &mut self,
+        annotation: &AnnotationArgs,
+        payload: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
+        None
+    }
+
+    /// Called for a named result typedef, e.g. `type outcome = result<u32, string>;`.
+    #[allow(unused)]
+    fn visit_result(
+        &mut self,
+        annotation: &AnnotationArgs,
+        result: &Result_,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Type> {
         None
     }
@@ -98,9 +280,10 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_field(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         field: &Field,
         field_index: usize,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Field> {
         None
     }
@@ -108,9 +291,25 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_variant_case(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         case: &Case,
         case_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::VariantCase> {
+        None
+    }
+
+    /// Called for each flag of a `flags` type.
+    ///
+    /// Flags are rendered as members of their parent type, so they share the
+    /// variant case contribution type.
+    #[allow(unused)]
+    fn visit_flag(
+        &mut self,
+        annotation: &AnnotationArgs,
+        flag: &Flag,
+        flag_index: usize,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::VariantCase> {
         None
     }
@@ -119,18 +318,39 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_function(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         func: &Function,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Function> {
         None
     }
 
+    /// Called for a function annotation that targets one of the function's
+    /// parameters by naming it first, e.g. `#range(age, 18, 120)` on
+    /// `func(age: u8)`, see [`param_target`].
+    ///
+    /// `annotation` holds the remaining arguments, here `18, 120`, so the
+    /// same code can handle an annotation on a field and on a parameter. If
+    /// this returns `None` the annotation is offered to
+    /// [`Visitor::visit_function`] with all of its arguments instead.
+    #[allow(unused)]
+    fn visit_param(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        param_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<<Self::Contributions as ContributionTypes>::Param> {
+        None
+    }
+
     // ==================== Module/Interface Hooks ====================
     #[allow(unused)]
     fn visit_interface(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         interface: Option<&Interface>,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Module> {
         None
     }
@@ -138,29 +358,1054 @@ pub trait Visitor {
     #[allow(unused)]
     fn visit_world(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         world: &World,
+        cx: &VisitContext<'_>,
     ) -> Option<<Self::Contributions as ContributionTypes>::Module> {
         None
     }
 }
 
+/// Well-known values for [`Visitor::priority`].
+pub mod priority {
+    /// For contributions that must precede all others, e.g. `#[cfg]`
+    /// attributes.
+    pub const FIRST: i32 = -1000;
+
+    /// For contributions that check their input, e.g. body prefixes that
+    /// validate arguments before any other prefix sees them.
+    pub const VALIDATION: i32 = -100;
+
+    /// The priority of visitors that don't override [`super::Visitor::priority`].
+    pub const DEFAULT: i32 = 0;
+
+    /// For attributes read by a derive macro, e.g. `#[serde(...)]`, which
+    /// generators place after the attribute deriving it. Contributions at
+    /// this priority or above are derive helpers, see
+    /// [`super::visit_annotations_with_priority`].
+    pub const DERIVE_HELPERS: i32 = 500;
+
+    /// For contributions that must follow all others.
+    pub const LAST: i32 = 1000;
+}
+
+/// The target of the annotation opting an item out of inherited
+/// annotations, see [`Visitor::inherit`]. It's handled by
+/// [`visit_annotations`] rather than by a visitor.
+pub const NO_INHERIT: &str = "no_inherit";
+
+/// The visitors registered with a generator, keyed by target.
+///
+/// Any number of visitors can share a target, in which case every one of
+/// them is offered each annotation with that target.
+pub struct VisitorMap<T: ?Sized> {
+    visitors: HashMap<String, Vec<Registration<T>>>,
+}
+
+/// A visitor registered in a [`VisitorMap`], with the priority its
+/// contributions are ordered by.
+pub struct Registration<T: ?Sized> {
+    visitor: Box<T>,
+    priority: Option<i32>,
+}
+
+impl<T: Visitor + ?Sized> Registration<T> {
+    pub fn visitor(&self) -> &T {
+        &self.visitor
+    }
+
+    pub fn visitor_mut(&mut self) -> &mut T {
+        &mut self.visitor
+    }
+
+    /// The priority given at registration, or else the visitor's own
+    /// [`Visitor::priority`].
+    pub fn priority(&self) -> i32 {
+        self.priority.unwrap_or_else(|| self.visitor.priority())
+    }
+}
+
+impl<T: ?Sized> Default for VisitorMap<T> {
+    fn default() -> Self {
+        VisitorMap {
+            visitors: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Visitor + ?Sized> VisitorMap<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `visitor` for its target, after any visitors already
+    /// registered for it.
+    pub fn register(&mut self, visitor: Box<T>) {
+        self.push(visitor, None);
+    }
+
+    /// Registers `visitor` like [`VisitorMap::register`], but orders its
+    /// contributions by `priority` instead of its own
+    /// [`Visitor::priority`], e.g. to move a built-in visitor's attributes
+    /// after those of another one.
+    pub fn register_with_priority(&mut self, visitor: Box<T>, priority: i32) {
+        self.push(visitor, Some(priority));
+    }
+
+    /// Registers `visitor` unless other visitors are already registered for
+    /// its target, for fallbacks like built-in visitors.
+    pub fn register_default(&mut self, visitor: Box<T>) {
+        if !self.contains(visitor.target()) {
+            self.register(visitor);
+        }
+    }
+
+    /// Orders the contributions of every visitor registered for `target` by
+    /// `priority`, as if they were registered with
+    /// [`VisitorMap::register_with_priority`]. Returns whether any visitor
+    /// is registered for it.
+    pub fn set_priority(&mut self, target: &str, priority: i32) -> bool {
+        let Some(chain) = self.visitors.get_mut(target) else {
+            return false;
+        };
+        for registration in chain {
+            registration.priority = Some(priority);
+        }
+        true
+    }
+
+    /// Returns whether any visitor is registered for `target`.
+    pub fn contains(&self, target: &str) -> bool {
+        self.visitors.contains_key(target)
+    }
+
+    fn push(&mut self, visitor: Box<T>, priority: Option<i32>) {
+        self.visitors
+            .entry(visitor.target().to_string())
+            .or_default()
+            .push(Registration { visitor, priority });
+    }
+}
+
+/// Checks that `name` names one of a generator's built-in visitors, which
+/// `lookup` creates by name and `names` lists, for the parsers of the
+/// generators' `--annotations` options.
+pub fn parse_builtin<T: ?Sized>(
+    name: &str,
+    names: &[&str],
+    lookup: impl Fn(&str) -> Option<Box<T>>,
+) -> Result<String, String> {
+    match lookup(name) {
+        Some(_) => Ok(name.to_string()),
+        None => Err(unknown_builtin(name, names)),
+    }
+}
+
+/// Registers the built-in visitors `requested` by name as defaults, see
+/// [`VisitorMap::register_default`], reporting names that aren't built-in
+/// visitors to `diagnostics` as errors.
+pub fn register_builtins<T: Visitor + ?Sized>(
+    visitors: &mut VisitorMap<T>,
+    diagnostics: &mut Diagnostics,
+    requested: &[String],
+    names: &[&str],
+    lookup: impl Fn(&str) -> Option<Box<T>>,
+) {
+    for name in requested {
+        match lookup(name) {
+            Some(visitor) => visitors.register_default(visitor),
+            None => diagnostics.error(&Location::default(), unknown_builtin(name, names)),
+        }
+    }
+}
+
+fn unknown_builtin(name: &str, names: &[&str]) -> String {
+    format!(
+        "unknown built-in annotation visitor `{name}`; expected one of: {}",
+        names.join(", ")
+    )
+}
+
+/// The visitors given to a generator through its options, as a function
+/// creating them.
+///
+/// Options are `Clone` and build generators by reference, so rather than
+/// holding visitors, which can't be cloned, they hold this factory and every
+/// generator built gets fresh visitors from it. The visitors are registered
+/// before the built-in ones, see [`register_builtins`].
+pub struct VisitorList<T: ?Sized> {
+    factory: Option<Arc<dyn Fn() -> Vec<Box<T>> + Send + Sync>>,
+}
+
+impl<T: ?Sized> VisitorList<T> {
+    pub fn new(factory: impl Fn() -> Vec<Box<T>> + Send + Sync + 'static) -> Self {
+        VisitorList {
+            factory: Some(Arc::new(factory)),
+        }
+    }
+
+    /// Creates the visitors for a generator being built.
+    pub fn create(&self) -> Vec<Box<T>> {
+        match &self.factory {
+            Some(factory) => factory(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl<T: ?Sized> Default for VisitorList<T> {
+    fn default() -> Self {
+        VisitorList { factory: None }
+    }
+}
+
+impl<T: ?Sized> Clone for VisitorList<T> {
+    fn clone(&self) -> Self {
+        VisitorList {
+            factory: self.factory.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for VisitorList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VisitorList").finish_non_exhaustive()
+    }
+}
+
 pub trait FindVisitorWithWarning<T: ?Sized> {
-    const DEFAULT_WARNING: bool = true;
-    
-    fn find_visitor_with_warning(&mut self, target: &str, warn: Option<bool>) -> Option<&mut Box<T>>;
+    /// Finds the visitors registered for `target`, reporting unknown targets
+    /// of the annotation at `location` to `diagnostics`.
+    fn find_visitor_with_warning(
+        &mut self,
+        target: &str,
+        location: &Location,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<&mut [Registration<T>]>;
 }
 
-impl<T: ?Sized> FindVisitorWithWarning<T> for HashMap<String, Box<T>> {
-    fn find_visitor_with_warning(&mut self, target: &str, warn: Option<bool>) -> Option<&mut Box<T>> {
-        let print_warning = warn.unwrap_or(Self::DEFAULT_WARNING);
-        
-        let result = self.get_mut(target);
+impl<T: ?Sized> FindVisitorWithWarning<T> for VisitorMap<T> {
+    fn find_visitor_with_warning(
+        &mut self,
+        target: &str,
+        location: &Location,
+        diagnostics: &mut Diagnostics,
+    ) -> Option<&mut [Registration<T>]> {
+        let result = self.visitors.get_mut(target).map(|v| v.as_mut_slice());
 
-        if print_warning && result.is_none() {
-            println!("cargo::warning=Warning: No visitor registered for annotation target '{}'", target);
+        if result.is_none() {
+            diagnostics.annotation(
+                location,
+                target,
+                format!("no visitor registered for annotation target '{target}'"),
+            );
         }
 
         result
     }
 }
+
+/// Parses the body of a `#target(...)` annotation found on the item at
+/// `location`.
+///
+/// Malformed arguments are reported to `diagnostics`, pointing at the offset
+/// within the body where parsing failed, and the visitor still receives the
+/// raw text so free-form annotations keep working.
+pub fn parse_annotation(
+    target: &str,
+    raw: &str,
+    location: &Location,
+    diagnostics: &mut Diagnostics,
+) -> AnnotationArgs {
+    match AnnotationArgs::parse(raw) {
+        Ok(args) => args,
+        Err(e) => {
+            diagnostics.annotation(
+                &location.at_offset(e.offset),
+                target,
+                format!(
+                    "invalid arguments for annotation '#{target}': {}",
+                    e.message
+                ),
+            );
+            AnnotationArgs::unparsed(raw)
+        }
+    }
+}
+
+/// The `(target, raw)` pairs of the annotations of an interface or world.
+fn annotation_pairs<K: ToString, V: ToString>(
+    annotations: impl IntoIterator<Item = (K, V)>,
+) -> Vec<(String, String)> {
+    annotations
+        .into_iter()
+        .map(|(target, raw)| (target.to_string(), raw.to_string()))
+        .collect()
+}
+
+/// Finds the parameter of `func` targeted by a function annotation with the
+/// arguments `args`.
+///
+/// An annotation targets a parameter when its first argument is an
+/// identifier spelling the parameter's WIT name, as in `#range(age, 18, 120)`
+/// on `func(age: u8)`. Returns the parameter's index and the remaining
+/// arguments.
+pub fn param_target(func: &Function, args: &AnnotationArgs) -> Option<(usize, AnnotationArgs)> {
+    let (first, rest) = args.split_first()?;
+    let name = match (&first.name, &first.value) {
+        (None, AnnotationValue::Ident(name)) => name,
+        _ => return None,
+    };
+    let index = func.params.iter().position(|(param, _)| param == name)?;
+    Some((index, rest))
+}
+
+/// Why a visitor doesn't accept an annotation.
+#[derive(Debug, Clone)]
+enum Mismatch {
+    Kind,
+    Value(ValueKind),
+    Args(String),
+}
+
+impl Mismatch {
+    /// Ranks reasons from the least to the most specific, so that the most
+    /// useful one is reported when none of the visitors for a target accept
+    /// an annotation.
+    fn rank(&self) -> u8 {
+        match self {
+            Mismatch::Kind => 0,
+            Mismatch::Value(_) => 1,
+            Mismatch::Args(_) => 2,
+        }
+    }
+}
+
+/// Checks an annotation with the arguments `args` on an item of kind `kind`,
+/// holding values of type `ty` if it's a field, case or parameter, against
+/// what `visitor` declares it accepts.
+fn check_visitor<T: Visitor + ?Sized>(
+    visitor: &T,
+    resolve: &Resolve,
+    kind: ItemKind,
+    ty: Option<&Type>,
+    args: &AnnotationArgs,
+) -> Result<(), Mismatch> {
+    if let Some(kinds) = visitor.item_kinds() {
+        let inherited = visitor.inherit() && matches!(kind, ItemKind::Interface | ItemKind::World);
+        if !kinds.contains(&kind) && !inherited {
+            return Err(Mismatch::Kind);
+        }
+    }
+    let value = ty.map(|ty| ValueKind::of(resolve, ty));
+    if let (Some(kinds), Some(value)) = (visitor.value_kinds(), value) {
+        if !kinds.contains(&value) {
+            return Err(Mismatch::Value(value));
+        }
+    }
+    match visitor.schema() {
+        Some(schema) => schema.check_for(args, value).map_err(Mismatch::Args),
+        None => Ok(()),
+    }
+}
+
+/// Checks an annotation against all visitors for its target, returning the
+/// most specific reason none of them accept it, or `None` if one does.
+fn check_chain<T: Visitor + ?Sized>(
+    chain: &[Registration<T>],
+    resolve: &Resolve,
+    kind: ItemKind,
+    ty: Option<&Type>,
+    args: &AnnotationArgs,
+) -> Option<Mismatch> {
+    let mut mismatch: Option<Mismatch> = None;
+    for registration in chain {
+        match check_visitor(registration.visitor(), resolve, kind, ty, args) {
+            Ok(()) => return None,
+            Err(m) => match &mismatch {
+                Some(prev) if prev.rank() >= m.rank() => {}
+                _ => mismatch = Some(m),
+            },
+        }
+    }
+    mismatch
+}
+
+/// Checks the annotations in `items`, e.g. those of
+/// [`world_annotated_items`], against what the visitors for their targets
+/// declare they accept: see [`Visitor::item_kinds`],
+/// [`Visitor::value_kinds`] and [`Visitor::schema`]. Annotations that none
+/// of their visitors accept are reported to `diagnostics` as errors, even
+/// outside of strict mode, since generating them would produce broken or
+/// silently incomplete bindings.
+///
+/// Generators call this before generating anything. Unknown targets and
+/// malformed arguments are left to [`visit_annotations`] to report.
+pub fn check_annotations<T: Visitor + ?Sized>(
+    visitors: &VisitorMap<T>,
+    diagnostics: &mut Diagnostics,
+    resolve: &Resolve,
+    items: &[AnnotatedItem],
+) {
+    for item in items {
+        let Some(chain) = visitors.visitors.get(&item.target) else {
+            continue;
+        };
+        let target = &item.target;
+        let args = match AnnotationArgs::parse(&item.value) {
+            Ok(args) => args,
+            // Without a schema the visitors get the raw arguments, which
+            // `visit_annotations` warns about.
+            Err(e) if chain.iter().any(|r| r.visitor().schema().is_some()) => {
+                diagnostics.annotation_error(
+                    &item.location.at_offset(e.offset),
+                    target,
+                    format!(
+                        "invalid arguments for annotation '#{target}': {}",
+                        e.message
+                    ),
+                );
+                continue;
+            }
+            Err(_) => continue,
+        };
+        let ty = item.ty.as_ref();
+        let mismatch = match item.kind {
+            // Visitors that decline `visit_param` are offered the annotation
+            // as one of the whole function, so either is fine.
+            ItemKind::Param => {
+                let (_, rest) = args.split_first().expect("the parameter is named");
+                let param = check_chain(chain, resolve, ItemKind::Param, ty, &rest);
+                let func = check_chain(chain, resolve, ItemKind::Function, None, &args);
+                match (param, func) {
+                    (Some(param), Some(func)) if func.rank() > param.rank() => Some(func),
+                    (Some(param), Some(_)) => Some(param),
+                    _ => None,
+                }
+            }
+            kind => check_chain(chain, resolve, kind, ty, &args),
+        };
+        let message = match mismatch {
+            None => continue,
+            Some(Mismatch::Kind) => {
+                let mut kinds = Vec::new();
+                for visitor in chain.iter().map(Registration::visitor) {
+                    let modules = [ItemKind::Interface, ItemKind::World];
+                    let inherited = visitor.inherit().then_some(&modules[..]);
+                    let declared = visitor.item_kinds().into_iter().chain(inherited);
+                    for kind in declared.flatten() {
+                        if !kinds.contains(kind) {
+                            kinds.push(*kind);
+                        }
+                    }
+                }
+                format!(
+                    "annotation '#{target}' is not allowed on this {}; it applies to: {}",
+                    item.kind,
+                    join(&kinds)
+                )
+            }
+            Some(Mismatch::Value(value)) => {
+                let mut values = Vec::new();
+                let visitors = chain.iter().map(Registration::visitor);
+                for kind in visitors.filter_map(|v| v.value_kinds()).flatten() {
+                    if !values.contains(kind) {
+                        values.push(*kind);
+                    }
+                }
+                format!(
+                    "annotation '#{target}' is not allowed on this {}, which holds {value} \
+                     values; it applies to: {}",
+                    item.kind,
+                    join(&values)
+                )
+            }
+            Some(Mismatch::Args(message)) => {
+                format!("invalid arguments for annotation '#{target}': {message}")
+            }
+        };
+        diagnostics.annotation_error(&item.location, target, message);
+    }
+}
+
+/// Checks the annotations bindings for `world` are generated from with
+/// [`check_annotations`], failing if any of them can't be generated.
+///
+/// This is what generators do in [`WorldGenerator::check_annotations`].
+///
+/// [`WorldGenerator::check_annotations`]: crate::WorldGenerator::check_annotations
+pub fn check_world_annotations<T: Visitor + ?Sized>(
+    visitors: &VisitorMap<T>,
+    diagnostics: &mut Diagnostics,
+    resolve: &Resolve,
+    world: WorldId,
+) -> anyhow::Result<()> {
+    let items = world_annotated_items(resolve, world);
+    check_annotations(visitors, diagnostics, resolve, &items);
+    diagnostics.bail_on_errors()
+}
+
+fn join(items: &[impl std::fmt::Display]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Dispatches the annotations found on the item at `location`, given as
+/// `(target, raw)` pairs, to their visitors.
+///
+/// `visit` is called with each visitor registered for an annotation's target
+/// and the parsed arguments, and should call the hook matching the annotated
+/// item. The contributions are returned ordered by their visitors'
+/// priority, see [`Registration::priority`].
+/// Unknown targets and malformed arguments are reported to `diagnostics`, as
+/// are annotations that all of their visitors decline in strict mode.
+///
+/// `inherit_from` is the context of an item contained in an interface or a
+/// world, to also offer it the annotations it inherits from them, see
+/// [`Visitor::inherit`]. It's `None` for interfaces and worlds themselves.
+pub fn visit_annotations<T, R, K, V>(
+    visitors: &mut VisitorMap<T>,
+    diagnostics: &mut Diagnostics,
+    location: &Location,
+    annotations: impl IntoIterator<Item = (K, V)>,
+    inherit_from: Option<&VisitContext<'_>>,
+    visit: impl FnMut(&mut T, &AnnotationArgs) -> Option<R>,
+) -> Vec<R>
+where
+    T: Visitor + ?Sized,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    visit_annotations_with_priority(
+        visitors,
+        diagnostics,
+        location,
+        annotations,
+        inherit_from,
+        visit,
+    )
+    .into_iter()
+    .map(|(_, contribution)| contribution)
+    .collect()
+}
+
+/// Like [`visit_annotations`], but pairs each contribution with the priority
+/// of the visitor it came from, for generators that place contributions in
+/// bands, e.g. derive helper attributes after the derives, see
+/// [`priority::DERIVE_HELPERS`].
+pub fn visit_annotations_with_priority<T, R, K, V>(
+    visitors: &mut VisitorMap<T>,
+    diagnostics: &mut Diagnostics,
+    location: &Location,
+    annotations: impl IntoIterator<Item = (K, V)>,
+    inherit_from: Option<&VisitContext<'_>>,
+    mut visit: impl FnMut(&mut T, &AnnotationArgs) -> Option<R>,
+) -> Vec<(i32, R)>
+where
+    T: Visitor + ?Sized,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut contributions = Vec::new();
+    // The targets the item doesn't inherit, or `None` if it opted out of
+    // inheriting altogether.
+    let mut skipped = Some(Vec::new());
+    for (target, raw) in annotations {
+        let (target, raw) = (target.as_ref(), raw.as_ref());
+        if target == NO_INHERIT {
+            let args = parse_annotation(target, raw, location, diagnostics);
+            if args.args().is_empty() {
+                skipped = None;
+            } else if let Some(skipped) = &mut skipped {
+                let targets = args.positional().filter_map(|v| v.as_str());
+                skipped.extend(targets.map(String::from));
+            }
+            continue;
+        }
+        if let Some(skipped) = &mut skipped {
+            skipped.push(target.to_string());
+        }
+        let Some(chain) = visitors.find_visitor_with_warning(target, location, diagnostics) else {
+            continue;
+        };
+        let args = parse_annotation(target, raw, location, diagnostics);
+        let mut accepted = false;
+        for registration in chain.iter_mut() {
+            let priority = registration.priority();
+            if let Some(contribution) = visit(registration.visitor_mut(), &args) {
+                contributions.push((priority, contribution));
+                accepted = true;
+            }
+        }
+        if !accepted && diagnostics.is_strict() {
+            diagnostics.annotation(
+                location,
+                target,
+                format!("annotation '#{target}' is not supported on this item"),
+            );
+        }
+    }
+    if let (Some(cx), Some(mut skipped)) = (inherit_from, skipped) {
+        let world = &cx.resolve.worlds[cx.world];
+        let mut scopes = vec![annotation_pairs(world.annotations.iter())];
+        if let Some(id) = cx.interface {
+            let interface = &cx.resolve.interfaces[id];
+            scopes.push(annotation_pairs(interface.annotations.iter()));
+        }
+        // The closest scope goes first and hides the targets it annotates
+        // from the ones further out.
+        for scope in scopes.iter().rev() {
+            for (target, raw) in scope {
+                if skipped.contains(target) {
+                    continue;
+                }
+                let Some(chain) = visitors.visitors.get_mut(target.as_str()) else {
+                    continue;
+                };
+                // Malformed arguments were reported at the interface or world.
+                let args =
+                    AnnotationArgs::parse(raw).unwrap_or_else(|_| AnnotationArgs::unparsed(raw));
+                for registration in chain.iter_mut() {
+                    if !registration.visitor().inherit() {
+                        continue;
+                    }
+                    let priority = registration.priority();
+                    if let Some(contribution) = visit(registration.visitor_mut(), &args) {
+                        contributions.push((priority, contribution));
+                    }
+                }
+            }
+            skipped.extend(scope.iter().map(|(target, _)| target.clone()));
+        }
+    }
+    // The sort is stable, so ties keep annotation and registration order.
+    contributions.sort_by_key(|(priority, _)| *priority);
+    contributions
+}
+
+/// Generators holding the visitors and diagnostics their annotations are
+/// dispatched with.
+pub trait VisitAnnotations {
+    type Visitor: Visitor + ?Sized;
+
+    /// The visitors registered with the generator, and where it reports
+    /// annotation problems.
+    fn visitors_and_diagnostics(&mut self) -> (&mut VisitorMap<Self::Visitor>, &mut Diagnostics);
+
+    /// Dispatches the annotations found at `location` to their visitors, see
+    /// [`visit_annotations`].
+    fn visit_annotations<R, K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        location: &Location,
+        annotations: impl IntoIterator<Item = (K, V)>,
+        inherit_from: Option<&VisitContext<'_>>,
+        visit: impl FnMut(&mut Self::Visitor, &AnnotationArgs) -> Option<R>,
+    ) -> Vec<R> {
+        let (visitors, diagnostics) = self.visitors_and_diagnostics();
+        visit_annotations(
+            visitors,
+            diagnostics,
+            location,
+            annotations,
+            inherit_from,
+            visit,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Strings;
+
+    impl ContributionTypes for Strings {
+        type Type = ();
+        type Field = ();
+        type VariantCase = ();
+        type Function = String;
+        type Param = ();
+        type Module = ();
+    }
+
+    /// Contributes its name, followed by the annotation's arguments if any, to
+    /// every function, or declines if it has no name.
+    struct Named {
+        target: &'static str,
+        name: Option<&'static str>,
+        priority: i32,
+        inherit: bool,
+    }
+
+    impl Visitor for Named {
+        type Contributions = Strings;
+
+        fn target(&self) -> &str {
+            self.target
+        }
+
+        fn priority(&self) -> i32 {
+            self.priority
+        }
+
+        fn inherit(&self) -> bool {
+            self.inherit
+        }
+
+        fn visit_function(
+            &mut self,
+            annotation: &AnnotationArgs,
+            _func: &Function,
+            _cx: &VisitContext<'_>,
+        ) -> Option<String> {
+            self.name.map(|name| match annotation.as_str() {
+                "" => name.to_string(),
+                args => format!("{name}({args})"),
+            })
+        }
+    }
+
+    fn visitor(target: &'static str, name: Option<&'static str>, priority: i32) -> Box<Named> {
+        Box::new(Named {
+            target,
+            name,
+            priority,
+            inherit: false,
+        })
+    }
+
+    fn inheriting(target: &'static str, name: Option<&'static str>) -> Box<Named> {
+        Box::new(Named {
+            inherit: true,
+            ..*visitor(target, name, priority::DEFAULT)
+        })
+    }
+
+    /// Declares what it accepts depending on its target, like `#repr(u8)`,
+    /// `#range(0, 10)` and `#trace` would.
+    struct Declared(&'static str);
+
+    impl Visitor for Declared {
+        type Contributions = Strings;
+
+        fn target(&self) -> &str {
+            self.0
+        }
+
+        fn inherit(&self) -> bool {
+            self.0 == "trace"
+        }
+
+        fn item_kinds(&self) -> Option<&[ItemKind]> {
+            match self.0 {
+                "repr" => Some(&[ItemKind::Enum]),
+                "range" => Some(&[ItemKind::Field, ItemKind::Param]),
+                "trace" => Some(&[ItemKind::Function]),
+                _ => None,
+            }
+        }
+
+        fn value_kinds(&self) -> Option<&[ValueKind]> {
+            match self.0 {
+                "range" => Some(&[ValueKind::Integer, ValueKind::Float]),
+                _ => None,
+            }
+        }
+
+        fn schema(&self) -> Option<AnnotationSchema> {
+            match self.0 {
+                "repr" => Some(AnnotationSchema::new().arg(ArgKind::Ident)),
+                "range" => Some(
+                    AnnotationSchema::new()
+                        .arg(ArgKind::Number)
+                        .arg(ArgKind::Number),
+                ),
+                "trace" => Some(AnnotationSchema::new()),
+                _ => None,
+            }
+        }
+    }
+
+    fn visit(
+        visitors: &mut VisitorMap<Named>,
+        annotations: &[(&str, &str)],
+    ) -> (Vec<String>, Vec<String>) {
+        visit_in("package a:b; world w {}", visitors, annotations)
+    }
+
+    /// Visits a function with `annotations` in the first interface of `wit`,
+    /// or in its world if it has none.
+    fn visit_in(
+        wit: &str,
+        visitors: &mut VisitorMap<Named>,
+        annotations: &[(&str, &str)],
+    ) -> (Vec<String>, Vec<String>) {
+        let func = Function {
+            name: "f".to_string(),
+            kind: FunctionKind::Freestanding,
+            params: Vec::new(),
+            result: None,
+            docs: Default::default(),
+            stability: Default::default(),
+            annotations: Default::default(),
+        };
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", wit).unwrap();
+        let cx = VisitContext {
+            interface: resolve.interfaces.iter().next().map(|(id, _)| id),
+            ..VisitContext::new(&resolve, resolve.select_world(&[pkg], None).unwrap())
+        };
+        let mut diagnostics = Diagnostics::new(true);
+        let contributions = visit_annotations(
+            visitors,
+            &mut diagnostics,
+            &Location::default(),
+            annotations.iter().copied(),
+            Some(&cx),
+            |visitor, args| visitor.visit_function(args, &func, &cx),
+        );
+        let messages = diagnostics.iter().map(|d| d.message.clone()).collect();
+        (contributions, messages)
+    }
+
+    #[test]
+    fn test_visitors_sharing_a_target() {
+        let mut visitors = VisitorMap::new();
+        visitors.register(visitor("trace", Some("a"), priority::DEFAULT));
+        visitors.register(visitor("trace", None, priority::DEFAULT));
+        visitors.register(visitor("trace", Some("b"), priority::DEFAULT));
+        visitors.register_default(visitor("trace", Some("builtin"), priority::DEFAULT));
+        visitors.register(visitor("skip", None, priority::DEFAULT));
+
+        let (contributions, messages) =
+            visit(&mut visitors, &[("trace", ""), ("skip", ""), ("nope", "")]);
+        assert_eq!(contributions, ["a", "b"]);
+        assert_eq!(
+            messages,
+            [
+                "annotation '#skip' is not supported on this item",
+                "no visitor registered for annotation target 'nope'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_priority_orders_contributions() {
+        let mut visitors = VisitorMap::new();
+        visitors.register(visitor("trace", Some("trace"), priority::DEFAULT));
+        visitors.register(visitor("late", Some("late"), priority::LAST));
+        visitors.register(visitor("cfg", Some("cfg"), priority::FIRST));
+        visitors.register(visitor("check", Some("check"), priority::VALIDATION));
+        visitors.register(visitor("trace", Some("trace2"), priority::DEFAULT));
+
+        let (contributions, _) = visit(
+            &mut visitors,
+            &[("late", ""), ("trace", ""), ("check", ""), ("cfg", "")],
+        );
+        assert_eq!(contributions, ["cfg", "check", "trace", "trace2", "late"]);
+    }
+
+    #[test]
+    fn test_priority_given_at_registration() {
+        let mut visitors = VisitorMap::new();
+        visitors.register(visitor("cfg", Some("cfg"), priority::FIRST));
+        visitors.register_with_priority(
+            visitor("trace", Some("trace"), priority::DEFAULT),
+            priority::FIRST - 1,
+        );
+        visitors.register_with_priority(
+            visitor("trace", Some("trace2"), priority::LAST),
+            priority::DEFAULT,
+        );
+        visitors.register(visitor("late", Some("late"), priority::LAST));
+
+        let (contributions, _) = visit(&mut visitors, &[("late", ""), ("cfg", ""), ("trace", "")]);
+        assert_eq!(contributions, ["trace", "cfg", "trace2", "late"]);
+
+        assert!(visitors.set_priority("late", priority::FIRST - 2));
+        assert!(!visitors.set_priority("nope", priority::FIRST));
+        let (contributions, _) = visit(&mut visitors, &[("late", ""), ("cfg", ""), ("trace", "")]);
+        assert_eq!(contributions, ["late", "trace", "cfg", "trace2"]);
+    }
+
+    #[test]
+    fn test_inherited_annotations() {
+        let wit = r#"
+            package a:b;
+
+            #trace(iface)
+            interface i {}
+
+            #trace(world)
+            #log
+            #plain
+            world w {
+                import i;
+            }
+        "#;
+        let mut visitors = VisitorMap::new();
+        visitors.register(inheriting("trace", Some("trace")));
+        visitors.register(inheriting("log", Some("log")));
+        visitors.register(inheriting("log", None));
+        visitors.register(visitor("plain", Some("plain"), priority::DEFAULT));
+
+        // The interface's annotation hides the world's, and visitors that
+        // don't inherit aren't offered anything.
+        let (contributions, messages) = visit_in(wit, &mut visitors, &[]);
+        assert_eq!(contributions, ["trace(iface)", "log"]);
+        assert!(messages.is_empty(), "{messages:?}");
+
+        // The item's own annotation hides the inherited one.
+        let (contributions, _) = visit_in(wit, &mut visitors, &[("trace", "own")]);
+        assert_eq!(contributions, ["trace(own)", "log"]);
+
+        let (contributions, messages) = visit_in(wit, &mut visitors, &[("no_inherit", "log")]);
+        assert_eq!(contributions, ["trace(iface)"]);
+        assert!(messages.is_empty(), "{messages:?}");
+
+        let (contributions, _) = visit_in(wit, &mut visitors, &[("no_inherit", "")]);
+        assert!(contributions.is_empty());
+    }
+
+    #[test]
+    fn test_check_annotations() {
+        let wit = r#"
+            package a:b;
+
+            #trace
+            interface api {
+                #repr(u8)
+                enum color { red }
+
+                record user {
+                    #range(0, 10)
+                    age: u32,
+                    #range(0, 10)
+                    name: string,
+                }
+
+                #repr(u8)
+                f: func();
+
+                #range(x, 1)
+                g: func(x: u32);
+
+                #range(y, 0, 1)
+                h: func(y: string);
+
+                #trace(verbose)
+                i: func();
+            }
+
+            #repr
+            world w {
+                import api;
+                export api;
+                #unknown
+                export run: func();
+            }
+        "#;
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", wit).unwrap();
+        let world = resolve.select_world(&[pkg], None).unwrap();
+        let mut visitors = VisitorMap::new();
+        for target in ["repr", "range", "trace", "note"] {
+            visitors.register(Box::new(Declared(target)));
+        }
+
+        // Problems are errors even outside of strict mode, and the interface
+        // is only checked once although it's both imported and exported.
+        let mut diagnostics = Diagnostics::new(false);
+        let mut items = world_annotated_items(&resolve, world);
+        // Arguments that don't parse can't match a schema, while visitors
+        // without one are given them raw.
+        let age = items
+            .iter()
+            .find(|item| item.location.to_string() == "a:b/api.user.age")
+            .unwrap();
+        for (target, value) in [("range", "0, \"x"), ("note", "\"x")] {
+            items.push(AnnotatedItem {
+                target: target.to_string(),
+                value: value.to_string(),
+                ..age.clone()
+            });
+        }
+        check_annotations(&visitors, &mut diagnostics, &resolve, &items);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity == crate::Severity::Error));
+        let messages = diagnostics
+            .iter()
+            .map(|d| format!("{}: {}", d.location, d.message))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "a:b/w: annotation '#repr' is not allowed on this world; it applies to: enum",
+                "a:b/api.user.name: annotation '#range' is not allowed on this field, \
+                 which holds string values; it applies to: integer, float",
+                "a:b/api.f: annotation '#repr' is not allowed on this function; \
+                 it applies to: enum",
+                "a:b/api.g.x: invalid arguments for annotation '#range': \
+                 expected 2 argument(s), found 1",
+                "a:b/api.h.y: annotation '#range' is not allowed on this parameter, \
+                 which holds string values; it applies to: integer, float",
+                "a:b/api.i: invalid arguments for annotation '#trace': \
+                 expected no arguments, found 1",
+                "a:b/api.user.age: invalid arguments for annotation '#range': \
+                 unterminated string literal",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_visitor_list_creates_visitors_per_build() {
+        let list = VisitorList::<Named>::new(|| {
+            vec![
+                visitor("trace", None, priority::DEFAULT),
+                visitor("trace", Some("a"), priority::DEFAULT),
+            ]
+        });
+        let clone = list.clone();
+
+        // Every generator built, from the options or a clone of them, gets
+        // its own visitors.
+        assert_eq!(clone.create().len(), 2);
+        assert_eq!(list.create().len(), 2);
+        assert!(VisitorList::<Named>::default().create().is_empty());
+        assert_eq!(format!("{list:?}"), "VisitorList { .. }");
+    }
+
+    #[test]
+    fn test_register_builtins() {
+        fn builtin(name: &str) -> Option<Box<Named>> {
+            match name {
+                "trace" => Some(visitor("trace", Some("builtin"), priority::DEFAULT)),
+                "cfg" => Some(visitor("cfg", Some("cfg"), priority::DEFAULT)),
+                _ => None,
+            }
+        }
+        let names = ["trace", "cfg"];
+
+        assert_eq!(parse_builtin("cfg", &names, builtin), Ok("cfg".to_string()));
+        assert_eq!(
+            parse_builtin("nope", &names, builtin),
+            Err("unknown built-in annotation visitor `nope`; expected one of: trace, cfg".into())
+        );
+
+        // The built-in visitor for `trace` is a fallback for the one already
+        // registered.
+        let mut visitors = VisitorMap::new();
+        visitors.register(visitor("trace", Some("trace"), priority::DEFAULT));
+        let mut diagnostics = Diagnostics::new(false);
+        let requested = ["trace", "nope", "cfg"].map(String::from);
+        register_builtins(&mut visitors, &mut diagnostics, &requested, &names, builtin);
+        assert_eq!(diagnostics.error_count(), 1);
+
+        let (contributions, _) = visit(&mut visitors, &[("trace", ""), ("cfg", "")]);
+        assert_eq!(contributions, ["trace", "cfg"]);
+    }
+}
@@ -0,0 +1,523 @@
+use std::fmt;
+use std::ops::Deref;
+
+/// The parsed body of an annotation, e.g. the `rename_all = "camelCase"` in
+/// `#serde(rename_all = "camelCase")`.
+///
+/// Arguments are a comma separated list of positional values and
+/// `key = value` pairs. Parsing is lenient: anything that isn't a literal,
+/// an identifier or a nested list is kept verbatim as an
+/// [`AnnotationValue::Expr`], so free-form annotations such as
+/// `#assert(amount > 0, "msg")` still work. The raw text is always available
+/// through [`AnnotationArgs::as_str`] or by dereferencing to `str`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnnotationArgs {
+    raw: String,
+    args: Vec<AnnotationArg>,
+}
+
+/// A single argument of an annotation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationArg {
+    /// The key of a `key = value` argument, `None` for positional arguments.
+    pub name: Option<String>,
+    pub value: AnnotationValue,
+}
+
+/// The value of an annotation argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnnotationValue {
+    /// A bare identifier or path, e.g. `Debug` or `serde::Serialize`.
+    Ident(String),
+    /// A string literal with its escapes resolved.
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    /// A parenthesized or bracketed list, e.g. `(18, 120)`.
+    List(Vec<AnnotationArg>),
+    /// Any other expression, kept verbatim, e.g. `amount > 0`.
+    Expr(String),
+}
+
+/// An error produced when the body of an annotation is malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotationParseError {
+    pub message: String,
+    /// Byte offset of the error within the annotation body.
+    pub offset: usize,
+}
+
+impl AnnotationArgs {
+    /// Parses the body of an annotation.
+    ///
+    /// Only unbalanced delimiters and unterminated string literals are
+    /// errors; everything else parses into some [`AnnotationValue`].
+    pub fn parse(raw: &str) -> Result<AnnotationArgs, AnnotationParseError> {
+        Ok(AnnotationArgs {
+            raw: raw.to_string(),
+            args: parse_list(raw, 0)?,
+        })
+    }
+
+    /// Wraps `raw` without any structured arguments.
+    ///
+    /// Used as a fallback when `raw` fails to parse so that visitors still see
+    /// the annotation text.
+    pub fn unparsed(raw: &str) -> AnnotationArgs {
+        AnnotationArgs {
+            raw: raw.to_string(),
+            args: Vec::new(),
+        }
+    }
+
+    /// The annotation body exactly as written.
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// All arguments in source order.
+    pub fn args(&self) -> &[AnnotationArg] {
+        &self.args
+    }
+
+    /// Values of the positional arguments in source order.
+    pub fn positional(&self) -> impl Iterator<Item = &AnnotationValue> {
+        self.args
+            .iter()
+            .filter(|arg| arg.name.is_none())
+            .map(|arg| &arg.value)
+    }
+
+    /// The value of the `name = value` argument, if present.
+    pub fn named(&self, name: &str) -> Option<&AnnotationValue> {
+        self.args
+            .iter()
+            .find(|arg| arg.name.as_deref() == Some(name))
+            .map(|arg| &arg.value)
+    }
+
+    /// Splits off the first argument, returning it and the remaining
+    /// arguments.
+    pub fn split_first(&self) -> Option<(&AnnotationArg, AnnotationArgs)> {
+        let (first, rest) = self.args.split_first()?;
+        // The raw text of the remaining arguments starts after the comma
+        // ending the first argument.
+        let pieces = split_top_level(&self.raw, 0).ok()?;
+        let first_piece = pieces.iter().position(|(_, p)| !p.trim().is_empty())?;
+        let raw = match pieces.get(first_piece + 1) {
+            Some((start, _)) => self.raw[*start..].trim(),
+            None => "",
+        };
+        Some((
+            first,
+            AnnotationArgs {
+                raw: raw.to_string(),
+                args: rest.to_vec(),
+            },
+        ))
+    }
+}
+
+impl Deref for AnnotationArgs {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl fmt::Display for AnnotationArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl AnnotationValue {
+    /// The text of an identifier or string literal.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AnnotationValue::Ident(s) | AnnotationValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            AnnotationValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AnnotationValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[AnnotationArg]> {
+        match self {
+            AnnotationValue::List(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Values are displayed as source text, with string literals quoted and
+/// escaped, so they can be pasted back into generated code.
+impl fmt::Display for AnnotationValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnnotationValue::Ident(s) | AnnotationValue::Expr(s) => f.write_str(s),
+            AnnotationValue::String(s) => write!(f, "{s:?}"),
+            AnnotationValue::Integer(i) => write!(f, "{i}"),
+            AnnotationValue::Float(x) => write!(f, "{x:?}"),
+            AnnotationValue::Bool(b) => write!(f, "{b}"),
+            AnnotationValue::List(items) => {
+                f.write_str("(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+impl fmt::Display for AnnotationArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} = {}", self.value),
+            None => write!(f, "{}", self.value),
+        }
+    }
+}
+
+impl AnnotationParseError {
+    fn new(message: impl Into<String>, offset: usize) -> AnnotationParseError {
+        AnnotationParseError {
+            message: message.into(),
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for AnnotationParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for AnnotationParseError {}
+
+/// Parses a comma separated list of arguments; `base` is the offset of `src`
+/// within the annotation body.
+fn parse_list(src: &str, base: usize) -> Result<Vec<AnnotationArg>, AnnotationParseError> {
+    let mut args = Vec::new();
+    for (start, piece) in split_top_level(src, base)? {
+        let trimmed = piece.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let offset = start + (piece.len() - piece.trim_start().len());
+        args.push(parse_arg(trimmed, offset)?);
+    }
+    Ok(args)
+}
+
+/// Splits `src` on commas that aren't nested in delimiters or string
+/// literals, checking that delimiters are balanced along the way.
+fn split_top_level(src: &str, base: usize) -> Result<Vec<(usize, &str)>, AnnotationParseError> {
+    let mut pieces = Vec::new();
+    let mut open: Vec<(char, usize)> = Vec::new();
+    let mut piece_start = 0;
+    let mut chars = src.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut escaped = false;
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    return Err(AnnotationParseError::new(
+                        "unterminated string literal",
+                        base + i,
+                    ));
+                }
+            }
+            '(' | '[' | '{' => open.push((c, i)),
+            ')' | ']' | '}' => match open.pop() {
+                Some((opener, _)) if closing(opener) == c => {}
+                _ => {
+                    return Err(AnnotationParseError::new(
+                        format!("unbalanced `{c}`"),
+                        base + i,
+                    ))
+                }
+            },
+            ',' if open.is_empty() => {
+                pieces.push((base + piece_start, &src[piece_start..i]));
+                piece_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if let Some((opener, i)) = open.pop() {
+        return Err(AnnotationParseError::new(
+            format!("unclosed `{opener}`"),
+            base + i,
+        ));
+    }
+    pieces.push((base + piece_start, &src[piece_start..]));
+    Ok(pieces)
+}
+
+fn closing(opener: char) -> char {
+    match opener {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+fn parse_arg(piece: &str, offset: usize) -> Result<AnnotationArg, AnnotationParseError> {
+    if let Some(eq) = find_assignment(piece) {
+        let key = piece[..eq].trim();
+        if is_ident(key) {
+            let rest = &piece[eq + 1..];
+            let value_offset = offset + eq + 1 + (rest.len() - rest.trim_start().len());
+            return Ok(AnnotationArg {
+                name: Some(key.to_string()),
+                value: parse_value(rest.trim(), value_offset)?,
+            });
+        }
+    }
+    Ok(AnnotationArg {
+        name: None,
+        value: parse_value(piece, offset)?,
+    })
+}
+
+/// Finds the `=` of a `key = value` argument, skipping `==` and `=>`.
+fn find_assignment(piece: &str) -> Option<usize> {
+    let eq = piece.find('=')?;
+    match piece[eq + 1..].chars().next() {
+        Some('=') | Some('>') => None,
+        _ => Some(eq),
+    }
+}
+
+fn parse_value(text: &str, offset: usize) -> Result<AnnotationValue, AnnotationParseError> {
+    if let Some(s) = parse_string(text) {
+        return Ok(AnnotationValue::String(s));
+    }
+    if let Some(inner) = strip_list_delimiters(text) {
+        return Ok(AnnotationValue::List(parse_list(inner, offset + 1)?));
+    }
+    match text {
+        "true" => return Ok(AnnotationValue::Bool(true)),
+        "false" => return Ok(AnnotationValue::Bool(false)),
+        _ => {}
+    }
+    if let Ok(i) = text.parse::<i64>() {
+        return Ok(AnnotationValue::Integer(i));
+    }
+    // `f64::from_str` also accepts words like `inf` and `NaN`, which are
+    // identifiers here.
+    if text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        if let Ok(x) = text.parse::<f64>() {
+            return Ok(AnnotationValue::Float(x));
+        }
+    }
+    if is_path(text) {
+        return Ok(AnnotationValue::Ident(text.to_string()));
+    }
+    Ok(AnnotationValue::Expr(text.to_string()))
+}
+
+/// Returns the contents of `text` if it is a single string literal.
+fn parse_string(text: &str) -> Option<String> {
+    let body = text.strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (i + 1 == body.len()).then_some(value),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                '0' => value.push('\0'),
+                other => value.push(other),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Returns the contents of `text` if it is a single `(...)` or `[...]` list.
+fn strip_list_delimiters(text: &str) -> Option<&str> {
+    let close = match text.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let inner = text.strip_suffix(close)?.get(1..)?;
+    // `(a) + (b)` starts and ends with parentheses but isn't a list, which
+    // shows up as the inner text not being balanced on its own.
+    split_top_level(inner, 0).ok()?;
+    Some(inner)
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Identifiers and `::`-separated paths such as `serde::Serialize`.
+fn is_path(s: &str) -> bool {
+    !s.is_empty() && s.split("::").all(is_ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positional(args: &AnnotationArgs) -> Vec<AnnotationValue> {
+        args.positional().cloned().collect()
+    }
+
+    #[test]
+    fn test_positional_and_named() {
+        let args =
+            AnnotationArgs::parse("Debug, serde::Serialize, rename_all = \"camelCase\"").unwrap();
+        assert_eq!(
+            positional(&args),
+            [
+                AnnotationValue::Ident("Debug".to_string()),
+                AnnotationValue::Ident("serde::Serialize".to_string()),
+            ]
+        );
+        assert_eq!(
+            args.named("rename_all"),
+            Some(&AnnotationValue::String("camelCase".to_string()))
+        );
+        assert_eq!(args.named("missing"), None);
+        assert_eq!(
+            args.as_str(),
+            "Debug, serde::Serialize, rename_all = \"camelCase\""
+        );
+    }
+
+    #[test]
+    fn test_literals_and_lists() {
+        let args = AnnotationArgs::parse("(18, 120), [1.5, -2], true, \"a \\\"b\\\"\"").unwrap();
+        assert_eq!(
+            positional(&args),
+            [
+                AnnotationValue::List(vec![
+                    AnnotationArg {
+                        name: None,
+                        value: AnnotationValue::Integer(18),
+                    },
+                    AnnotationArg {
+                        name: None,
+                        value: AnnotationValue::Integer(120),
+                    },
+                ]),
+                AnnotationValue::List(vec![
+                    AnnotationArg {
+                        name: None,
+                        value: AnnotationValue::Float(1.5),
+                    },
+                    AnnotationArg {
+                        name: None,
+                        value: AnnotationValue::Integer(-2),
+                    },
+                ]),
+                AnnotationValue::Bool(true),
+                AnnotationValue::String("a \"b\"".to_string()),
+            ]
+        );
+        assert_eq!(args.args()[0].to_string(), "(18, 120)");
+    }
+
+    #[test]
+    fn test_free_form_expressions() {
+        let args = AnnotationArgs::parse("amount > 0, \"amount, if set\"").unwrap();
+        assert_eq!(
+            positional(&args),
+            [
+                AnnotationValue::Expr("amount > 0".to_string()),
+                AnnotationValue::String("amount, if set".to_string()),
+            ]
+        );
+
+        let args = AnnotationArgs::parse("a == b, (x) + (y), f(1, 2)").unwrap();
+        assert_eq!(
+            positional(&args),
+            [
+                AnnotationValue::Expr("a == b".to_string()),
+                AnnotationValue::Expr("(x) + (y)".to_string()),
+                AnnotationValue::Expr("f(1, 2)".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_first() {
+        let args = AnnotationArgs::parse("age, 18, max = 120").unwrap();
+        let (first, rest) = args.split_first().unwrap();
+        assert_eq!(first.value, AnnotationValue::Ident("age".to_string()));
+        assert_eq!(rest.as_str(), "18, max = 120");
+        assert_eq!(positional(&rest), [AnnotationValue::Integer(18)]);
+        assert_eq!(rest.named("max"), Some(&AnnotationValue::Integer(120)));
+
+        let args = AnnotationArgs::parse("data").unwrap();
+        let (_, rest) = args.split_first().unwrap();
+        assert!(rest.is_empty());
+        assert!(rest.args().is_empty());
+
+        assert!(AnnotationArgs::parse("").unwrap().split_first().is_none());
+    }
+
+    #[test]
+    fn test_empty() {
+        let args = AnnotationArgs::parse("").unwrap();
+        assert!(args.args().is_empty());
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = AnnotationArgs::parse("a, (b, c").unwrap_err();
+        assert_eq!(err.offset, 3);
+        assert!(err.message.contains("unclosed"));
+
+        let err = AnnotationArgs::parse("a)").unwrap_err();
+        assert_eq!(err.offset, 1);
+
+        let err = AnnotationArgs::parse("rename = \"oops").unwrap_err();
+        assert_eq!(err.offset, 9);
+        assert!(err.message.contains("unterminated"));
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt;
+
+use wit_parser::*;
+
+use super::{param_target, AnnotationArgs};
+use crate::diagnostics::Location;
+
+/// The kinds of WIT items annotations can be placed on, each matching a
+/// [`super::Visitor`] hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ItemKind {
+    World,
+    Interface,
+    Record,
+    Variant,
+    Enum,
+    Flags,
+    Resource,
+    /// A named alias of another type, e.g. `type counter = u32;`.
+    Alias,
+    Tuple,
+    List,
+    Option,
+    Result,
+    /// A named `future` or `stream` type, which no hook is called for.
+    AsyncType,
+    Field,
+    /// A case of a variant or of an enum.
+    Case,
+    Flag,
+    Function,
+    /// A parameter of a function, targeted by a function annotation naming
+    /// it first, see [`super::param_target`].
+    Param,
+}
+
+impl ItemKind {
+    /// The kind of the type definition `kind`.
+    pub fn of_type(kind: &TypeDefKind) -> ItemKind {
+        match kind {
+            TypeDefKind::Record(_) => ItemKind::Record,
+            TypeDefKind::Variant(_) => ItemKind::Variant,
+            TypeDefKind::Enum(_) => ItemKind::Enum,
+            TypeDefKind::Flags(_) => ItemKind::Flags,
+            TypeDefKind::Resource => ItemKind::Resource,
+            TypeDefKind::Tuple(_) => ItemKind::Tuple,
+            TypeDefKind::List(_) | TypeDefKind::FixedSizeList(..) => ItemKind::List,
+            TypeDefKind::Option(_) => ItemKind::Option,
+            TypeDefKind::Result(_) => ItemKind::Result,
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => ItemKind::AsyncType,
+            TypeDefKind::Type(_) | TypeDefKind::Handle(_) | TypeDefKind::Unknown => ItemKind::Alias,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemKind::World => "world",
+            ItemKind::Interface => "interface",
+            ItemKind::Record => "record",
+            ItemKind::Variant => "variant",
+            ItemKind::Enum => "enum",
+            ItemKind::Flags => "flags",
+            ItemKind::Resource => "resource",
+            ItemKind::Alias => "alias",
+            ItemKind::Tuple => "tuple",
+            ItemKind::List => "list",
+            ItemKind::Option => "option",
+            ItemKind::Result => "result",
+            ItemKind::AsyncType => "future or stream",
+            ItemKind::Field => "field",
+            ItemKind::Case => "case",
+            ItemKind::Flag => "flag",
+            ItemKind::Function => "function",
+            ItemKind::Param => "parameter",
+        }
+    }
+}
+
+impl fmt::Display for ItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A `#target(value)` annotation found in a [`Resolve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedItem {
+    /// The annotated item, located like diagnostics about it are.
+    pub location: Location,
+    pub kind: ItemKind,
+    pub target: String,
+    /// The raw body of the annotation, empty for a bare `#target`.
+    pub value: String,
+    /// The type of the value of an annotated field, case or parameter.
+    pub ty: Option<Type>,
+}
+
+/// Lists every annotation in `resolve`, including those of dependencies,
+/// package by package in definition order.
+pub fn annotated_items(resolve: &Resolve) -> Vec<AnnotatedItem> {
+    let mut items = Vec::new();
+    for (_, package) in resolve.packages.iter() {
+        for (_, id) in package.interfaces.iter() {
+            push_interface(resolve, *id, &mut items);
+        }
+        for (_, id) in package.worlds.iter() {
+            // Named interfaces are listed with their package.
+            push_world(resolve, *id, &mut items, |id, items| {
+                if resolve.interfaces[id].name.is_none() {
+                    push_interface(resolve, id, items);
+                }
+            });
+        }
+    }
+    items
+}
+
+/// Lists the annotations bindings for `world` are generated from: those of
+/// the world, of the interfaces it imports and exports, and of its own
+/// functions and types.
+pub fn world_annotated_items(resolve: &Resolve, world: WorldId) -> Vec<AnnotatedItem> {
+    let mut items = Vec::new();
+    let mut seen = Vec::new();
+    push_world(resolve, world, &mut items, |id, items| {
+        // An interface can be both imported and exported.
+        if !seen.contains(&id) {
+            seen.push(id);
+            push_interface(resolve, id, items);
+        }
+    });
+    items
+}
+
+fn push_world(
+    resolve: &Resolve,
+    id: WorldId,
+    items: &mut Vec<AnnotatedItem>,
+    mut interface: impl FnMut(InterfaceId, &mut Vec<AnnotatedItem>),
+) {
+    let world = &resolve.worlds[id];
+    let location = Location::for_world(resolve, id);
+    push(items, &location, ItemKind::World, world.annotations.iter());
+    for item in world.imports.values().chain(world.exports.values()) {
+        match item {
+            WorldItem::Interface { id, .. } => interface(*id, items),
+            WorldItem::Function(func) => push_function(&location, func, items),
+            WorldItem::Type(id) => push_type(resolve, *id, items),
+        }
+    }
+}
+
+fn push_interface(resolve: &Resolve, id: InterfaceId, items: &mut Vec<AnnotatedItem>) {
+    let interface = &resolve.interfaces[id];
+    let location = Location::for_interface(resolve, id);
+    push(
+        items,
+        &location,
+        ItemKind::Interface,
+        interface.annotations.iter(),
+    );
+    for (_, ty) in interface.types.iter() {
+        push_type(resolve, *ty, items);
+    }
+    for (_, func) in interface.functions.iter() {
+        push_function(&location, func, items);
+    }
+}
+
+fn push_type(resolve: &Resolve, id: TypeId, items: &mut Vec<AnnotatedItem>) {
+    let ty = &resolve.types[id];
+    let location = Location::for_type(resolve, id);
+    push(
+        items,
+        &location,
+        ItemKind::of_type(&ty.kind),
+        ty.annotations.iter(),
+    );
+    match &ty.kind {
+        TypeDefKind::Record(record) => {
+            for field in record.fields.iter() {
+                let location = location.member(&field.name);
+                let annotations = field.annotations.iter();
+                push_value(
+                    items,
+                    &location,
+                    ItemKind::Field,
+                    Some(field.ty),
+                    annotations,
+                );
+            }
+        }
+        TypeDefKind::Variant(variant) => {
+            for case in variant.cases.iter() {
+                let location = location.member(&case.name);
+                let annotations = case.annotations.iter();
+                push_value(items, &location, ItemKind::Case, case.ty, annotations);
+            }
+        }
+        TypeDefKind::Enum(enum_) => {
+            for case in enum_.cases.iter() {
+                let location = location.member(&case.name);
+                push(items, &location, ItemKind::Case, case.annotations.iter());
+            }
+        }
+        TypeDefKind::Flags(flags) => {
+            for flag in flags.flags.iter() {
+                let location = location.member(&flag.name);
+                push(items, &location, ItemKind::Flag, flag.annotations.iter());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn push_function(scope: &Location, func: &Function, items: &mut Vec<AnnotatedItem>) {
+    let location = scope.member(&func.name);
+    for (target, value) in func.annotations.iter() {
+        let args = AnnotationArgs::parse(value).unwrap_or_else(|_| AnnotationArgs::unparsed(value));
+        let (location, kind, ty) = match param_target(func, &args) {
+            Some((index, _)) => {
+                let (name, ty) = &func.params[index];
+                (location.member(name), ItemKind::Param, Some(*ty))
+            }
+            None => (location.clone(), ItemKind::Function, None),
+        };
+        push_value(items, &location, kind, ty, [(target, value)]);
+    }
+}
+
+fn push<K: ToString, V: ToString>(
+    items: &mut Vec<AnnotatedItem>,
+    location: &Location,
+    kind: ItemKind,
+    annotations: impl IntoIterator<Item = (K, V)>,
+) {
+    push_value(items, location, kind, None, annotations);
+}
+
+fn push_value<K: ToString, V: ToString>(
+    items: &mut Vec<AnnotatedItem>,
+    location: &Location,
+    kind: ItemKind,
+    ty: Option<Type>,
+    annotations: impl IntoIterator<Item = (K, V)>,
+) {
+    for (target, value) in annotations {
+        items.push(AnnotatedItem {
+            location: location.clone(),
+            kind,
+            target: target.to_string(),
+            value: value.to_string(),
+            ty,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotated_items() {
+        let mut resolve = Resolve::default();
+        resolve
+            .push_str(
+                "test.wit",
+                r#"
+                package a:b;
+
+                #trace
+                interface api {
+                    #derive(Hash)
+                    record point {
+                        #range(0, 10)
+                        x: u32,
+                    }
+
+                    enum color {
+                        #default
+                        red,
+                    }
+
+                    #deprecated("use area2")
+                    #range(p, 0, 10)
+                    area: func(p: point) -> u32;
+                }
+
+                #cfg(feature = "w")
+                world w {
+                    import api;
+                    #export
+                    export run: func();
+                }
+                "#,
+            )
+            .unwrap();
+
+        let items = annotated_items(&resolve)
+            .into_iter()
+            .map(|item| {
+                let location = item.location.to_string();
+                (location, item.kind, item.target, item.value)
+            })
+            .collect::<Vec<_>>();
+        let expected = [
+            ("a:b/api", ItemKind::Interface, "trace", ""),
+            ("a:b/api.point", ItemKind::Record, "derive", "Hash"),
+            ("a:b/api.point.x", ItemKind::Field, "range", "0, 10"),
+            ("a:b/api.color.red", ItemKind::Case, "default", ""),
+            (
+                "a:b/api.area",
+                ItemKind::Function,
+                "deprecated",
+                "\"use area2\"",
+            ),
+            ("a:b/api.area.p", ItemKind::Param, "range", "p, 0, 10"),
+            ("a:b/w", ItemKind::World, "cfg", "feature = \"w\""),
+            ("a:b/w.run", ItemKind::Function, "export", ""),
+        ];
+        let expected = expected
+            .iter()
+            .map(|(location, kind, target, value)| {
+                (
+                    location.to_string(),
+                    *kind,
+                    target.to_string(),
+                    value.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(items, expected);
+    }
+}
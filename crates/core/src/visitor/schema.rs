@@ -0,0 +1,348 @@
+use std::fmt;
+
+use wit_parser::*;
+
+use super::{AnnotationArgs, AnnotationValue};
+
+/// The kinds of literals an annotation argument can be, for
+/// [`AnnotationSchema`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A bare identifier or path, e.g. `u8` or `crate::invalid_input`.
+    Ident,
+    String,
+    Integer,
+    /// An integer or a float.
+    Number,
+    /// A number matching the values of the annotated field, case or
+    /// parameter: an integer for integer values, and an integer or a float
+    /// otherwise.
+    Value,
+    Bool,
+    List,
+    /// Any value, including free-form expressions.
+    Any,
+}
+
+impl ArgKind {
+    /// Whether an argument with the value `value` is of this kind.
+    pub fn accepts(&self, value: &AnnotationValue) -> bool {
+        matches!(
+            (self, value),
+            (ArgKind::Any, _)
+                | (ArgKind::Ident, AnnotationValue::Ident(_))
+                | (ArgKind::String, AnnotationValue::String(_))
+                | (ArgKind::Integer, AnnotationValue::Integer(_))
+                | (
+                    ArgKind::Number | ArgKind::Value,
+                    AnnotationValue::Integer(_) | AnnotationValue::Float(_)
+                )
+                | (ArgKind::Bool, AnnotationValue::Bool(_))
+                | (ArgKind::List, AnnotationValue::List(_))
+        )
+    }
+
+    /// The kind an argument must be on an item holding `value`s, which only
+    /// differs from this kind for [`ArgKind::Value`].
+    pub fn for_value(&self, value: Option<ValueKind>) -> ArgKind {
+        match (self, value) {
+            (ArgKind::Value, Some(ValueKind::Integer)) => ArgKind::Integer,
+            (ArgKind::Value, _) => ArgKind::Number,
+            (kind, _) => *kind,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArgKind::Ident => "an identifier",
+            ArgKind::String => "a string",
+            ArgKind::Integer => "an integer",
+            ArgKind::Number | ArgKind::Value => "a number",
+            ArgKind::Bool => "a boolean",
+            ArgKind::List => "a list",
+            ArgKind::Any => "any value",
+        }
+    }
+}
+
+impl fmt::Display for ArgKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Describes `value` for error messages, e.g. "an integer `3`".
+fn describe(value: &AnnotationValue) -> String {
+    let kind = match value {
+        AnnotationValue::Ident(_) => "an identifier",
+        AnnotationValue::String(_) => "a string",
+        AnnotationValue::Integer(_) => "an integer",
+        AnnotationValue::Float(_) => "a float",
+        AnnotationValue::Bool(_) => "a boolean",
+        AnnotationValue::List(_) => "a list",
+        AnnotationValue::Expr(_) => "an expression",
+    };
+    format!("{kind} `{value}`")
+}
+
+/// The arguments an annotation takes, declared by [`super::Visitor::schema`]:
+/// how many positional arguments there are and what they are, and which
+/// `key = value` arguments are allowed.
+///
+/// The schema is built by chaining, e.g. `#range(18, 120)` is described by
+/// `AnnotationSchema::new().arg(ArgKind::Number).arg(ArgKind::Number)`. A
+/// new schema accepts no arguments at all.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationSchema {
+    required: Vec<ArgKind>,
+    optional: Vec<ArgKind>,
+    rest: Option<ArgKind>,
+    keys: Vec<(String, ArgKind)>,
+}
+
+impl AnnotationSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a required positional argument, after the existing ones.
+    ///
+    /// Required arguments come before optional ones, so this must not be
+    /// called after [`AnnotationSchema::optional_arg`].
+    pub fn arg(mut self, kind: ArgKind) -> Self {
+        assert!(
+            self.optional.is_empty(),
+            "required arguments must come before optional ones"
+        );
+        self.required.push(kind);
+        self
+    }
+
+    /// Adds an optional positional argument, after the existing ones.
+    pub fn optional_arg(mut self, kind: ArgKind) -> Self {
+        self.optional.push(kind);
+        self
+    }
+
+    /// Accepts any number of positional arguments of `kind` after the
+    /// declared ones.
+    pub fn rest(mut self, kind: ArgKind) -> Self {
+        self.rest = Some(kind);
+        self
+    }
+
+    /// Accepts a `name = value` argument.
+    pub fn key(mut self, name: &str, kind: ArgKind) -> Self {
+        self.keys.push((name.to_string(), kind));
+        self
+    }
+
+    /// Checks `args` against the schema, returning a description of the
+    /// first problem found.
+    pub fn check(&self, args: &AnnotationArgs) -> Result<(), String> {
+        self.check_for(args, None)
+    }
+
+    /// Like [`AnnotationSchema::check`], for an annotation on a field, case
+    /// or parameter holding `value`s, which [`ArgKind::Value`] arguments
+    /// must match.
+    pub fn check_for(
+        &self,
+        args: &AnnotationArgs,
+        value_kind: Option<ValueKind>,
+    ) -> Result<(), String> {
+        let positional = args.positional().collect::<Vec<_>>();
+        let min = self.required.len();
+        let max = min + self.optional.len();
+        let found = positional.len();
+        if found < min || (found > max && self.rest.is_none()) {
+            let expected = match (min, max) {
+                (0, 0) => "no arguments".to_string(),
+                (min, _) if self.rest.is_some() => format!("at least {min} argument(s)"),
+                (min, max) if min == max => format!("{min} argument(s)"),
+                (min, max) => format!("{min} to {max} arguments"),
+            };
+            return Err(format!("expected {expected}, found {found}"));
+        }
+        let kinds = self.required.iter().chain(&self.optional);
+        let kinds = kinds.map(Some).chain(std::iter::repeat(self.rest.as_ref()));
+        for (i, (value, kind)) in positional.iter().zip(kinds).enumerate() {
+            let kind = kind
+                .expect("argument count was checked")
+                .for_value(value_kind);
+            if !kind.accepts(value) {
+                return Err(format!(
+                    "argument {} must be {kind}, found {}",
+                    i + 1,
+                    describe(value)
+                ));
+            }
+        }
+        for arg in args.args() {
+            let Some(name) = &arg.name else {
+                continue;
+            };
+            let Some((_, kind)) = self.keys.iter().find(|(key, _)| key == name) else {
+                if self.keys.is_empty() {
+                    return Err(format!("unexpected key `{name}`"));
+                }
+                let keys = self.keys.iter().map(|(key, _)| format!("`{key}`"));
+                return Err(format!(
+                    "unknown key `{name}`; expected one of: {}",
+                    keys.collect::<Vec<_>>().join(", ")
+                ));
+            };
+            let kind = kind.for_value(value_kind);
+            if !kind.accepts(&arg.value) {
+                return Err(format!(
+                    "`{name}` must be {kind}, found {}",
+                    describe(&arg.value)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The kinds of values fields, cases and parameters hold, for
+/// [`super::Visitor::value_kinds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    /// A signed or unsigned integer of any width.
+    Integer,
+    Float,
+    Char,
+    String,
+    List,
+    /// Any other type, e.g. a record or a resource.
+    Other,
+}
+
+impl ValueKind {
+    /// The kind of values of type `ty`, looking through aliases.
+    pub fn of(resolve: &Resolve, ty: &Type) -> ValueKind {
+        match ty {
+            Type::Bool => ValueKind::Bool,
+            Type::U8
+            | Type::U16
+            | Type::U32
+            | Type::U64
+            | Type::S8
+            | Type::S16
+            | Type::S32
+            | Type::S64 => ValueKind::Integer,
+            Type::F32 | Type::F64 => ValueKind::Float,
+            Type::Char => ValueKind::Char,
+            Type::String => ValueKind::String,
+            Type::Id(id) => match &resolve.types[*id].kind {
+                TypeDefKind::Type(ty) => ValueKind::of(resolve, ty),
+                TypeDefKind::List(_) => ValueKind::List,
+                _ => ValueKind::Other,
+            },
+            _ => ValueKind::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValueKind::Bool => "bool",
+            ValueKind::Integer => "integer",
+            ValueKind::Float => "float",
+            ValueKind::Char => "char",
+            ValueKind::String => "string",
+            ValueKind::List => "list",
+            ValueKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(schema: &AnnotationSchema, raw: &str) -> Result<(), String> {
+        schema.check(&AnnotationArgs::parse(raw).unwrap())
+    }
+
+    #[test]
+    fn test_schema() {
+        let range = AnnotationSchema::new()
+            .arg(ArgKind::Number)
+            .arg(ArgKind::Number);
+        assert_eq!(check(&range, "18, 120.5"), Ok(()));
+        assert_eq!(
+            check(&range, "18"),
+            Err("expected 2 argument(s), found 1".to_string())
+        );
+        assert_eq!(
+            check(&range, "\"a\", 1"),
+            Err("argument 1 must be a number, found a string `\"a\"`".to_string())
+        );
+        assert_eq!(
+            check(&range, "1, 2, max = 3"),
+            Err("unexpected key `max`".to_string())
+        );
+
+        let flag = AnnotationSchema::new();
+        assert_eq!(check(&flag, ""), Ok(()));
+        assert_eq!(
+            check(&flag, "x"),
+            Err("expected no arguments, found 1".to_string())
+        );
+
+        let deprecated = AnnotationSchema::new()
+            .optional_arg(ArgKind::String)
+            .key("since", ArgKind::String)
+            .key("note", ArgKind::String);
+        assert_eq!(check(&deprecated, ""), Ok(()));
+        assert_eq!(check(&deprecated, "since = \"1.0\""), Ok(()));
+        assert_eq!(
+            check(&deprecated, "\"a\", \"b\""),
+            Err("expected 0 to 1 arguments, found 2".to_string())
+        );
+        assert_eq!(
+            check(&deprecated, "reason = \"x\""),
+            Err("unknown key `reason`; expected one of: `since`, `note`".to_string())
+        );
+        assert_eq!(
+            check(&deprecated, "since = 1"),
+            Err("`since` must be a string, found an integer `1`".to_string())
+        );
+
+        let derive = AnnotationSchema::new()
+            .arg(ArgKind::Ident)
+            .rest(ArgKind::Ident);
+        assert_eq!(check(&derive, "Debug, serde::Serialize"), Ok(()));
+        assert_eq!(
+            check(&derive, ""),
+            Err("expected at least 1 argument(s), found 0".to_string())
+        );
+        assert_eq!(
+            check(&derive, "Debug, 3"),
+            Err("argument 2 must be an identifier, found an integer `3`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_value_args() {
+        let range = AnnotationSchema::new()
+            .arg(ArgKind::Value)
+            .arg(ArgKind::Value);
+        let args = AnnotationArgs::parse("1.5, 10").unwrap();
+        assert_eq!(range.check(&args), Ok(()));
+        assert_eq!(range.check_for(&args, Some(ValueKind::Float)), Ok(()));
+        assert_eq!(
+            range.check_for(&args, Some(ValueKind::Integer)),
+            Err("argument 1 must be an integer, found a float `1.5`".to_string())
+        );
+        let args = AnnotationArgs::parse("0, 100").unwrap();
+        assert_eq!(range.check_for(&args, Some(ValueKind::Integer)), Ok(()));
+    }
+}
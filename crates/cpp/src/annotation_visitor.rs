@@ -0,0 +1,395 @@
+use wit_bindgen_core::visitor::{priority, AnnotationSchema, AnnotationValue, ArgKind, ItemKind};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, ContributionTypes, VisitContext, Visitor};
+
+/// Contributions for type definitions
+#[derive(Default, Debug, Clone)]
+pub struct CppTypeContribution {
+    /// Attributes to add to the type, e.g. `nodiscard` or
+    /// `deprecated("...")`, rendered as `[[...]]`
+    pub attributes: Vec<String>,
+
+    /// Member declarations to add to the generated struct or class, e.g.
+    /// `bool operator==(const Point&) const = default;` (only used for
+    /// records, variants and resources)
+    pub members: Vec<String>,
+}
+
+impl CppTypeContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add a member function, operator or other member declaration
+    pub fn add_member(&mut self, code: impl Into<String>) {
+        self.members.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.members.is_empty()
+    }
+}
+
+/// Contributions for struct fields
+#[derive(Default, Debug, Clone)]
+pub struct CppFieldContribution {
+    /// Field-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl CppFieldContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for variant cases, enum cases and flags
+#[derive(Default, Debug, Clone)]
+pub struct CppVariantCaseContribution {
+    /// Case-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl CppVariantCaseContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for functions
+#[derive(Default, Debug, Clone)]
+pub struct CppFunctionContribution {
+    /// Attributes to add to the function's declaration in the header
+    pub attributes: Vec<String>,
+
+    /// Statements to run in the export wrapper after the arguments have been
+    /// lifted, just before the implementation is called. The arguments are
+    /// in scope under their WIT names.
+    pub body_prefix: Vec<String>,
+
+    /// Statements to run in the export wrapper just before it returns
+    pub body_suffix: Vec<String>,
+}
+
+impl CppFunctionContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add code to run before the implementation is called
+    pub fn add_body_prefix(&mut self, code: impl Into<String>) {
+        self.body_prefix.push(code.into());
+    }
+
+    /// Add code to run before the export wrapper returns
+    pub fn add_body_suffix(&mut self, code: impl Into<String>) {
+        self.body_suffix.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.body_prefix.is_empty() && self.body_suffix.is_empty()
+    }
+}
+
+/// Contributions for the header of an interface, or of the world
+#[derive(Default, Debug, Clone)]
+pub struct CppModuleContribution {
+    /// Headers to `#include`, e.g. `<cassert>` or `"my_header.h"`
+    pub includes: Vec<String>,
+}
+
+impl CppModuleContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an include
+    pub fn add_include(&mut self, include: impl Into<String>) {
+        self.includes.push(include.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty()
+    }
+}
+
+/// The family of C++ contribution types.
+pub struct CppContributions;
+
+impl ContributionTypes for CppContributions {
+    type Type = CppTypeContribution;
+    type Field = CppFieldContribution;
+    type VariantCase = CppVariantCaseContribution;
+    type Function = CppFunctionContribution;
+    type Module = CppModuleContribution;
+    // Annotations naming a parameter are only offered to `visit_function`.
+    type Param = ();
+}
+
+/// C++-specific visitor type alias.
+pub type CppVisitor = dyn Visitor<Contributions = CppContributions>;
+
+/// Renders `attributes` as a single `[[...]]` attribute specifier, or nothing
+/// if there are none.
+pub(crate) fn attribute_specifier(attributes: &[String]) -> String {
+    if attributes.is_empty() {
+        String::new()
+    } else {
+        format!("[[{}]]", attributes.join(", "))
+    }
+}
+
+/// The names of all built-in annotation visitors.
+pub const BUILTIN_ANNOTATIONS: &[&str] = &["deprecated", "nodiscard", "assert"];
+
+/// Returns the built-in visitor for the annotation target `name`, if any.
+pub fn builtin_visitor(name: &str) -> Option<Box<CppVisitor>> {
+    Some(match name {
+        "deprecated" => Box::new(DeprecatedVisitor),
+        "nodiscard" => Box::new(NodiscardVisitor),
+        "assert" => Box::new(AssertVisitor),
+        _ => return None,
+    })
+}
+
+/// `#deprecated` or `#deprecated("use x instead")` marks types, fields,
+/// cases, flags and functions as `[[deprecated]]`.
+pub struct DeprecatedVisitor;
+
+impl DeprecatedVisitor {
+    fn attribute(annotation: &AnnotationArgs) -> String {
+        match annotation.positional().next() {
+            Some(msg @ AnnotationValue::String(_)) => format!("deprecated({msg})"),
+            _ => "deprecated".to_string(),
+        }
+    }
+
+    fn type_attribute(annotation: &AnnotationArgs) -> Option<CppTypeContribution> {
+        let mut contrib = CppTypeContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn case_attribute(annotation: &AnnotationArgs) -> Option<CppVariantCaseContribution> {
+        let mut contrib = CppVariantCaseContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = CppContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Field,
+            ItemKind::Case,
+            ItemKind::Flag,
+            ItemKind::Function,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().optional_arg(ArgKind::String))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppFieldContribution> {
+        let mut contrib = CppFieldContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppVariantCaseContribution> {
+        Self::case_attribute(annotation)
+    }
+
+    fn visit_flag(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flag: &Flag,
+        _flag_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppVariantCaseContribution> {
+        Self::case_attribute(annotation)
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppFunctionContribution> {
+        let mut contrib = CppFunctionContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+/// `#nodiscard` marks functions `[[nodiscard]]`.
+pub struct NodiscardVisitor;
+
+impl Visitor for NodiscardVisitor {
+    type Contributions = CppContributions;
+
+    fn target(&self) -> &str {
+        "nodiscard"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new())
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppFunctionContribution> {
+        let mut contrib = CppFunctionContribution::new();
+        contrib.add_attribute("nodiscard");
+        Some(contrib)
+    }
+}
+
+/// `#assert(n < 100)` checks a condition on the arguments of an export
+/// before its implementation is called, and aborts if it doesn't hold.
+/// Unlike `assert` it's kept in `NDEBUG` builds.
+pub struct AssertVisitor;
+
+impl Visitor for AssertVisitor {
+    type Contributions = CppContributions;
+
+    fn target(&self) -> &str {
+        "assert"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().arg(ArgKind::Any))
+    }
+
+    fn priority(&self) -> i32 {
+        priority::VALIDATION
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppFunctionContribution> {
+        let mut contrib = CppFunctionContribution::new();
+        contrib.add_body_prefix(format!("if (!({})) abort();", annotation.trim()));
+        Some(contrib)
+    }
+}
@@ -1,3 +1,7 @@
+use annotation_visitor::{
+    attribute_specifier, CppFunctionContribution, CppModuleContribution, CppTypeContribution,
+    CppVisitor,
+};
 use anyhow::bail;
 use heck::{ToPascalCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
 use std::{
@@ -13,14 +17,19 @@ use wit_bindgen_c::to_c_ident;
 use wit_bindgen_core::{
     abi::{self, AbiVariant, Bindgen, Bitcast, LiftLower, WasmSignature, WasmType},
     uwrite, uwriteln,
+    visitor::{
+        check_world_annotations, register_builtins, VisitAnnotations, VisitorList, VisitorMap,
+    },
     wit_parser::{
-        Alignment, ArchitectureSize, Docs, Function, FunctionKind, Handle, Int, InterfaceId,
+        Alignment, ArchitectureSize, Case, Docs, Function, FunctionKind, Handle, Int, InterfaceId,
         Resolve, SizeAlign, Stability, Type, TypeDef, TypeDefKind, TypeId, TypeOwner, WorldId,
         WorldKey,
     },
-    Files, InterfaceGenerator, Source, Types, WorldGenerator,
+    AnnotationArgs, Diagnostic, Diagnostics, Files, InterfaceGenerator, Location, Source, Types,
+    VisitContext, WorldGenerator,
 };
 
+pub mod annotation_visitor;
 // mod wamr;
 mod symbol_name;
 
@@ -88,6 +97,15 @@ enum Direction {
     Export,
 }
 
+impl From<Direction> for wit_bindgen_core::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Import => wit_bindgen_core::Direction::Import,
+            Direction::Export => wit_bindgen_core::Direction::Export,
+        }
+    }
+}
+
 #[derive(Default)]
 struct Cpp {
     opts: Opts,
@@ -107,6 +125,9 @@ struct Cpp {
     // needed for symmetric disambiguation
     interface_prefixes: HashMap<(Direction, WorldKey), String>,
     import_prefix: Option<String>,
+
+    visitor_map: VisitorMap<CppVisitor>,
+    diagnostics: Diagnostics,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -165,6 +186,29 @@ pub struct Opts {
     /// Where to place output files
     #[cfg_attr(feature = "clap", arg(skip))]
     out_dir: Option<PathBuf>,
+
+    /// Fail generation on annotation problems, such as annotations without a
+    /// registered visitor, instead of reporting them as warnings.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = bool::default()))]
+    pub strict_annotations: bool,
+
+    /// Built-in annotation visitors to enable, out of `deprecated`,
+    /// `nodiscard` and `assert`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_annotation, value_delimiter = ','))]
+    pub annotations: Vec<String>,
+
+    /// Visitors for the annotations in the WIT, see [`VisitorList`].
+    #[cfg_attr(feature = "clap", arg(skip))]
+    pub visitors: VisitorList<CppVisitor>,
+}
+
+#[cfg(feature = "clap")]
+fn parse_annotation(s: &str) -> Result<String, String> {
+    wit_bindgen_core::visitor::parse_builtin(
+        s,
+        annotation_visitor::BUILTIN_ANNOTATIONS,
+        annotation_visitor::builtin_visitor,
+    )
 }
 
 /// Supported API styles for the generated bindings.
@@ -249,6 +293,17 @@ impl Opts {
     pub fn build(mut self, out_dir: Option<&PathBuf>) -> Box<dyn WorldGenerator> {
         let mut r = Cpp::new();
         self.out_dir = out_dir.cloned();
+        for visitor in self.visitors.create() {
+            r.visitor_map.register(visitor);
+        }
+        r.diagnostics = Diagnostics::new(self.strict_annotations);
+        register_builtins(
+            &mut r.visitor_map,
+            &mut r.diagnostics,
+            &self.annotations,
+            annotation_visitor::BUILTIN_ANNOTATIONS,
+            annotation_visitor::builtin_visitor,
+        );
         r.opts = self;
         Box::new(r)
     }
@@ -281,6 +336,43 @@ impl Cpp {
         self.includes.push(s.to_string());
     }
 
+    /// Visits the annotations on an interface, adding their includes to the
+    /// header currently being generated.
+    fn visit_interface(&mut self, resolve: &Resolve, id: InterfaceId, direction: Direction) {
+        let interface = &resolve.interfaces[id];
+        let location = Location::for_interface(resolve, id);
+        let cx = VisitContext {
+            interface: Some(id),
+            direction: Some(direction.into()),
+            module_path: Some(
+                namespace(
+                    resolve,
+                    &TypeOwner::Interface(id),
+                    direction == Direction::Export,
+                    &self.opts,
+                )
+                .join("::"),
+            ),
+            ..VisitContext::new(resolve, self.world_id.unwrap())
+        };
+        for contrib in self.visit_annotations(
+            &location,
+            interface.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_interface(args, Some(interface), &cx),
+        ) {
+            self.add_module_contribution(contrib);
+        }
+    }
+
+    fn add_module_contribution(&mut self, contrib: CppModuleContribution) {
+        for include in contrib.includes {
+            if !self.includes.contains(&include) {
+                self.includes.push(include);
+            }
+        }
+    }
+
     fn interface<'a>(
         &'a mut self,
         resolve: &'a Resolve,
@@ -448,7 +540,19 @@ struct FileContext {
     dependencies: Includes,
 }
 
+impl VisitAnnotations for Cpp {
+    type Visitor = CppVisitor;
+
+    fn visitors_and_diagnostics(&mut self) -> (&mut VisitorMap<CppVisitor>, &mut Diagnostics) {
+        (&mut self.visitor_map, &mut self.diagnostics)
+    }
+}
+
 impl WorldGenerator for Cpp {
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+        check_world_annotations(&self.visitor_map, &mut self.diagnostics, resolve, world)
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let name = &resolve.worlds[world].name;
         self.world = name.to_string();
@@ -490,6 +594,7 @@ impl WorldGenerator for Cpp {
         }
 
         let store = self.start_new_file(None);
+        self.visit_interface(resolve, id, Direction::Import);
         self.imported_interfaces.insert(id);
         let wasm_import_module = resolve.name_world_key(name);
         let binding = Some(name);
@@ -525,6 +630,7 @@ impl WorldGenerator for Cpp {
                 Some(prefix.clone() + old_prefix.as_ref().unwrap_or(&String::new()));
         }
         let store = self.start_new_file(None);
+        self.visit_interface(resolve, id, Direction::Export);
         self.h_src
             .src
             .push_str(&format!("// export_interface {name:?}\n"));
@@ -608,6 +714,18 @@ impl WorldGenerator for Cpp {
         files: &mut Files,
     ) -> std::result::Result<(), anyhow::Error> {
         let world = &resolve.worlds[world_id];
+        let location = Location::for_world(resolve, world_id);
+        let cx = VisitContext::new(resolve, world_id);
+        for contrib in self.visit_annotations(
+            &location,
+            world.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_world(args, world, &cx),
+        ) {
+            self.add_module_contribution(contrib);
+        }
+        self.diagnostics.bail_on_errors()?;
+
         let snake = world.name.to_snake_case();
         let linking_symbol = wit_bindgen_c::component_type_object::linking_symbol(&world.name);
 
@@ -709,6 +827,10 @@ impl WorldGenerator for Cpp {
         }
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
 
 // determine namespace (for the lifted C++ function)
@@ -795,7 +917,88 @@ struct CppInterfaceGenerator<'a> {
     pub wasm_import_module: Option<String>,
 }
 
-impl CppInterfaceGenerator<'_> {
+impl<'a> CppInterfaceGenerator<'a> {
+    /// The context visitors get for an item defined by this generator, called
+    /// `name` in the generated code.
+    fn visit_context(&self, name: Option<String>) -> VisitContext<'a> {
+        let guest_export = !self.in_guest_import;
+        let owner = match self.interface {
+            Some(id) => TypeOwner::Interface(id),
+            None => TypeOwner::World(self.gen.world_id.unwrap()),
+        };
+        VisitContext {
+            interface: self.interface,
+            direction: Some(if guest_export {
+                wit_bindgen_core::Direction::Export
+            } else {
+                wit_bindgen_core::Direction::Import
+            }),
+            name,
+            module_path: Some(
+                namespace(self.resolve, &owner, guest_export, &self.gen.opts).join("::"),
+            ),
+            ..VisitContext::new(self.resolve, self.gen.world_id.unwrap())
+        }
+    }
+
+    /// Collects the contributions of the visitors for the annotations on the
+    /// type `id`.
+    fn type_contribution(
+        &mut self,
+        id: TypeId,
+        mut visit: impl FnMut(
+            &mut CppVisitor,
+            &AnnotationArgs,
+            &VisitContext<'_>,
+        ) -> Option<CppTypeContribution>,
+    ) -> CppTypeContribution {
+        let location = Location::for_type(self.resolve, id);
+        let name = self.resolve.types[id]
+            .name
+            .as_ref()
+            .map(|n| n.to_pascal_case());
+        let cx = self.visit_context(name);
+        let mut contribution = CppTypeContribution::new();
+        for contrib in self.gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visit(visitor, args, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+            contribution.members.extend(contrib.members);
+        }
+        contribution
+    }
+
+    /// Collects the contributions of the visitors for the annotations on
+    /// `func`.
+    fn function_contribution(&mut self, func: &Function) -> CppFunctionContribution {
+        let location = self.function_location(func);
+        let (_, name) = self.func_namespace_name(func, !self.in_guest_import, false);
+        let cx = self.visit_context(Some(name));
+        let mut contribution = CppFunctionContribution::new();
+        for contrib in self.gen.visit_annotations(
+            &location,
+            func.annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_function(args, func, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+            contribution.body_prefix.extend(contrib.body_prefix);
+            contribution.body_suffix.extend(contrib.body_suffix);
+        }
+        contribution
+    }
+
+    fn function_location(&self, func: &Function) -> Location {
+        match self.interface {
+            Some(id) => Location::for_interface(self.resolve, id),
+            None => Location::for_world(self.resolve, self.gen.world_id.unwrap()),
+        }
+        .member(&func.name)
+    }
+
     fn types(&mut self, iface: InterfaceId) {
         let iface = &self.resolve().interfaces[iface];
         for (name, id) in iface.types.iter() {
@@ -1072,10 +1275,15 @@ impl CppInterfaceGenerator<'_> {
         func: &Function,
         variant: AbiVariant,
         import: bool,
+        attributes: &[String],
     ) -> Vec<String> {
         let is_special = is_special_method(func);
         let from_namespace = self.gen.h_src.namespace.clone();
         let cpp_sig = self.high_level_signature(func, variant, &from_namespace);
+        self.gen
+            .h_src
+            .src
+            .push_str(&with_space(attribute_specifier(attributes)));
         if cpp_sig.static_member {
             self.gen.h_src.src.push_str("static ");
         }
@@ -1198,7 +1406,8 @@ impl CppInterfaceGenerator<'_> {
             AbiVariant::GuestExportAsync => todo!(),
             AbiVariant::GuestExportAsyncStackful => todo!(),
         };
-        let params = self.print_signature(func, variant, !export);
+        let contribution = self.function_contribution(func);
+        let params = self.print_signature(func, variant, !export, &contribution.attributes);
         let special = is_special_method(func);
         if !matches!(special, SpecialMethod::Allocate) {
             self.gen.c_src.src.push_str("{\n");
@@ -1319,6 +1528,10 @@ impl CppInterfaceGenerator<'_> {
                     f.variant = variant;
                     f.needs_dealloc = needs_dealloc;
                     f.cabi_post = None;
+                    if export {
+                        f.body_prefix = contribution.body_prefix;
+                        f.body_suffix = contribution.body_suffix;
+                    }
                     abi::call(f.gen.resolve, variant, lift_lower, func, &mut f, false);
                     let code = String::from(f.src);
                     self.gen.c_src.src.push_str(&code);
@@ -1695,16 +1908,35 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         let namespc = namespace(self.resolve, &ty.owner, guest_export, &self.gen.opts);
 
         if self.gen.is_first_definition(&namespc, &name) {
+            let contribution = self.type_contribution(id, |visitor, args, cx| {
+                visitor.visit_record(args, record, id, cx)
+            });
             self.gen.h_src.change_namespace(&namespc);
             Self::docs(&mut self.gen.h_src.src, docs);
             let pascal = name.to_pascal_case();
 
-            uwriteln!(self.gen.h_src.src, "struct {pascal} {{");
-            for field in record.fields.iter() {
+            let attrs = with_space(attribute_specifier(&contribution.attributes));
+            uwriteln!(self.gen.h_src.src, "struct {attrs}{pascal} {{");
+            for (i, field) in record.fields.iter().enumerate() {
+                let location = Location::for_type(self.resolve, id).member(&field.name);
+                let cx = self.visit_context(Some(field.name.to_snake_case()));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    field.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_field(args, field, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 Self::docs(&mut self.gen.h_src.src, &field.docs);
                 let typename = self.type_name(&field.ty, &namespc, Flavor::InStruct);
                 let fname = field.name.to_snake_case();
-                uwriteln!(self.gen.h_src.src, "{typename} {fname};");
+                let attrs = with_space(attribute_specifier(&attributes));
+                uwriteln!(self.gen.h_src.src, "{attrs}{typename} {fname};");
+            }
+            for member in contribution.members.iter() {
+                uwriteln!(self.gen.h_src.src, "{member}");
             }
             uwriteln!(self.gen.h_src.src, "}};");
             self.type_record_param(id, name, record, namespc.as_slice());
@@ -1755,8 +1987,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
                 }
                 (true, true) => format!("wit::{RESOURCE_IMPORT_BASE_CLASS_NAME}<{pascal}>"),
             };
+            let contribution = self
+                .type_contribution(id, |visitor, args, cx| visitor.visit_resource(args, id, cx));
             let derive = format!(" : public {base_type}");
-            uwriteln!(self.gen.h_src.src, "class {pascal}{derive} {{\n");
+            let attrs = with_space(attribute_specifier(&contribution.attributes));
+            uwriteln!(self.gen.h_src.src, "class {attrs}{pascal}{derive} {{\n");
             uwriteln!(self.gen.h_src.src, "public:\n");
             let variant = if guest_import {
                 AbiVariant::GuestImport
@@ -1864,6 +2099,9 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
                 };
                 self.generate_function(&func2, &TypeOwner::Interface(intf), variant);
             }
+            for member in contribution.members.iter() {
+                uwriteln!(self.gen.h_src.src, "{member}");
+            }
             uwriteln!(self.gen.h_src.src, "}};\n");
             self.gen.finish_file(&user_filename, store);
         }
@@ -1880,16 +2118,35 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         let guest_export = self.is_exported_type(ty);
         let namespc = namespace(self.resolve, &ty.owner, guest_export, &self.gen.opts);
         if self.gen.is_first_definition(&namespc, name) {
+            let contribution = self.type_contribution(id, |visitor, args, cx| {
+                visitor.visit_flags(args, flags, id, cx)
+            });
             self.gen.h_src.change_namespace(&namespc);
             Self::docs(&mut self.gen.h_src.src, docs);
             let pascal = name.to_pascal_case();
             let int_repr = wit_bindgen_c::int_repr(wit_bindgen_c::flags_repr(flags));
-            uwriteln!(self.gen.h_src.src, "enum class {pascal} : {int_repr} {{");
+            let attrs = with_space(attribute_specifier(&contribution.attributes));
+            uwriteln!(
+                self.gen.h_src.src,
+                "enum class {attrs}{pascal} : {int_repr} {{"
+            );
             uwriteln!(self.gen.h_src.src, "k_None = 0,");
             for (n, field) in flags.flags.iter().enumerate() {
+                let location = Location::for_type(self.resolve, id).member(&field.name);
+                let cx = self.visit_context(Some(format!("k{}", field.name.to_pascal_case())));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    field.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_flag(args, field, n, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 Self::docs(&mut self.gen.h_src.src, &field.docs);
                 let fname = field.name.to_pascal_case();
-                uwriteln!(self.gen.h_src.src, "k{fname} = (1ULL<<{n}),");
+                let attrs = with_space(attribute_specifier(&attributes));
+                uwriteln!(self.gen.h_src.src, "k{fname} {attrs}= (1ULL<<{n}),");
             }
             uwriteln!(self.gen.h_src.src, "}};");
             uwriteln!(
@@ -1921,21 +2178,37 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         let guest_export = self.is_exported_type(ty);
         let namespc = namespace(self.resolve, &ty.owner, guest_export, &self.gen.opts);
         if self.gen.is_first_definition(&namespc, name) {
+            let contribution = self.type_contribution(id, |visitor, args, cx| {
+                visitor.visit_variant(args, variant, id, cx)
+            });
             self.gen.h_src.change_namespace(&namespc);
             Self::docs(&mut self.gen.h_src.src, docs);
             let pascal = name.to_pascal_case();
-            uwriteln!(self.gen.h_src.src, "struct {pascal} {{");
+            let attrs = with_space(attribute_specifier(&contribution.attributes));
+            uwriteln!(self.gen.h_src.src, "struct {attrs}{pascal} {{");
             let mut inner_namespace = namespc.clone();
             inner_namespace.push(pascal.clone());
             let mut all_types = String::new();
-            for case in variant.cases.iter() {
+            for (i, case) in variant.cases.iter().enumerate() {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(case.name.to_pascal_case()));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 Self::docs(&mut self.gen.h_src.src, &case.docs);
                 let case_pascal = case.name.to_pascal_case();
                 if !all_types.is_empty() {
                     all_types += ", ";
                 }
                 all_types += &case_pascal;
-                uwrite!(self.gen.h_src.src, "struct {case_pascal} {{");
+                let attrs = with_space(attribute_specifier(&attributes));
+                uwrite!(self.gen.h_src.src, "struct {attrs}{case_pascal} {{");
                 if let Some(ty) = case.ty.as_ref() {
                     let typestr = self.type_name(ty, &inner_namespace, Flavor::InStruct);
                     uwrite!(self.gen.h_src.src, " {typestr} value; ")
@@ -1943,6 +2216,9 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
                 uwriteln!(self.gen.h_src.src, "}};");
             }
             uwriteln!(self.gen.h_src.src, "  std::variant<{all_types}> variants;");
+            for member in contribution.members.iter() {
+                uwriteln!(self.gen.h_src.src, "{member}");
+            }
             uwriteln!(self.gen.h_src.src, "}};");
             self.gen.dependencies.needs_variant = true;
         }
@@ -1979,17 +2255,43 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         let guest_export = self.is_exported_type(ty);
         let namespc = namespace(self.resolve, &ty.owner, guest_export, &self.gen.opts);
         if self.gen.is_first_definition(&namespc, name) {
+            let contribution = self.type_contribution(id, |visitor, args, cx| {
+                visitor.visit_enum(args, enum_, id, cx)
+            });
             self.gen.h_src.change_namespace(&namespc);
             let pascal = name.to_pascal_case();
             Self::docs(&mut self.gen.h_src.src, docs);
             let int_t = wit_bindgen_c::int_repr(enum_.tag());
-            uwriteln!(self.gen.h_src.src, "enum class {pascal} : {int_t} {{");
+            let attrs = with_space(attribute_specifier(&contribution.attributes));
+            uwriteln!(
+                self.gen.h_src.src,
+                "enum class {attrs}{pascal} : {int_t} {{"
+            );
             for (i, case) in enum_.cases.iter().enumerate() {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(format!("k{}", case.name.to_pascal_case())));
+                let mut attributes = Vec::new();
+                // Enum cases are visited as variant cases without a payload
+                let variant_case = Case {
+                    name: case.name.clone(),
+                    docs: case.docs.clone(),
+                    ty: None,
+                    annotations: case.annotations.clone(),
+                };
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, &variant_case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 Self::docs(&mut self.gen.h_src.src, &case.docs);
                 uwriteln!(
                     self.gen.h_src.src,
-                    " k{} = {i},",
+                    " k{} {}= {i},",
                     case.name.to_pascal_case(),
+                    with_space(attribute_specifier(&attributes)),
                 );
             }
             uwriteln!(self.gen.h_src.src, "}};\n");
@@ -2006,11 +2308,15 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         let ty = &self.resolve.types[id];
         let guest_export = self.is_exported_type(ty);
         let namespc = namespace(self.resolve, &ty.owner, guest_export, &self.gen.opts);
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_alias(args, alias_type, id, cx)
+        });
         self.gen.h_src.change_namespace(&namespc);
         let pascal = name.to_pascal_case();
         Self::docs(&mut self.gen.h_src.src, docs);
         let typename = self.type_name(alias_type, &namespc, Flavor::InStruct);
-        uwriteln!(self.gen.h_src.src, "using {pascal} = {typename};");
+        let attrs = with_space(attribute_specifier(&contribution.attributes));
+        uwriteln!(self.gen.h_src.src, "using {pascal} {attrs}= {typename};");
     }
 
     fn type_list(
@@ -2063,6 +2369,12 @@ struct FunctionBindgen<'a, 'b> {
     cabi_post: Option<CabiPostInformation>,
     needs_dealloc: bool,
     leak_on_insertion: Option<String>,
+    /// Statements contributed by annotation visitors to run just before an
+    /// export's implementation is called.
+    body_prefix: Vec<String>,
+    /// Statements contributed by annotation visitors to run just before an
+    /// export wrapper returns.
+    body_suffix: Vec<String>,
 }
 
 impl<'a, 'b> FunctionBindgen<'a, 'b> {
@@ -2080,6 +2392,8 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             cabi_post: None,
             needs_dealloc: false,
             leak_on_insertion: None,
+            body_prefix: Vec::new(),
+            body_suffix: Vec::new(),
         }
     }
 
@@ -2093,6 +2407,20 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         format!("{base}{idx}")
     }
 
+    /// Returns whether `name` may be taken by a local of the generated
+    /// glue, which are the parameters, `ret`, or a base name followed by the
+    /// counter of [`Self::tmp`] such as `len3`.
+    fn is_local(&self, name: &str) -> bool {
+        const BASES: &[&str] = &[
+            "base", "flags", "i", "l", "len", "obj", "option", "payload", "ptr", "result", "tuple",
+            "variant", "vec",
+        ];
+        let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        self.params.iter().any(|p| p == name)
+            || name == "ret"
+            || (base.len() < name.len() && BASES.contains(&base))
+    }
+
     fn push_str(&mut self, s: &str) {
         self.src.push_str(s);
     }
@@ -2153,6 +2481,16 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
     }
 }
 
+/// Appends a space to a non-empty attribute specifier, so that it can be
+/// spliced in front of a name.
+fn with_space(attributes: String) -> String {
+    if attributes.is_empty() {
+        attributes
+    } else {
+        attributes + " "
+    }
+}
+
 fn move_if_necessary(arg: &str) -> String {
     // if it is a name of a variable move it
     if !arg.is_empty() && arg.chars().all(char::is_alphanumeric) {
@@ -2928,6 +3266,28 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
             }
             abi::Instruction::CallInterface { func, .. } => {
                 // dbg!(func);
+                if !self.body_prefix.is_empty() {
+                    // Bind the lifted arguments to their WIT names so the
+                    // prefix can refer to them, forwarding them on unchanged.
+                    for (op, (param, _)) in operands.iter_mut().zip(func.params.iter()) {
+                        let name = to_c_ident(&param.to_snake_case());
+                        if self.is_local(&name) {
+                            let location = self.gen.function_location(func).member(param);
+                            self.gen.gen.diagnostics.error(
+                                &location,
+                                format!(
+                                    "can't bind parameter for the body prefix: \
+                                     name `{name}` is taken by a local of the bindings"
+                                ),
+                            );
+                        }
+                        uwriteln!(self.src, "auto&& {name} = {op};");
+                        *op = format!("std::forward<decltype({name})>({name})");
+                    }
+                    for code in std::mem::take(&mut self.body_prefix) {
+                        uwriteln!(self.src, "{code}");
+                    }
+                }
                 self.let_results(if func.result.is_some() { 1 } else { 0 }, results);
                 let (namespace, func_name_h) = self.gen.func_namespace_name(func, true, true);
                 if matches!(func.kind, FunctionKind::Method(_)) {
@@ -2951,6 +3311,9 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
                 }
             }
             abi::Instruction::Return { amt, func } => {
+                for code in std::mem::take(&mut self.body_suffix) {
+                    uwriteln!(self.src, "{code}");
+                }
                 match amt {
                     0 => {}
                     _ => {
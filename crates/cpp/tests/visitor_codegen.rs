@@ -0,0 +1,268 @@
+// Runs the C++ generator with visitors registered and checks that their
+// contributions show up in the generated headers and source.
+
+use wit_bindgen_core::visitor::VisitorList;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, Files, VisitContext, Visitor};
+use wit_bindgen_cpp::annotation_visitor::*;
+use wit_bindgen_cpp::Opts;
+
+/// Generates bindings for the only world in `wit` and returns all headers
+/// concatenated, and the C++ source, with all whitespace runs collapsed to a
+/// single space.
+fn generate(
+    wit: &str,
+    split_interfaces: bool,
+    visitors: impl Fn() -> Vec<Box<CppVisitor>> + Send + Sync + 'static,
+) -> (String, String) {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        split_interfaces,
+        visitors: VisitorList::new(visitors),
+        ..Default::default()
+    };
+    let mut files = Files::default();
+    opts.build(None)
+        .generate(&resolve, world, &mut files)
+        .unwrap();
+    let collect = |ext: &str| {
+        files
+            .iter()
+            .filter(|(name, _)| name.ends_with(ext))
+            .map(|(_, src)| std::str::from_utf8(src).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    (collect(".h"), collect(".cpp"))
+}
+
+/// Gives records a defaulted `operator==`, which needs `<compare>`.
+struct EqVisitor;
+
+impl Visitor for EqVisitor {
+    type Contributions = CppContributions;
+
+    fn target(&self) -> &str {
+        "eq"
+    }
+
+    fn visit_record(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppTypeContribution> {
+        let mut contrib = CppTypeContribution::new();
+        contrib.add_member("bool operator==(const Point&) const = default;");
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppModuleContribution> {
+        let mut contrib = CppModuleContribution::new();
+        contrib.add_include("<compare>");
+        Some(contrib)
+    }
+}
+
+/// Checks the annotation's expression with `assert` before calling the
+/// export, unlike the built-in `AssertVisitor`, and traces its return.
+struct TraceAssertVisitor;
+
+impl Visitor for TraceAssertVisitor {
+    type Contributions = CppContributions;
+
+    fn target(&self) -> &str {
+        "assert"
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CppFunctionContribution> {
+        let mut contrib = CppFunctionContribution::new();
+        contrib.add_body_prefix(format!("assert({});", annotation.as_str()));
+        contrib.add_body_suffix(format!("trace_return(\"{}\");", func.name));
+        Some(contrib)
+    }
+}
+
+const ATTRIBUTES_WIT: &str = r#"
+    package test:cpp;
+
+    interface api {
+        #deprecated("use point3")
+        record point {
+            x: u32,
+            #deprecated
+            y: u32,
+        }
+
+        variant shape {
+            #deprecated
+            dot,
+            circle(u32),
+        }
+
+        #nodiscard
+        distance: func(a: point, b: point) -> u32;
+        area: func(s: shape) -> u32;
+    }
+
+    world test {
+        import api;
+    }
+"#;
+
+#[test]
+fn test_attributes() {
+    for split_interfaces in [false, true] {
+        let (h, _) = generate(ATTRIBUTES_WIT, split_interfaces, || {
+            vec![Box::new(DeprecatedVisitor), Box::new(NodiscardVisitor)]
+        });
+
+        assert!(
+            h.contains("struct [[deprecated(\"use point3\")]] Point {"),
+            "type attribute missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+        assert!(
+            h.contains("[[deprecated]] uint32_t y;"),
+            "field attribute missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+        assert!(
+            h.contains("struct [[deprecated]] Dot {"),
+            "case attribute missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+        assert!(
+            h.contains("[[nodiscard]] uint32_t Distance("),
+            "function attribute missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+    }
+}
+
+#[test]
+fn test_members_and_includes() {
+    let wit = r#"
+        package test:cpp;
+
+        #eq
+        interface api {
+            #eq
+            record point {
+                x: u32,
+                y: u32,
+            }
+        }
+
+        world test {
+            import api;
+        }
+    "#;
+    for split_interfaces in [false, true] {
+        let (h, _) = generate(wit, split_interfaces, || vec![Box::new(EqVisitor)]);
+
+        assert!(
+            h.contains("uint32_t y; bool operator==(const Point&) const = default; };"),
+            "member missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+        assert_eq!(
+            h.matches("#include <compare>").count(),
+            1,
+            "include missing (split_interfaces = {split_interfaces}):\n{h}"
+        );
+    }
+}
+
+#[test]
+fn test_export_body_prefix_and_suffix() {
+    let wit = r#"
+        package test:cpp;
+
+        interface math {
+            #assert(divisor != 0)
+            divide: func(dividend: u32, divisor: u32) -> u32;
+        }
+
+        world test {
+            import math;
+            export math;
+        }
+    "#;
+    let (_, cpp) = generate(wit, false, || vec![Box::new(TraceAssertVisitor)]);
+
+    // Only the export wrapper runs the prefix and suffix.
+    assert_eq!(cpp.matches("assert(divisor != 0);").count(), 1, "{cpp}");
+    let binding = cpp.find("auto&& divisor = ").expect(&cpp);
+    let prefix = cpp.find("assert(divisor != 0);").unwrap();
+    assert!(
+        binding < prefix,
+        "arguments should be bound before the prefix:\n{cpp}"
+    );
+    assert!(
+        cpp.contains("std::forward<decltype(divisor)>(divisor)"),
+        "bound arguments should be passed on to the implementation:\n{cpp}"
+    );
+    let suffix = cpp.find("trace_return(\"divide\"); return ").expect(&cpp);
+    assert!(prefix < suffix, "{cpp}");
+}
+
+#[test]
+fn test_export_body_prefix_bindings() {
+    let wit = r#"
+        package test:cpp;
+
+        interface math {
+            #assert(this_ != 0)
+            check: func(this: u32);
+        }
+
+        world test {
+            export math;
+        }
+    "#;
+    let (_, cpp) = generate(wit, false, || vec![Box::new(TraceAssertVisitor)]);
+    assert!(
+        cpp.contains("auto&& this_ = "),
+        "keywords should be escaped:\n{cpp}"
+    );
+
+    // The name of a local of the bindings can't be bound.
+    let wit = wit.replace("this", "len0");
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", &wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        visitors: VisitorList::new(|| vec![Box::new(TraceAssertVisitor) as Box<CppVisitor>]),
+        ..Default::default()
+    };
+    let mut generator = opts.build(None);
+    let err = generator
+        .generate(&resolve, world, &mut Files::default())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "generation failed due to 1 annotation error(s)"
+    );
+    let messages = generator
+        .take_diagnostics()
+        .into_iter()
+        .map(|d| d.message)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        ["can't bind parameter for the body prefix: \
+          name `len0` is taken by a local of the bindings"]
+    );
+}
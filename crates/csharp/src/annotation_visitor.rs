@@ -0,0 +1,385 @@
+use wit_bindgen_core::visitor::{AnnotationSchema, AnnotationValue, ArgKind, ItemKind};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, ContributionTypes, VisitContext, Visitor};
+
+/// Contributions for type definitions
+#[derive(Default, Debug, Clone)]
+pub struct CSharpTypeContribution {
+    /// Attributes to add to the type, e.g. `Obsolete("...")` or
+    /// `global::System.Serializable`, each rendered as `[...]`
+    pub attributes: Vec<String>,
+
+    /// Member declarations to add to the generated struct or class, e.g.
+    /// `public override string ToString() => ...;` (only used for records,
+    /// variants and resources)
+    pub members: Vec<String>,
+}
+
+impl CSharpTypeContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add a method, property or other member declaration
+    pub fn add_member(&mut self, code: impl Into<String>) {
+        self.members.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.members.is_empty()
+    }
+}
+
+/// Contributions for record fields
+#[derive(Default, Debug, Clone)]
+pub struct CSharpFieldContribution {
+    /// Field-level attributes, e.g. `JsonPropertyName("...")`
+    pub attributes: Vec<String>,
+}
+
+impl CSharpFieldContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for variant cases, enum cases and flags
+#[derive(Default, Debug, Clone)]
+pub struct CSharpVariantCaseContribution {
+    /// Case-level attributes, added to the enum member or, for variants, to
+    /// the case's factory method
+    pub attributes: Vec<String>,
+}
+
+impl CSharpVariantCaseContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for functions
+#[derive(Default, Debug, Clone)]
+pub struct CSharpFunctionContribution {
+    /// Attributes to add to the generated method: the import wrapper, or the
+    /// interface method of an export and its stub implementation
+    pub attributes: Vec<String>,
+}
+
+impl CSharpFunctionContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for the files generated for an interface, or for the world
+///
+/// Contributions from the world apply to every generated file.
+#[derive(Default, Debug, Clone)]
+pub struct CSharpModuleContribution {
+    /// Namespaces to import with `using`, e.g.
+    /// `System.Text.Json.Serialization`
+    pub usings: Vec<String>,
+
+    /// Attributes to add to the generated `partial` stub class
+    pub stub_attributes: Vec<String>,
+
+    /// Member declarations to add to the generated `partial` stub class
+    pub stub_members: Vec<String>,
+}
+
+impl CSharpModuleContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a `using` directive
+    pub fn add_using(&mut self, namespace: impl Into<String>) {
+        self.usings.push(namespace.into());
+    }
+
+    /// Add an attribute to the stub class
+    pub fn add_stub_attribute(&mut self, attr: impl Into<String>) {
+        self.stub_attributes.push(attr.into());
+    }
+
+    /// Add a member declaration to the stub class
+    pub fn add_stub_member(&mut self, code: impl Into<String>) {
+        self.stub_members.push(code.into());
+    }
+
+    /// Merge another contribution into this one, skipping `using`s that are
+    /// already present
+    pub fn extend(&mut self, other: CSharpModuleContribution) {
+        for using in other.usings {
+            if !self.usings.contains(&using) {
+                self.usings.push(using);
+            }
+        }
+        self.stub_attributes.extend(other.stub_attributes);
+        self.stub_members.extend(other.stub_members);
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.usings.is_empty() && self.stub_attributes.is_empty() && self.stub_members.is_empty()
+    }
+}
+
+/// The family of C# contribution types.
+pub struct CSharpContributions;
+
+impl ContributionTypes for CSharpContributions {
+    type Type = CSharpTypeContribution;
+    type Field = CSharpFieldContribution;
+    type VariantCase = CSharpVariantCaseContribution;
+    type Function = CSharpFunctionContribution;
+    type Module = CSharpModuleContribution;
+    // Annotations naming a parameter are only offered to `visit_function`.
+    type Param = ();
+}
+
+/// C#-specific visitor type alias.
+pub type CSharpVisitor = dyn Visitor<Contributions = CSharpContributions>;
+
+/// Renders `attributes` as one `[...]` attribute section per line, each
+/// followed by a newline, or nothing if there are none.
+pub(crate) fn attribute_sections(attributes: &[String]) -> String {
+    attributes
+        .iter()
+        .map(|attr| format!("[{attr}]\n"))
+        .collect()
+}
+
+/// Renders `usings` as `using` directives, each followed by a newline.
+pub(crate) fn using_directives(usings: &[String]) -> String {
+    usings
+        .iter()
+        .map(|using| format!("using {using};\n"))
+        .collect()
+}
+
+/// The names of all built-in annotation visitors.
+pub const BUILTIN_ANNOTATIONS: &[&str] = &["deprecated", "rename"];
+
+/// Returns the built-in visitor for the annotation target `name`, if any.
+pub fn builtin_visitor(name: &str) -> Option<Box<CSharpVisitor>> {
+    Some(match name {
+        "deprecated" => Box::new(DeprecatedVisitor),
+        "rename" => Box::new(RenameVisitor),
+        _ => return None,
+    })
+}
+
+/// `#deprecated` or `#deprecated("use x instead")` marks types, fields,
+/// cases, flags and functions `[Obsolete]`.
+pub struct DeprecatedVisitor;
+
+impl DeprecatedVisitor {
+    fn attribute(annotation: &AnnotationArgs) -> String {
+        match annotation.positional().next() {
+            Some(msg @ AnnotationValue::String(_)) => format!("global::System.Obsolete({msg})"),
+            _ => "global::System.Obsolete".to_string(),
+        }
+    }
+
+    fn type_attribute(annotation: &AnnotationArgs) -> Option<CSharpTypeContribution> {
+        let mut contrib = CSharpTypeContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn case_attribute(annotation: &AnnotationArgs) -> Option<CSharpVariantCaseContribution> {
+        let mut contrib = CSharpVariantCaseContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = CSharpContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Field,
+            ItemKind::Case,
+            ItemKind::Flag,
+            ItemKind::Function,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().optional_arg(ArgKind::String))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpFieldContribution> {
+        let mut contrib = CSharpFieldContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpVariantCaseContribution> {
+        Self::case_attribute(annotation)
+    }
+
+    fn visit_flag(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flag: &Flag,
+        _flag_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpVariantCaseContribution> {
+        Self::case_attribute(annotation)
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpFunctionContribution> {
+        let mut contrib = CSharpFunctionContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+/// `#rename("name")` renames record fields for `System.Text.Json`, in the
+/// style of serde's `rename`.
+pub struct RenameVisitor;
+
+impl Visitor for RenameVisitor {
+    type Contributions = CSharpContributions;
+
+    fn target(&self) -> &str {
+        "rename"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Field])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().arg(ArgKind::String))
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpFieldContribution> {
+        let name = annotation.positional().next()?;
+        let mut contrib = CSharpFieldContribution::new();
+        contrib.add_attribute(format!(
+            "global::System.Text.Json.Serialization.JsonPropertyName({name})"
+        ));
+        Some(contrib)
+    }
+}
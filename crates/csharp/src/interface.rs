@@ -1,3 +1,7 @@
+use crate::annotation_visitor::{
+    attribute_sections, CSharpFunctionContribution, CSharpModuleContribution,
+    CSharpTypeContribution, CSharpVisitor,
+};
 use crate::csharp_ident::ToCSharpIdent;
 use crate::function::FunctionBindgen;
 use crate::function::ResourceInfo;
@@ -7,19 +11,23 @@ use std::collections::HashMap;
 use std::fmt::Write;
 use std::ops::Deref;
 use wit_bindgen_core::abi::LiftLower;
+use wit_bindgen_core::visitor::VisitAnnotations;
 use wit_bindgen_core::{
-    abi, uwrite, uwriteln, Direction, InterfaceGenerator as CoreInterfaceGenerator,
+    abi, uwrite, uwriteln, AnnotationArgs, Direction, InterfaceGenerator as CoreInterfaceGenerator,
+    Location, VisitContext,
 };
 use wit_parser::abi::AbiVariant;
 use wit_parser::{
-    Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle, Int, InterfaceId, LiveTypes,
-    Record, Resolve, Result_, Tuple, Type, TypeDefKind, TypeId, TypeOwner, Variant, WorldKey,
+    Case, Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle, Int, InterfaceId,
+    LiveTypes, Record, Resolve, Result_, Tuple, Type, TypeDefKind, TypeId, TypeOwner, Variant,
+    WorldKey,
 };
 
 pub(crate) struct InterfaceFragment {
     pub(crate) csharp_src: String,
     pub(crate) csharp_interop_src: String,
     pub(crate) stub: String,
+    pub(crate) module: CSharpModuleContribution,
 }
 
 pub(crate) struct InterfaceTypeAndFragments {
@@ -43,13 +51,15 @@ pub(crate) struct InterfaceGenerator<'a> {
     pub(crate) src: String,
     pub(crate) csharp_interop_src: String,
     pub(crate) stub: String,
+    pub(crate) module: CSharpModuleContribution,
     pub(crate) csharp_gen: &'a mut CSharp,
     pub(crate) resolve: &'a Resolve,
     pub(crate) name: &'a str,
+    pub(crate) interface: Option<InterfaceId>,
     pub(crate) direction: Direction,
 }
 
-impl InterfaceGenerator<'_> {
+impl<'a> InterfaceGenerator<'a> {
     pub fn is_async(kind: &FunctionKind) -> bool {
         matches!(
             kind,
@@ -168,6 +178,7 @@ impl InterfaceGenerator<'_> {
                 csharp_src: self.src,
                 csharp_interop_src: self.csharp_interop_src,
                 stub: self.stub,
+                module: self.module,
             });
     }
 
@@ -176,9 +187,86 @@ impl InterfaceGenerator<'_> {
             csharp_src: self.src,
             csharp_interop_src: self.csharp_interop_src,
             stub: self.stub,
+            module: self.module,
         });
     }
 
+    /// Visits the annotations on the interface `id`, collecting their
+    /// contributions for the files generated for it.
+    pub(crate) fn visit_interface(&mut self, id: InterfaceId) {
+        let interface = &self.resolve.interfaces[id];
+        let location = Location::for_interface(self.resolve, id);
+        let cx = self.visit_context(None);
+        for contrib in self.csharp_gen.visit_annotations(
+            &location,
+            interface.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_interface(args, Some(interface), &cx),
+        ) {
+            self.module.extend(contrib);
+        }
+    }
+
+    /// The context visitors get for an item defined by this generator, called
+    /// `name` in the generated code.
+    fn visit_context(&self, name: Option<String>) -> VisitContext<'a> {
+        VisitContext {
+            interface: self.interface,
+            direction: Some(self.direction),
+            name,
+            module_path: Some(self.name.to_string()),
+            ..VisitContext::new(self.resolve, self.csharp_gen.world_id.unwrap())
+        }
+    }
+
+    /// Collects the contributions of the visitors for the annotations on the
+    /// type `id`.
+    fn type_contribution(
+        &mut self,
+        id: TypeId,
+        mut visit: impl FnMut(
+            &mut CSharpVisitor,
+            &AnnotationArgs,
+            &VisitContext<'_>,
+        ) -> Option<CSharpTypeContribution>,
+    ) -> CSharpTypeContribution {
+        let location = Location::for_type(self.resolve, id);
+        let name = self.resolve.types[id].name.as_ref();
+        let cx = self.visit_context(name.map(|n| n.to_upper_camel_case()));
+        let mut contribution = CSharpTypeContribution::new();
+        for contrib in self.csharp_gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visit(visitor, args, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+            contribution.members.extend(contrib.members);
+        }
+        contribution
+    }
+
+    /// Collects the contributions of the visitors for the annotations on
+    /// `func`, generated as the method `name`.
+    fn function_contribution(&mut self, func: &Function, name: &str) -> CSharpFunctionContribution {
+        let location = match self.interface {
+            Some(id) => Location::for_interface(self.resolve, id),
+            None => Location::for_world(self.resolve, self.csharp_gen.world_id.unwrap()),
+        }
+        .member(&func.name);
+        let cx = self.visit_context(Some(name.to_string()));
+        let mut contribution = CSharpFunctionContribution::new();
+        for contrib in self.csharp_gen.visit_annotations(
+            &location,
+            func.annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_function(args, func, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+        }
+        contribution
+    }
+
     pub(crate) fn import(&mut self, import_module_name: &str, func: &Function) {
         let camel_name = match &func.kind {
             FunctionKind::Freestanding
@@ -197,6 +285,9 @@ impl InterfaceGenerator<'_> {
 
         let modifiers = modifiers(func, &camel_name, Direction::Import);
 
+        let attributes =
+            attribute_sections(&self.function_contribution(func, &camel_name).attributes);
+
         let interop_camel_name = func.item_name().to_upper_camel_case();
 
         let sig = self.resolve.wasm_signature(AbiVariant::GuestImport, func);
@@ -300,7 +391,7 @@ impl InterfaceGenerator<'_> {
             uwrite!(
                 target,
                 r#"
-                    {access} {modifiers} unsafe {result_type} {camel_name}({params})
+                    {attributes}{access} {modifiers} unsafe {result_type} {camel_name}({params})
                     {{
                         {src}
                     }}
@@ -449,6 +540,9 @@ impl InterfaceGenerator<'_> {
 
         let modifiers = modifiers(func, &camel_name, Direction::Export);
 
+        let attributes =
+            attribute_sections(&self.function_contribution(func, &camel_name).attributes);
+
         let sig = self.resolve.wasm_signature(AbiVariant::GuestExport, func);
 
         let (result_type, results) = self.func_payload_and_return_type(func);
@@ -621,7 +715,7 @@ impl InterfaceGenerator<'_> {
         if !matches!(&func.kind, FunctionKind::Constructor(_)) {
             uwrite!(
                 self.src,
-                r#"{modifiers} {result_type} {camel_name}({params});
+                r#"{attributes}{modifiers} {result_type} {camel_name}({params});
 
             "#
             );
@@ -633,7 +727,7 @@ impl InterfaceGenerator<'_> {
             uwrite!(
                 self.stub,
                 r#"
-                {sig} {{
+                {attributes}{sig} {{
                     throw new global::System.NotImplementedException();
                 }}
                 "#
@@ -854,6 +948,10 @@ impl InterfaceGenerator<'_> {
         let name = info.name.clone();
         let upper_camel = name.to_upper_camel_case();
         let docs = info.docs.clone();
+        let contribution =
+            self.type_contribution(id, |visitor, args, cx| visitor.visit_resource(args, id, cx));
+        let attributes = attribute_sections(&contribution.attributes);
+        let members = contribution.members.join("\n");
         self.print_docs(&docs);
 
         match self.direction {
@@ -870,7 +968,7 @@ impl InterfaceGenerator<'_> {
                 uwriteln!(
                     self.src,
                     r#"
-                    {access} class {upper_camel}: global::System.IDisposable {{
+                    {attributes}{access} class {upper_camel}: global::System.IDisposable {{
                         internal int Handle {{ get; set; }}
 
                         {access} readonly record struct THandle(int Handle);
@@ -892,6 +990,8 @@ impl InterfaceGenerator<'_> {
                                 Handle = 0;
                             }}
                         }}
+
+                        {members}
                     "#
                 );
             }
@@ -936,7 +1036,7 @@ impl InterfaceGenerator<'_> {
                 uwriteln!(
                     self.src,
                     r#"
-                    {access} abstract class {upper_camel}: global::System.IDisposable {{
+                    {attributes}{access} abstract class {upper_camel}: global::System.IDisposable {{
                         internal static RepTable<{upper_camel}> repTable = new ();
                         internal int Handle {{ get; set; }}
 
@@ -967,6 +1067,8 @@ impl InterfaceGenerator<'_> {
                         ~{upper_camel}() {{
                             Dispose(false);
                         }}
+
+                        {members}
                     }}
 
                     {access} interface I{upper_camel} {{
@@ -1059,9 +1161,13 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.resolve
     }
 
-    fn type_record(&mut self, _id: TypeId, name: &str, record: &Record, docs: &Docs) {
+    fn type_record(&mut self, id: TypeId, name: &str, record: &Record, docs: &Docs) {
         let access = self.csharp_gen.access_modifier();
 
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_record(args, record, id, cx)
+        });
+
         self.print_docs(docs);
 
         let name = name.to_upper_camel_case();
@@ -1095,9 +1201,22 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
             record
                 .fields
                 .iter()
-                .map(|field| {
+                .enumerate()
+                .map(|(i, field)| {
+                    let location = Location::for_type(self.resolve, id).member(&field.name);
+                    let cx = self.visit_context(Some(field.name.to_csharp_ident()));
+                    let mut attributes = Vec::new();
+                    for contrib in self.csharp_gen.visit_annotations(
+                        &location,
+                        field.annotations.iter(),
+                        Some(&cx),
+                        |visitor, args| visitor.visit_field(args, field, i, &cx),
+                    ) {
+                        attributes.extend(contrib.attributes);
+                    }
                     format!(
-                        "{access} readonly {} {};",
+                        "{}{access} readonly {} {};",
+                        attribute_sections(&attributes),
                         self.type_name(&field.ty),
                         field.name.to_csharp_ident()
                     )
@@ -1106,21 +1225,29 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 .join("\n")
         };
 
+        let attributes = attribute_sections(&contribution.attributes);
+        let members = contribution.members.join("\n");
+
         uwrite!(
             self.src,
             "
-            {access} readonly struct {name} {{
+            {attributes}{access} readonly struct {name} {{
                 {fields}
 
                 {access} {name}({parameters}) {{
                     {assignments}
                 }}
+                {members}
             }}
             "
         );
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_flags(args, flags, id, cx)
+        });
+
         self.print_docs(docs);
 
         let name = name.to_upper_camel_case();
@@ -1130,13 +1257,25 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .iter()
             .enumerate()
             .map(|(i, flag)| {
+                let location = Location::for_type(self.resolve, id).member(&flag.name);
+                let cx = self.visit_context(Some(flag.name.to_shouty_snake_case()));
+                let mut attributes = Vec::new();
+                for contrib in self.csharp_gen.visit_annotations(
+                    &location,
+                    flag.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_flag(args, flag, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                let attributes = attribute_sections(&attributes);
                 let flag_name = flag.name.to_shouty_snake_case();
                 let suffix = if matches!(flags.repr(), FlagsRepr::U32(2)) {
                     "UL"
                 } else {
                     ""
                 };
-                format!("{flag_name} = 1{suffix} << {i},")
+                format!("{attributes}{flag_name} = 1{suffix} << {i},")
             })
             .collect::<Vec<_>>()
             .join("\n");
@@ -1149,11 +1288,12 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         };
 
         let access = self.csharp_gen.access_modifier();
+        let attributes = attribute_sections(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            {access} enum {name} {enum_type} {{
+            {attributes}{access} enum {name} {enum_type} {{
                 {enum_elements}
             }}
             "
@@ -1164,7 +1304,11 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.type_name(&Type::Id(id));
     }
 
-    fn type_variant(&mut self, _id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+    fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_variant(args, variant, id, cx)
+        });
+
         self.print_docs(docs);
 
         let name = name.to_upper_camel_case();
@@ -1174,7 +1318,20 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let constructors = variant
             .cases
             .iter()
-            .map(|case| {
+            .enumerate()
+            .map(|(i, case)| {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(case.name.to_csharp_ident()));
+                let mut attributes = Vec::new();
+                for contrib in self.csharp_gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                let attributes = attribute_sections(&attributes);
                 let case_name = case.name.to_csharp_ident();
                 let tag = case.name.to_csharp_ident_upper();
                 let (parameter, argument) = if let Some(ty) = self.non_empty_type(case.ty.as_ref())
@@ -1188,7 +1345,7 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 };
 
                 format!(
-                    "{access} static {name} {tag}({parameter}) {{
+                    "{attributes}{access} static {name} {tag}({parameter}) {{
                          return new {name}(Tags.{tag}, {argument});
                      }}
                     "
@@ -1234,10 +1391,13 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .collect::<Vec<_>>()
             .join("\n");
 
+        let attributes = attribute_sections(&contribution.attributes);
+        let members = contribution.members.join("\n");
+
         uwrite!(
             self.src,
             "
-            {access} class {name} {{
+            {attributes}{access} class {name} {{
                 {access} readonly {tag_type} Tag;
                 private readonly object? value;
 
@@ -1252,6 +1412,7 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 {access} class Tags {{
                     {tags}
                 }}
+                {members}
             }}
             "
         );
@@ -1265,7 +1426,11 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.type_name(&Type::Id(id));
     }
 
-    fn type_enum(&mut self, _id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_enum(args, enum_, id, cx)
+        });
+
         self.print_docs(docs);
 
         let name = name.to_upper_camel_case();
@@ -1273,16 +1438,42 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let cases = enum_
             .cases
             .iter()
-            .map(|case| case.name.to_shouty_snake_case())
+            .enumerate()
+            .map(|(i, case)| {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(case.name.to_shouty_snake_case()));
+                let mut attributes = Vec::new();
+                // Enum cases are visited as variant cases without a payload
+                let variant_case = Case {
+                    name: case.name.clone(),
+                    docs: case.docs.clone(),
+                    ty: None,
+                    annotations: case.annotations.clone(),
+                };
+                for contrib in self.csharp_gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, &variant_case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                format!(
+                    "{}{}",
+                    attribute_sections(&attributes),
+                    case.name.to_shouty_snake_case()
+                )
+            })
             .collect::<Vec<_>>()
             .join(", ");
 
         let access = self.csharp_gen.access_modifier();
+        let attributes = attribute_sections(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            {access} enum {name} {{
+            {attributes}{access} enum {name} {{
                 {cases}
             }}
             "
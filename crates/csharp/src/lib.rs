@@ -1,6 +1,9 @@
-use wit_bindgen_core::WorldGenerator;
+use annotation_visitor::CSharpVisitor;
+use wit_bindgen_core::visitor::{register_builtins, VisitorList};
+use wit_bindgen_core::{Diagnostics, WorldGenerator};
 use wit_component::StringEncoding;
 
+pub mod annotation_visitor;
 mod csharp_ident;
 mod csproj;
 mod function;
@@ -34,17 +37,52 @@ pub struct Opts {
     /// Generate code for WIT `Result` types instead of exceptions
     #[cfg_attr(feature = "clap", arg(long))]
     pub with_wit_results: bool,
+
+    /// Fail generation on annotation problems, such as annotations without a
+    /// registered visitor, instead of reporting them as warnings.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub strict_annotations: bool,
+
+    /// Built-in annotation visitors to enable, out of `deprecated` and
+    /// `rename`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_annotation, value_delimiter = ','))]
+    pub annotations: Vec<String>,
+
+    /// Visitors for the annotations in the WIT, see [`VisitorList`].
+    #[cfg_attr(feature = "clap", arg(skip))]
+    pub visitors: VisitorList<CSharpVisitor>,
 }
 
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
-        Box::new(world_generator::CSharp {
+        let mut r = world_generator::CSharp {
             opts: self.clone(),
             ..world_generator::CSharp::default()
-        })
+        };
+        for visitor in self.visitors.create() {
+            r.visitor_map.register(visitor);
+        }
+        r.diagnostics = Diagnostics::new(self.strict_annotations);
+        register_builtins(
+            &mut r.visitor_map,
+            &mut r.diagnostics,
+            &self.annotations,
+            annotation_visitor::BUILTIN_ANNOTATIONS,
+            annotation_visitor::builtin_visitor,
+        );
+        Box::new(r)
     }
 }
 
+#[cfg(feature = "clap")]
+fn parse_annotation(s: &str) -> Result<String, String> {
+    wit_bindgen_core::visitor::parse_builtin(
+        s,
+        annotation_visitor::BUILTIN_ANNOTATIONS,
+        annotation_visitor::builtin_visitor,
+    )
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
 pub enum CSharpRuntime {
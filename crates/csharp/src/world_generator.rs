@@ -1,3 +1,6 @@
+use crate::annotation_visitor::{
+    attribute_sections, using_directives, CSharpModuleContribution, CSharpVisitor,
+};
 use crate::csharp_ident::ToCSharpIdent;
 use crate::function::ResourceInfo;
 use crate::interface::{InterfaceFragment, InterfaceGenerator, InterfaceTypeAndFragments};
@@ -8,7 +11,11 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::ops::Deref;
 use std::{iter, mem};
-use wit_bindgen_core::{uwrite, Direction, Files, InterfaceGenerator as _, WorldGenerator};
+use wit_bindgen_core::visitor::{check_world_annotations, VisitAnnotations, VisitorMap};
+use wit_bindgen_core::{
+    uwrite, Diagnostic, Diagnostics, Direction, Files, InterfaceGenerator as _, Location,
+    VisitContext, WorldGenerator,
+};
 use wit_component::WitPrinter;
 use wit_parser::abi::WasmType;
 use wit_parser::{
@@ -41,6 +48,10 @@ pub struct CSharp {
     pub(crate) all_resources: HashMap<TypeId, ResourceInfo>,
     pub(crate) world_resources: HashMap<TypeId, ResourceInfo>,
     pub(crate) import_funcs_called: bool,
+    pub(crate) world_id: Option<WorldId>,
+    pub(crate) visitor_map: VisitorMap<CSharpVisitor>,
+    pub(crate) diagnostics: Diagnostics,
+    pub(crate) world_module: CSharpModuleContribution,
 }
 
 impl CSharp {
@@ -61,19 +72,32 @@ impl CSharp {
         &'a mut self,
         resolve: &'a Resolve,
         name: &'a str,
+        interface: Option<InterfaceId>,
         direction: Direction,
     ) -> InterfaceGenerator<'a> {
         InterfaceGenerator {
             src: String::new(),
             csharp_interop_src: String::new(),
             stub: String::new(),
+            module: CSharpModuleContribution::new(),
             csharp_gen: self,
             resolve,
             name,
+            interface,
             direction,
         }
     }
 
+    /// Merges the module contributions of the world with those of
+    /// `fragments`, for the files generated from them.
+    fn module_contribution(&self, fragments: &[InterfaceFragment]) -> CSharpModuleContribution {
+        let mut module = self.world_module.clone();
+        for fragment in fragments {
+            module.extend(fragment.module.clone());
+        }
+        module
+    }
+
     // returns the qualifier and last part
     pub(crate) fn get_class_name_from_qualified_name(qualified_type: &str) -> (String, String) {
         let parts: Vec<&str> = qualified_type.split('.').collect();
@@ -89,10 +113,23 @@ impl CSharp {
     }
 }
 
+impl VisitAnnotations for CSharp {
+    type Visitor = CSharpVisitor;
+
+    fn visitors_and_diagnostics(&mut self) -> (&mut VisitorMap<CSharpVisitor>, &mut Diagnostics) {
+        (&mut self.visitor_map, &mut self.diagnostics)
+    }
+}
+
 impl WorldGenerator for CSharp {
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+        check_world_annotations(&self.visitor_map, &mut self.diagnostics, resolve, world)
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let name = &resolve.worlds[world].name;
         self.name = name.to_string();
+        self.world_id = Some(world);
         self.sizes.fill(resolve);
     }
 
@@ -105,7 +142,8 @@ impl WorldGenerator for CSharp {
     ) -> anyhow::Result<()> {
         let name = interface_name(self, resolve, key, Direction::Import);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name, Direction::Import);
+        let mut gen = self.interface(resolve, &name, Some(id), Direction::Import);
+        gen.visit_interface(id);
 
         let mut old_resources = mem::take(&mut gen.csharp_gen.all_resources);
         gen.types(id);
@@ -153,7 +191,7 @@ impl WorldGenerator for CSharp {
 
         let name = &format!("{}-world", resolve.worlds[world].name).to_upper_camel_case();
         let name = &format!("{name}.I{name}");
-        let mut gen = self.interface(resolve, name, Direction::Import);
+        let mut gen = self.interface(resolve, name, None, Direction::Import);
 
         for (resource, funcs) in by_resource(
             funcs.iter().copied(),
@@ -184,7 +222,8 @@ impl WorldGenerator for CSharp {
     ) -> anyhow::Result<()> {
         let name = interface_name(self, resolve, key, Direction::Export);
         self.interface_names.insert(id, name.clone());
-        let mut gen = self.interface(resolve, &name, Direction::Export);
+        let mut gen = self.interface(resolve, &name, Some(id), Direction::Export);
+        gen.visit_interface(id);
 
         let mut old_resources = mem::take(&mut gen.csharp_gen.all_resources);
         gen.types(id);
@@ -228,7 +267,7 @@ impl WorldGenerator for CSharp {
     ) -> anyhow::Result<()> {
         let name = &format!("{}-world", resolve.worlds[world].name).to_upper_camel_case();
         let name = &format!("{name}.I{name}");
-        let mut gen = self.interface(resolve, name, Direction::Export);
+        let mut gen = self.interface(resolve, name, None, Direction::Export);
 
         for (resource, funcs) in by_resource(funcs.iter().copied(), iter::empty()) {
             if let Some(resource) = resource {
@@ -257,7 +296,7 @@ impl WorldGenerator for CSharp {
     ) {
         let name = &format!("{}-world", resolve.worlds[world].name).to_upper_camel_case();
         let name = &format!("{name}.I{name}");
-        let mut gen = self.interface(resolve, name, Direction::Import);
+        let mut gen = self.interface(resolve, name, None, Direction::Import);
 
         let mut old_resources = mem::take(&mut gen.csharp_gen.all_resources);
         for (ty_name, ty) in types {
@@ -278,6 +317,18 @@ impl WorldGenerator for CSharp {
         }
 
         let world = &resolve.worlds[id];
+        let location = Location::for_world(resolve, id);
+        let cx = VisitContext::new(resolve, id);
+        for contrib in self.visit_annotations(
+            &location,
+            world.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_world(args, world, &cx),
+        ) {
+            self.world_module.extend(contrib);
+        }
+        self.diagnostics.bail_on_errors()?;
+
         let world_namespace = self.qualifier();
         let world_namespace = world_namespace.strip_suffix(".").unwrap();
         let namespace = format!("{world_namespace}");
@@ -293,6 +344,7 @@ impl WorldGenerator for CSharp {
         );
         let mut src = String::new();
         src.push_str(&header);
+        src.push_str(&using_directives(&self.world_module.usings));
 
         let access = self.access_modifier();
 
@@ -560,12 +612,19 @@ impl WorldGenerator for CSharp {
                 .collect::<Vec<_>>()
                 .join("\n");
 
+            let module = self.module_contribution(fragments);
+            let usings = using_directives(&module.usings);
+            let attributes = attribute_sections(&module.stub_attributes);
+            let members = module.stub_members.join("\n");
+
             let body = format!(
                 "{header}
+                 {usings}
                  namespace {fully_qualified_namespace};
 
-                 {access} partial class {stub_class_name} : {interface_or_class_name} {{
+                 {attributes}{access} partial class {stub_class_name} : {interface_or_class_name} {{
                     {body}
+                    {members}
                  }}
                 "
             );
@@ -678,10 +737,12 @@ impl WorldGenerator for CSharp {
                 .collect::<Vec<_>>()
                 .join("\n");
 
+            let usings = using_directives(&self.module_contribution(fragments).usings);
+
             if body.len() > 0 {
                 let body = format!(
                     "{header}
-
+                    {usings}
                     namespace {namespace};
 
                     {access} interface {interface_name} {{
@@ -703,7 +764,7 @@ impl WorldGenerator for CSharp {
             let class_name = interface_name.strip_prefix("I").unwrap();
             let body = format!(
                 "{header}
-
+                {usings}
                 namespace {namespace}
                 {{
                   {access} static class {class_name}Interop {{
@@ -725,6 +786,10 @@ impl WorldGenerator for CSharp {
 
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
 
 enum Stubs<'a> {
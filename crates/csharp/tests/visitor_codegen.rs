@@ -0,0 +1,241 @@
+// Runs the C# generator with visitors registered and checks that their
+// contributions show up in the generated files.
+
+use wit_bindgen_core::visitor::VisitorList;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, Files, VisitContext, Visitor};
+use wit_bindgen_csharp::annotation_visitor::*;
+use wit_bindgen_csharp::Opts;
+
+/// Generates bindings, with stubs, for the only world in `wit` and returns
+/// the generated C# files by name, with all whitespace runs collapsed to a
+/// single space.
+fn generate(
+    wit: &str,
+    visitors: impl Fn() -> Vec<Box<CSharpVisitor>> + Send + Sync + 'static,
+) -> Vec<(String, String)> {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        generate_stub: true,
+        skip_support_files: true,
+        visitors: VisitorList::new(visitors),
+        ..Default::default()
+    };
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    files
+        .iter()
+        .filter(|(name, _)| name.ends_with(".cs"))
+        .map(|(name, src)| {
+            let src = std::str::from_utf8(src)
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            (name.to_string(), src)
+        })
+        .collect()
+}
+
+/// Returns the contents of all `files` concatenated.
+fn all(files: &[(String, String)]) -> String {
+    files
+        .iter()
+        .map(|(_, src)| src.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renames fields like the built-in `RenameVisitor`, but imports the
+/// attribute's namespace from the world, to test world contributions.
+struct UsingRenameVisitor;
+
+impl Visitor for UsingRenameVisitor {
+    type Contributions = CSharpContributions;
+
+    fn target(&self) -> &str {
+        "rename"
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpFieldContribution> {
+        let name = annotation.positional().next()?.as_str()?;
+        let mut contrib = CSharpFieldContribution::new();
+        contrib.add_attribute(format!("JsonPropertyName({name:?})"));
+        Some(contrib)
+    }
+
+    fn visit_world(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpModuleContribution> {
+        let mut contrib = CSharpModuleContribution::new();
+        contrib.add_using("System.Text.Json.Serialization");
+        Some(contrib)
+    }
+}
+
+/// Adds a call counter to records and to the stub class of an interface.
+struct CountedVisitor;
+
+impl Visitor for CountedVisitor {
+    type Contributions = CSharpContributions;
+
+    fn target(&self) -> &str {
+        "counted"
+    }
+
+    fn visit_record(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpTypeContribution> {
+        let mut contrib = CSharpTypeContribution::new();
+        contrib.add_member("public static int Created;");
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<CSharpModuleContribution> {
+        let mut contrib = CSharpModuleContribution::new();
+        contrib.add_using("System.Diagnostics");
+        contrib.add_stub_attribute("DebuggerDisplay(\"{Calls}\")");
+        contrib.add_stub_member("public static int Calls;");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_attributes() {
+    let files = generate(
+        r#"
+            package test:cs;
+
+            interface api {
+                #deprecated("use point3")
+                record point {
+                    #rename("x_pos")
+                    x: u32,
+                    y: u32,
+                }
+
+                enum color {
+                    red,
+                    #deprecated
+                    green,
+                }
+
+                #deprecated("use distance3")
+                distance: func(a: point, b: point) -> u32;
+                paint: func(c: color);
+            }
+
+            #rename
+            world test {
+                import api;
+                export api;
+            }
+        "#,
+        || vec![Box::new(DeprecatedVisitor), Box::new(UsingRenameVisitor)],
+    );
+    let src = all(&files);
+
+    assert!(
+        src.contains("[global::System.Obsolete(\"use point3\")] public readonly struct Point {"),
+        "type attribute missing:\n{src}"
+    );
+    assert!(
+        src.contains("[JsonPropertyName(\"x_pos\")] public readonly uint x;"),
+        "field attribute missing:\n{src}"
+    );
+    assert!(
+        src.contains("RED, [global::System.Obsolete] GREEN"),
+        "enum case attribute missing:\n{src}"
+    );
+    assert!(
+        src.contains(
+            "[global::System.Obsolete(\"use distance3\")] public static unsafe uint Distance("
+        ),
+        "import attribute missing:\n{src}"
+    );
+    assert!(
+        src.contains("[global::System.Obsolete(\"use distance3\")] static abstract uint Distance("),
+        "interface method attribute missing:\n{src}"
+    );
+    assert!(
+        src.contains("[global::System.Obsolete(\"use distance3\")] public static uint Distance("),
+        "stub method attribute missing:\n{src}"
+    );
+    // World contributions apply to every file.
+    for (name, src) in &files {
+        assert!(
+            src.contains("using System.Text.Json.Serialization;"),
+            "using missing from {name}:\n{src}"
+        );
+    }
+}
+
+#[test]
+fn test_members_and_stub_classes() {
+    let files = generate(
+        r#"
+            package test:cs;
+
+            #counted
+            interface api {
+                #counted
+                record point {
+                    x: u32,
+                }
+
+                origin: func() -> point;
+            }
+
+            world test {
+                export api;
+            }
+        "#,
+        || vec![Box::new(CountedVisitor)],
+    );
+    let src = all(&files);
+
+    assert!(
+        src.contains("public static int Created; }"),
+        "type member missing:\n{src}"
+    );
+    let (_, stub) = files
+        .iter()
+        .find(|(_, src)| src.contains("partial class ApiImpl"))
+        .expect(&src);
+    assert!(
+        stub.contains("[DebuggerDisplay(\"{Calls}\")] public partial class ApiImpl : IApi {"),
+        "stub attribute missing:\n{stub}"
+    );
+    assert!(
+        stub.contains("public static int Calls; }"),
+        "stub member missing:\n{stub}"
+    );
+    // Interface contributions only apply to the interface's own files.
+    for (name, src) in &files {
+        assert_eq!(
+            src.contains("using System.Diagnostics;"),
+            name.contains("Api"),
+            "unexpected usings in {name}:\n{src}"
+        );
+    }
+}
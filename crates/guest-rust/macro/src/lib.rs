@@ -7,8 +7,10 @@ use syn::parse::{Error, Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{braced, token, LitStr, Token};
+use wit_bindgen_core::visitor::parse_builtin;
 use wit_bindgen_core::wit_parser::{PackageId, Resolve, UnresolvedPackageGroup, WorldId};
-use wit_bindgen_core::AsyncFilterSet;
+use wit_bindgen_core::{AsyncFilterSet, Diagnostic, Diagnostics, Severity};
+use wit_bindgen_rust::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
 use wit_bindgen_rust::{Opts, Ownership, WithOption};
 
 #[proc_macro]
@@ -148,9 +150,24 @@ impl Parse for Config {
                     Opt::DisableCustomSectionLinkHelpers(disable) => {
                         opts.disable_custom_section_link_helpers = disable.value();
                     }
+                    Opt::TraceCalls(enable) => {
+                        opts.trace_calls = enable.value();
+                    }
                     Opt::Debug(enable) => {
                         debug = enable.value();
                     }
+                    Opt::StrictAnnotations(enable) => {
+                        opts.strict_annotations = enable.value();
+                    }
+                    Opt::AnnotationPriority(priorities) => {
+                        opts.annotation_priority.extend(priorities);
+                    }
+                    Opt::AnnotationConfig(path) => {
+                        opts.annotation_config = Some(PathBuf::from(path.value()));
+                    }
+                    Opt::Annotations(list) => {
+                        opts.annotations.extend(list.iter().map(|name| name.to_string()));
+                    }
                     Opt::Async(val, span) => {
                         if async_configured {
                             return Err(Error::new(span, "cannot specify second async config"));
@@ -174,8 +191,13 @@ impl Parse for Config {
                 )]));
             }
         }
-        let (resolve, main_packages, files) =
+        let (resolve, main_packages, mut files) =
             parse_source(&source, &features).map_err(|err| anyhow_to_syn(call_site, err))?;
+        if let Some(path) = &mut opts.annotation_config {
+            let root = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+            *path = root.join(&path);
+            files.push(path.clone());
+        }
         let world = resolve
             .select_world(&main_packages, world.as_deref())
             .map_err(|e| anyhow_to_syn(call_site, e))?;
@@ -244,9 +266,19 @@ impl Config {
     fn expand(self) -> Result<TokenStream> {
         let mut files = Default::default();
         let mut generator = self.opts.build();
-        generator
-            .generate(&self.resolve, self.world, &mut files)
-            .map_err(|e| anyhow_to_syn(Span::call_site(), e))?;
+        let result = generator.generate(&self.resolve, self.world, &mut files);
+
+        let mut diagnostics = generator.take_diagnostics();
+        Diagnostics::locate_in_files(&mut diagnostics, &self.files);
+        let mut errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| Error::new(Span::call_site(), d));
+        if let Some(mut error) = errors.next() {
+            error.extend(errors);
+            return Err(error);
+        }
+        result.map_err(|e| anyhow_to_syn(Span::call_site(), e))?;
         let (_, src) = files.iter().next().unwrap();
         let mut src = std::str::from_utf8(src).unwrap().to_string();
 
@@ -272,6 +304,10 @@ impl Config {
         }
         let mut contents = src.parse::<TokenStream>().unwrap();
 
+        for diagnostic in diagnostics.iter() {
+            contents.extend(warning(diagnostic));
+        }
+
         // Include a dummy `include_bytes!` for any files we read so rustc knows that
         // we depend on the contents of those files.
         for file in self.files.iter() {
@@ -289,6 +325,20 @@ impl Config {
     }
 }
 
+/// Proc macros can't emit warnings on stable, so a warning is surfaced by
+/// referring to a deprecated item whose note carries the message.
+fn warning(diagnostic: &Diagnostic) -> TokenStream {
+    let note = diagnostic.to_string();
+    quote::quote! {
+        const _: () = {
+            #[deprecated(note = #note)]
+            #[allow(non_camel_case_types)]
+            struct wit_bindgen_annotation_warning;
+            let _ = wit_bindgen_annotation_warning;
+        };
+    }
+}
+
 mod kw {
     syn::custom_keyword!(std_feature);
     syn::custom_keyword!(raw_strings);
@@ -314,8 +364,13 @@ mod kw {
     syn::custom_keyword!(generate_unused_types);
     syn::custom_keyword!(features);
     syn::custom_keyword!(disable_custom_section_link_helpers);
+    syn::custom_keyword!(trace_calls);
     syn::custom_keyword!(imports);
     syn::custom_keyword!(debug);
+    syn::custom_keyword!(strict_annotations);
+    syn::custom_keyword!(annotations);
+    syn::custom_keyword!(annotation_priority);
+    syn::custom_keyword!(annotation_config);
 }
 
 #[derive(Clone)]
@@ -370,8 +425,13 @@ enum Opt {
     GenerateUnusedTypes(syn::LitBool),
     Features(Vec<syn::LitStr>),
     DisableCustomSectionLinkHelpers(syn::LitBool),
+    TraceCalls(syn::LitBool),
     Async(AsyncFilterSet, Span),
     Debug(syn::LitBool),
+    StrictAnnotations(syn::LitBool),
+    Annotations(Vec<syn::Ident>),
+    AnnotationPriority(Vec<(String, i32)>),
+    AnnotationConfig(syn::LitStr),
 }
 
 impl Parse for Opt {
@@ -526,10 +586,47 @@ impl Parse for Opt {
             input.parse::<kw::disable_custom_section_link_helpers>()?;
             input.parse::<Token![:]>()?;
             Ok(Opt::DisableCustomSectionLinkHelpers(input.parse()?))
+        } else if l.peek(kw::trace_calls) {
+            input.parse::<kw::trace_calls>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::TraceCalls(input.parse()?))
         } else if l.peek(kw::debug) {
             input.parse::<kw::debug>()?;
             input.parse::<Token![:]>()?;
             Ok(Opt::Debug(input.parse()?))
+        } else if l.peek(kw::strict_annotations) {
+            input.parse::<kw::strict_annotations>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::StrictAnnotations(input.parse()?))
+        } else if l.peek(kw::annotations) {
+            input.parse::<kw::annotations>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::bracketed!(contents in input);
+            let list = Punctuated::<syn::Ident, Token![,]>::parse_terminated(&contents)?;
+            for name in list.iter() {
+                parse_builtin(&name.to_string(), BUILTIN_ANNOTATIONS, builtin_visitor)
+                    .map_err(|e| Error::new(name.span(), e))?;
+            }
+            Ok(Opt::Annotations(list.into_iter().collect()))
+        } else if l.peek(kw::annotation_priority) {
+            input.parse::<kw::annotation_priority>()?;
+            input.parse::<Token![:]>()?;
+            let contents;
+            syn::braced!(contents in input);
+            let fields = Punctuated::<_, Token![,]>::parse_terminated_with(&contents, |input| {
+                let target = input.parse::<syn::Ident>()?;
+                input.parse::<Token![:]>()?;
+                let negative = input.parse::<Option<Token![-]>>()?.is_some();
+                let priority: i32 = input.parse::<syn::LitInt>()?.base10_parse()?;
+                let priority = if negative { -priority } else { priority };
+                Ok((target.to_string(), priority))
+            })?;
+            Ok(Opt::AnnotationPriority(fields.into_iter().collect()))
+        } else if l.peek(kw::annotation_config) {
+            input.parse::<kw::annotation_config>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::AnnotationConfig(input.parse()?))
         } else if l.peek(Token![async]) {
             let span = input.parse::<Token![async]>()?.span;
             input.parse::<Token![:]>()?;
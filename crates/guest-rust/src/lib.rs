@@ -834,6 +834,13 @@ extern crate std;
 ///     // `#[used]` statics being emitted.
 ///     disable_custom_section_link_helpers: false,
 ///
+///     // Report all calls through import wrappers and export glue, with their
+///     // arguments and result, to the observer set with
+///     // `wit_bindgen::rt::set_call_observer`, as the `trace` annotation
+///     // visitor does for single functions and interfaces. This is `false`
+///     // by default.
+///     trace_calls: true,
+///
 ///     // Write generated code to a .rs file, which allows the compiler to
 ///     // emit more useful diagnostics for errors in the generated code.  This
 ///     // is primarily useful for `wit-bindgen` developers.
@@ -843,6 +850,32 @@ extern crate std;
 ///     // the specific `generate!` call where it is used.
 ///     debug: true,
 ///
+///     // Report problems with `#target` annotations in the WIT, such as
+///     // targets no visitor is registered for, as compile errors instead of
+///     // warnings. This is `false` by default.
+///     strict_annotations: true,
+///
+///     // Built-in visitors to apply for `#target` annotations in the WIT.
+///     // Available visitors are `derive`, `serde`, `repr`, `cfg`,
+///     // `deprecated`, `must_use`, `inline`, `assert`, the validation rules
+///     // `non_empty`, `min_length`, `max_length`, `range`, `finite` and
+///     // `email_format`, `validation_error` and `trace`. By default no
+///     // visitors are enabled and annotations are ignored with a warning.
+///     // Annotations placed on items or given arguments their visitor doesn't
+///     // accept, e.g. `#repr(u8)` on a function, are always compile errors.
+///     annotations: [derive, serde],
+///
+///     // Priorities replacing those of the visitors for some targets, which
+///     // order their contributions to an item: lower priorities come first.
+///     // See `wit_bindgen_core::visitor::priority` for the bands in use.
+///     annotation_priority: { trace: -200 },
+///
+///     // Path to a TOML file, relative to the crate root, that describes
+///     // visitors for annotations as string templates. See
+///     // `wit_bindgen_rust::annotation_visitor::TemplateVisitor` for the
+///     // format of this file.
+///     annotation_config: "annotations.toml",
+///
 ///     // Generate async import and/or export bindings.
 ///     //
 ///     // The resulting bindings will use the component model
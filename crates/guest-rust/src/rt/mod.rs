@@ -1,4 +1,5 @@
 use core::alloc::Layout;
+use core::fmt;
 use core::ptr::{self, NonNull};
 
 // Re-export `bitflags` so that we can reference it from macros.
@@ -228,3 +229,169 @@ impl Drop for Cleanup {
         }
     }
 }
+
+/// Checks a value against the validation annotations on its fields or cases,
+/// e.g. `#range(18, 120)`.
+///
+/// wit-bindgen-rust implements this for records and variants with such
+/// annotations, and for those containing them, and checks the arguments of
+/// exports with it after lifting them. The error describes the first check
+/// that failed, e.g. `"user.age must be in 18..=120"`.
+pub trait Validate {
+    fn validate(&self) -> Result<(), &'static str>;
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Result<(), &'static str> {
+        self.as_ref().map_or(Ok(()), T::validate)
+    }
+}
+
+impl<T: Validate> Validate for [T] {
+    fn validate(&self) -> Result<(), &'static str> {
+        self.iter().try_for_each(T::validate)
+    }
+}
+
+impl<T: Validate> Validate for alloc::vec::Vec<T> {
+    fn validate(&self) -> Result<(), &'static str> {
+        self.as_slice().validate()
+    }
+}
+
+impl<T: Validate, E: Validate> Validate for Result<T, E> {
+    fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            Ok(value) => value.validate(),
+            Err(error) => error.validate(),
+        }
+    }
+}
+
+// Tuples and results holding a validated type are checked through these, so
+// the values they hold alongside it have nothing to check.
+macro_rules! validate_nothing {
+    ($($ty:ty)*) => {$(
+        impl Validate for $ty {
+            fn validate(&self) -> Result<(), &'static str> {
+                Ok(())
+            }
+        }
+    )*};
+}
+
+validate_nothing!(() bool u8 u16 u32 u64 i8 i16 i32 i64 f32 f64 char str alloc::string::String);
+
+macro_rules! validate_tuples {
+    ($(($($name:ident)*))*) => {$(
+        impl<$($name: Validate),*> Validate for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn validate(&self) -> Result<(), &'static str> {
+                let ($($name,)*) = self;
+                $($name.validate()?;)*
+                Ok(())
+            }
+        }
+    )*};
+}
+
+validate_tuples! {
+    (A)
+    (A B)
+    (A B C)
+    (A B C D)
+    (A B C D E)
+    (A B C D E F)
+    (A B C D E F G)
+    (A B C D E F G H)
+    (A B C D E F G H I)
+    (A B C D E F G H I J)
+    (A B C D E F G H I J K)
+    (A B C D E F G H I J K L)
+}
+
+/// Identifies a call reported to a [`CallObserver`].
+#[derive(Debug, Clone, Copy)]
+pub struct CallSite {
+    /// The name of the interface the function belongs to, e.g.
+    /// `wasi:cli/run`, or `None` for functions of the world itself
+    pub interface: Option<&'static str>,
+
+    /// The WIT name of the function, e.g. `[method]blob.read`
+    pub function: &'static str,
+
+    /// Whether this is a call into an export of this component, rather than
+    /// out of it through an import
+    pub export: bool,
+}
+
+/// Reported to a [`CallObserver`] in place of arguments and results whose
+/// types might not implement `Debug`, such as types remapped with `with`.
+#[derive(Clone, Copy)]
+pub struct Opaque;
+
+impl fmt::Debug for Opaque {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("..")
+    }
+}
+
+/// Observes calls through the import wrappers and export glue of functions
+/// traced with the `trace` annotation visitor or the `trace_calls` option.
+///
+/// One observer can be set for the whole program with [`set_call_observer`].
+/// Without the `std` feature no observer can be set and tracing does
+/// nothing.
+pub trait CallObserver: Sync {
+    /// Called before the function runs, with its arguments in WIT order.
+    fn enter(&self, call: &CallSite, args: &[&dyn fmt::Debug]);
+
+    /// Called after the function returns, with its result, or `()` for
+    /// functions without one.
+    fn exit(&self, call: &CallSite, result: &dyn fmt::Debug);
+}
+
+#[cfg(feature = "std")]
+static CALL_OBSERVER: std::sync::OnceLock<&'static dyn CallObserver> = std::sync::OnceLock::new();
+
+/// Sets the observer of traced calls.
+///
+/// This can only be done once: if an observer was already set, `observer` is
+/// handed back as the error. Without the `std` feature this does nothing.
+pub fn set_call_observer(
+    observer: &'static dyn CallObserver,
+) -> Result<(), &'static dyn CallObserver> {
+    #[cfg(feature = "std")]
+    return CALL_OBSERVER.set(observer);
+    #[cfg(not(feature = "std"))]
+    {
+        let _ = observer;
+        Ok(())
+    }
+}
+
+/// Reports the start of a traced call to the observer, if one is set.
+///
+/// This is called by generated bindings.
+#[inline]
+pub fn trace_enter(call: &CallSite, args: &[&dyn fmt::Debug]) {
+    #[cfg(feature = "std")]
+    if let Some(observer) = CALL_OBSERVER.get() {
+        observer.enter(call, args);
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = (call, args);
+}
+
+/// Reports the end of a traced call to the observer, if one is set.
+///
+/// This is called by generated bindings.
+#[inline]
+pub fn trace_exit(call: &CallSite, result: &dyn fmt::Debug) {
+    #[cfg(feature = "std")]
+    if let Some(observer) = CALL_OBSERVER.get() {
+        observer.exit(call, result);
+    }
+    #[cfg(not(feature = "std"))]
+    let _ = (call, result);
+}
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use heck::*;
 use pulldown_cmark::{html, Event, LinkType, Parser, Tag};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 use wit_bindgen_core::{
     uwriteln, wit_parser, Files, InterfaceGenerator as _, Source, WorldGenerator,
@@ -14,6 +14,20 @@ struct Markdown {
     opts: Opts,
     hrefs: HashMap<String, String>,
     sizes: SizeAlign,
+    /// Items carrying annotations, keyed by the annotation's target, for the
+    /// annotation index.
+    annotated: BTreeMap<String, Vec<AnnotatedItem>>,
+}
+
+/// An annotated item listed in the annotation index.
+struct AnnotatedItem {
+    /// The item's name, qualified by its interface if it has one, e.g.
+    /// `test:md/shapes::point::x`.
+    name: String,
+    /// The item's anchor in the world's page, e.g. `point.x`.
+    anchor: String,
+    /// The annotation as written in the WIT, e.g. `#range(18, 120)`.
+    annotation: String,
 }
 
 #[derive(Default, Debug, Clone)]
@@ -52,10 +66,12 @@ impl WorldGenerator for Markdown {
             format!("#{}", world.name.to_snake_case()),
         );
 
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, None);
 
         gen.docs(&world.docs);
         gen.push_str("\n");
+        let annotations = annotation_pairs(world.annotations.iter());
+        gen.annotations(&annotations, &world.name, &world.name.to_snake_case());
 
         // Produce a table of contents for the world.
         let mut first = true;
@@ -126,9 +142,10 @@ impl WorldGenerator for Markdown {
         );
         self.hrefs
             .insert(name.to_string(), format!("#{}", name.to_snake_case()));
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, Some(&name));
         gen.docs(&resolve.interfaces[id].docs);
         gen.push_str("\n");
+        gen.interface_annotations(id, &name);
         gen.types(id);
         gen.funcs(id);
 
@@ -144,7 +161,7 @@ impl WorldGenerator for Markdown {
     ) {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Imported functions to world `{name}`\n");
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, None);
         for (_, func) in funcs {
             gen.func(func);
         }
@@ -165,7 +182,8 @@ impl WorldGenerator for Markdown {
         );
         self.hrefs
             .insert(name.to_string(), format!("#{}", name.to_snake_case()));
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, Some(&name));
+        gen.interface_annotations(id, &name);
         gen.types(id);
         gen.funcs(id);
         Ok(())
@@ -180,7 +198,7 @@ impl WorldGenerator for Markdown {
     ) -> Result<()> {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Exported functions from world `{name}`\n");
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, None);
         for (_, func) in funcs {
             gen.func(func);
         }
@@ -196,7 +214,7 @@ impl WorldGenerator for Markdown {
     ) {
         let name = &resolve.worlds[world].name;
         uwriteln!(self.src, "## Exported types from world `{name}`\n");
-        let mut gen = self.interface(resolve);
+        let mut gen = self.interface(resolve, None);
         for (name, ty) in types {
             gen.define_type(name, *ty);
         }
@@ -230,23 +248,90 @@ impl WorldGenerator for Markdown {
             files.push(&format!("{}.html", world.name), html_output.as_bytes());
         }
 
+        if !self.annotated.is_empty() {
+            // Also write an index of the annotated items, linking into the
+            // page written above.
+            let index = format!("{}-annotations", world.name);
+            let src = self.annotation_index(&world.name, &format!("{}.md", world.name));
+            let mut html_output = String::new();
+            if self.opts.html_in_md {
+                html::push_html(&mut html_output, Parser::new(&src));
+                files.push(&format!("{index}.md"), html_output.as_bytes());
+            } else {
+                files.push(&format!("{index}.md"), src.as_bytes());
+                let src = self.annotation_index(&world.name, &format!("{}.html", world.name));
+                html::push_html(&mut html_output, Parser::new(&src));
+                files.push(&format!("{index}.html"), html_output.as_bytes());
+            }
+        }
+
         Ok(())
     }
 }
 
 impl Markdown {
-    fn interface<'a>(&'a mut self, resolve: &'a Resolve) -> InterfaceGenerator<'a> {
+    /// Returns a generator for the items of the interface named `interface`,
+    /// or of the world if `None`.
+    fn interface<'a>(
+        &'a mut self,
+        resolve: &'a Resolve,
+        interface: Option<&str>,
+    ) -> InterfaceGenerator<'a> {
         InterfaceGenerator {
             gen: self,
             resolve,
+            interface: interface.map(|name| name.to_string()),
             types_header_printed: false,
         }
     }
+
+    /// Renders the annotation index, which lists the items carrying each
+    /// annotation target, with links into `page`.
+    fn annotation_index(&self, world: &str, page: &str) -> String {
+        let mut src = format!("# Annotation index for world `{world}`\n\n");
+        for (target, items) in &self.annotated {
+            uwriteln!(src, "## `#{target}`\n");
+            for item in items {
+                uwriteln!(
+                    src,
+                    "- [`{}`]({page}#{}): `{}`",
+                    item.name,
+                    item.anchor,
+                    item.annotation
+                );
+            }
+            src.push_str("\n");
+        }
+        src
+    }
+}
+
+/// Collects the `(target, value)` pairs of an item's annotations.
+fn annotation_pairs<T: ToString, V: ToString>(
+    annotations: impl IntoIterator<Item = (T, V)>,
+) -> Vec<(String, String)> {
+    annotations
+        .into_iter()
+        .map(|(target, value)| (target.to_string(), value.to_string()))
+        .collect()
+}
+
+/// Renders an annotation as written in the WIT, e.g. `#deprecated` or
+/// `#range(18, 120)`.
+fn render_annotation(target: &str, value: &str) -> String {
+    if value.is_empty() {
+        format!("#{target}")
+    } else {
+        format!("#{target}({value})")
+    }
 }
 
 struct InterfaceGenerator<'a> {
     gen: &'a mut Markdown,
     resolve: &'a Resolve,
+    /// The name of the interface being documented, qualifying the names of
+    /// its items in the annotation index, or `None` for the world's items.
+    interface: Option<String>,
     types_header_printed: bool,
 }
 
@@ -275,6 +360,9 @@ impl InterfaceGenerator<'_> {
         self.push_str(": func`");
         self.push_str("\n\n");
         self.docs(&func.docs);
+        let annotations = annotation_pairs(func.annotations.iter());
+        let name = self.qualified(&func.name);
+        self.annotations(&annotations, &name, &func.name.to_snake_case());
 
         if func.params.len() > 0 {
             self.push_str("\n");
@@ -308,6 +396,71 @@ impl InterfaceGenerator<'_> {
         self.gen.src.push_str(s);
     }
 
+    /// Returns the name of the item `name` in the annotation index, qualified
+    /// by its interface, e.g. `test:md/users::user`.
+    fn qualified(&self, name: &str) -> String {
+        match &self.interface {
+            Some(interface) => format!("{interface}::{name}"),
+            None => name.to_string(),
+        }
+    }
+
+    /// Records `annotations`, as `(target, value)` pairs, on the item `name`
+    /// at `anchor` for the annotation index.
+    ///
+    /// Items documented twice, like an interface that is both imported and
+    /// exported, are only listed once.
+    fn index_annotations(&mut self, annotations: &[(String, String)], name: &str, anchor: &str) {
+        for (target, value) in annotations {
+            let annotation = render_annotation(target, value);
+            let items = self.gen.annotated.entry(target.clone()).or_default();
+            let listed = |item: &AnnotatedItem| {
+                item.name == name && item.anchor == anchor && item.annotation == annotation
+            };
+            if items.iter().any(listed) {
+                continue;
+            }
+            items.push(AnnotatedItem {
+                name: name.to_string(),
+                anchor: anchor.to_string(),
+                annotation,
+            });
+        }
+    }
+
+    /// Prints an "Annotations" list for the item `name` at `anchor`, if it has
+    /// any annotations.
+    fn annotations(&mut self, annotations: &[(String, String)], name: &str, anchor: &str) {
+        if annotations.is_empty() {
+            return;
+        }
+        self.push_str("\n##### Annotations\n\n");
+        for (target, value) in annotations {
+            self.push_str(&format!("- `{}`\n", render_annotation(target, value)));
+        }
+        self.push_str("\n");
+        self.index_annotations(annotations, name, anchor);
+    }
+
+    /// Prints the annotations of a field, case or flag inline, as badges
+    /// after its list item.
+    fn annotation_badges(&mut self, annotations: &[(String, String)], name: &str, anchor: &str) {
+        for (target, value) in annotations {
+            self.push_str(&format!(" `{}`", render_annotation(target, value)));
+        }
+        self.index_annotations(annotations, name, anchor);
+    }
+
+    fn interface_annotations(&mut self, id: InterfaceId, name: &str) {
+        let annotations = annotation_pairs(self.resolve.interfaces[id].annotations.iter());
+        self.annotations(&annotations, name, &name.to_snake_case());
+    }
+
+    fn type_annotations(&mut self, id: TypeId, name: &str) {
+        let annotations = annotation_pairs(self.resolve.types[id].annotations.iter());
+        self.annotations(&annotations, &self.qualified(name), &name.to_snake_case());
+    }
+
     fn print_ty(&mut self, ty: &Type) {
         match ty {
             Type::Bool => self.push_str("`bool`"),
@@ -459,10 +612,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.resolve
     }
 
-    fn type_record(&mut self, _id: TypeId, name: &str, record: &Record, docs: &Docs) {
+    fn type_record(&mut self, id: TypeId, name: &str, record: &Record, docs: &Docs) {
         self.print_type_header("record", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
         self.push_str("\n##### Record Fields\n\n");
         for field in record.fields.iter() {
             self.push_str(&format!(
@@ -476,6 +630,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 format!("#{}.{}", name.to_snake_case(), field.name.to_snake_case()),
             );
             self.print_ty(&field.ty);
+            let annotations = annotation_pairs(field.annotations.iter());
+            self.annotation_badges(
+                &annotations,
+                &self.qualified(&format!("{}::{}", name, field.name)),
+                &format!("{}.{}", name.to_snake_case(), field.name.to_snake_case()),
+            );
             if field.docs.contents.is_some() {
                 self.gen.src.indent(1);
                 self.push_str("\n<p>");
@@ -487,16 +647,18 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.push_str("\n");
     }
 
-    fn type_resource(&mut self, _id: TypeId, name: &str, docs: &Docs) {
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
         self.print_type_header("resource", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
     }
 
-    fn type_tuple(&mut self, _id: TypeId, name: &str, tuple: &Tuple, docs: &Docs) {
+    fn type_tuple(&mut self, id: TypeId, name: &str, tuple: &Tuple, docs: &Docs) {
         self.print_type_header("tuple", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
         self.push_str("\n##### Tuple Fields\n\n");
         for (i, ty) in tuple.types.iter().enumerate() {
             self.push_str(&format!(
@@ -515,10 +677,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.push_str("\n");
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
         self.print_type_header("flags", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
         self.push_str("\n##### Flags members\n\n");
         for flag in flags.flags.iter() {
             self.push_str(&format!(
@@ -531,6 +694,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 format!("{}::{}", name, flag.name),
                 format!("#{}.{}", name.to_snake_case(), flag.name.to_snake_case()),
             );
+            let annotations = annotation_pairs(flag.annotations.iter());
+            self.annotation_badges(
+                &annotations,
+                &self.qualified(&format!("{}::{}", name, flag.name)),
+                &format!("{}.{}", name.to_snake_case(), flag.name.to_snake_case()),
+            );
             if flag.docs.contents.is_some() {
                 self.gen.src.indent(1);
                 self.push_str("\n<p>");
@@ -542,10 +711,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.push_str("\n");
     }
 
-    fn type_variant(&mut self, _id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+    fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
         self.print_type_header("variant", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
         self.push_str("\n##### Variant Cases\n\n");
         for case in variant.cases.iter() {
             self.push_str(&format!(
@@ -562,6 +732,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 self.push_str(": ");
                 self.print_ty(ty);
             }
+            let annotations = annotation_pairs(case.annotations.iter());
+            self.annotation_badges(
+                &annotations,
+                &self.qualified(&format!("{}::{}", name, case.name)),
+                &format!("{}.{}", name.to_snake_case(), case.name.to_snake_case()),
+            );
             if case.docs.contents.is_some() {
                 self.gen.src.indent(1);
                 self.push_str("\n<p>");
@@ -573,10 +749,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.push_str("\n");
     }
 
-    fn type_enum(&mut self, _id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
         self.print_type_header("enum", name);
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
         self.push_str("\n##### Enum Cases\n\n");
         for case in enum_.cases.iter() {
             self.push_str(&format!(
@@ -589,6 +766,12 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 format!("{}::{}", name, case.name),
                 format!("#{}.{}", name.to_snake_case(), case.name.to_snake_case()),
             );
+            let annotations = annotation_pairs(case.annotations.iter());
+            self.annotation_badges(
+                &annotations,
+                &self.qualified(&format!("{}::{}", name, case.name)),
+                &format!("{}.{}", name.to_snake_case(), case.name.to_snake_case()),
+            );
             if case.docs.contents.is_some() {
                 self.gen.src.indent(1);
                 self.push_str("\n<p>");
@@ -600,16 +783,17 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.push_str("\n");
     }
 
-    fn type_option(&mut self, _id: TypeId, name: &str, payload: &Type, docs: &Docs) {
+    fn type_option(&mut self, id: TypeId, name: &str, payload: &Type, docs: &Docs) {
         self.print_type_header("type", name);
         self.push_str("option<");
         self.print_ty(payload);
         self.push_str(">");
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
     }
 
-    fn type_result(&mut self, _id: TypeId, name: &str, result: &Result_, docs: &Docs) {
+    fn type_result(&mut self, id: TypeId, name: &str, result: &Result_, docs: &Docs) {
         self.print_type_header("type", name);
         match (result.ok, result.err) {
             (Some(ok), Some(err)) => {
@@ -635,14 +819,16 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         }
         self.push_str("\n");
         self.docs(docs);
+        self.type_annotations(id, name);
     }
 
-    fn type_alias(&mut self, _id: TypeId, name: &str, ty: &Type, docs: &Docs) {
+    fn type_alias(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs) {
         self.print_type_header("type", name);
         self.print_ty(ty);
         self.push_str("\n<p>");
         self.docs(docs);
         self.push_str("\n");
+        self.type_annotations(id, name);
     }
 
     fn type_list(&mut self, id: TypeId, name: &str, _ty: &Type, docs: &Docs) {
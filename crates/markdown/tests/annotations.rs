@@ -0,0 +1,193 @@
+// Runs the Markdown generator on annotated WIT and checks that annotations
+// are rendered on their items and listed in the annotation index.
+
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::Files;
+use wit_bindgen_markdown::Opts;
+
+/// Generates docs for the only world in `wit` and returns the generated
+/// files by name.
+fn generate(wit: &str) -> Vec<(String, String)> {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let mut files = Files::default();
+    Opts::default()
+        .build()
+        .generate(&resolve, world, &mut files)
+        .unwrap();
+    files
+        .iter()
+        .map(|(name, src)| (name.to_string(), String::from_utf8(src.to_vec()).unwrap()))
+        .collect()
+}
+
+/// Returns the contents of the file called `name`.
+fn file<'a>(files: &'a [(String, String)], name: &str) -> &'a str {
+    files
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, src)| src.as_str())
+        .unwrap_or_else(|| panic!("{name} was not generated"))
+}
+
+#[test]
+fn test_annotations_are_rendered() {
+    let files = generate(
+        r#"
+            package test:md;
+
+            #stable
+            interface users {
+                #deprecated("use person")
+                record user {
+                    #range(18, 120)
+                    age: u8,
+                    #max_length(255)
+                    name: string,
+                }
+
+                enum role {
+                    #deprecated
+                    guest,
+                    admin,
+                }
+
+                #deprecated
+                add: func(u: user);
+            }
+
+            world test {
+                import users;
+            }
+        "#,
+    );
+    let md = file(&files, "test.md");
+
+    assert!(
+        md.contains("##### Annotations\n\n- `#stable`\n"),
+        "interface annotations missing:\n{md}"
+    );
+    assert!(
+        md.contains("##### Annotations\n\n- `#deprecated(\"use person\")`\n"),
+        "type annotations missing:\n{md}"
+    );
+    assert!(
+        md.contains("`age`: `u8` `#range(18, 120)`"),
+        "field badge missing:\n{md}"
+    );
+    assert!(
+        md.contains("`name`: `string` `#max_length(255)`"),
+        "field badge missing:\n{md}"
+    );
+    assert!(
+        md.contains("`guest` `#deprecated`"),
+        "case badge missing:\n{md}"
+    );
+    let add = md.find("`add: func`").expect(md);
+    assert!(
+        md[add..].contains("##### Annotations\n\n- `#deprecated`\n"),
+        "function annotations missing:\n{md}"
+    );
+}
+
+#[test]
+fn test_annotation_index() {
+    let files = generate(
+        r#"
+            package test:md;
+
+            interface users {
+                #deprecated("use person")
+                record user {
+                    #range(18, 120)
+                    age: u8,
+                }
+
+                #deprecated
+                add: func(u: user);
+            }
+
+            world test {
+                import users;
+            }
+        "#,
+    );
+    let index = file(&files, "test-annotations.md");
+
+    let deprecated = index.find("## `#deprecated`").expect(index);
+    let range = index.find("## `#range`").expect(index);
+    assert!(deprecated < range, "targets should be sorted:\n{index}");
+    assert!(
+        index.contains("- [`test:md/users::user`](test.md#user): `#deprecated(\"use person\")`"),
+        "type entry missing:\n{index}"
+    );
+    assert!(
+        index.contains("- [`test:md/users::add`](test.md#add): `#deprecated`"),
+        "function entry missing:\n{index}"
+    );
+    assert!(
+        index.contains("- [`test:md/users::user::age`](test.md#user.age): `#range(18, 120)`"),
+        "field entry missing:\n{index}"
+    );
+    assert!(
+        file(&files, "test-annotations.html").contains("href=\"test.html#user.age\""),
+        "html index should link to the html page"
+    );
+}
+
+#[test]
+fn test_annotation_index_lists_items_once() {
+    let files = generate(
+        r#"
+            package test:md;
+
+            #stable
+            interface users {
+                #deprecated
+                record user {
+                    #range(18, 120)
+                    age: u8,
+                }
+            }
+
+            world test {
+                import users;
+                export users;
+            }
+        "#,
+    );
+    let index = file(&files, "test-annotations.md");
+
+    // The interface is documented both as an import and as an export, but
+    // its items are only listed once.
+    for entry in [
+        "- [`test:md/users`]",
+        "- [`test:md/users::user`]",
+        "- [`test:md/users::user::age`]",
+    ] {
+        assert_eq!(index.matches(entry).count(), 1, "{entry}:\n{index}");
+    }
+}
+
+#[test]
+fn test_no_index_without_annotations() {
+    let files = generate(
+        r#"
+            package test:md;
+
+            interface users {
+                record user {
+                    age: u8,
+                }
+            }
+
+            world test {
+                import users;
+            }
+        "#,
+    );
+
+    assert!(!file(&files, "test.md").contains("Annotations"));
+    assert!(files.iter().all(|(name, _)| !name.contains("annotations")));
+}
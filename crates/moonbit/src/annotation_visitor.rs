@@ -0,0 +1,349 @@
+use wit_bindgen_core::visitor::{AnnotationSchema, AnnotationValue, ArgKind, ItemKind};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, ContributionTypes, VisitContext, Visitor};
+
+/// Contributions for type definitions
+#[derive(Default, Debug, Clone)]
+pub struct MoonBitTypeContribution {
+    /// Traits to add to the type's `derive(...)` list, e.g. `ToJson`, on top
+    /// of those enabled for all types by `--derive-show` and `--derive-eq`
+    pub derives: Vec<String>,
+
+    /// Attributes to add to the type, e.g. `deprecated("...")`, rendered as
+    /// `#...`
+    pub attributes: Vec<String>,
+
+    /// Top-level code to add after the type, e.g. a trait `impl`
+    pub code: Vec<String>,
+}
+
+impl MoonBitTypeContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a derive
+    pub fn add_derive(&mut self, derive: impl Into<String>) {
+        self.derives.push(derive.into());
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add top-level code after the type
+    pub fn add_code(&mut self, code: impl Into<String>) {
+        self.code.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.derives.is_empty() && self.attributes.is_empty() && self.code.is_empty()
+    }
+}
+
+/// Contributions for struct fields
+#[derive(Default, Debug, Clone)]
+pub struct MoonBitFieldContribution {
+    /// Field-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl MoonBitFieldContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for variant and enum cases
+#[derive(Default, Debug, Clone)]
+pub struct MoonBitVariantCaseContribution {
+    /// Case-level attributes
+    pub attributes: Vec<String>,
+}
+
+impl MoonBitVariantCaseContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for functions
+#[derive(Default, Debug, Clone)]
+pub struct MoonBitFunctionContribution {
+    /// Attributes to add to the generated function: the import wrapper, or
+    /// the stub of an export
+    pub attributes: Vec<String>,
+}
+
+impl MoonBitFunctionContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+}
+
+/// Contributions for the package generated for an interface, or for the
+/// world
+#[derive(Default, Debug, Clone)]
+pub struct MoonBitModuleContribution {
+    /// Top-level code to add to the package
+    pub code: Vec<String>,
+}
+
+impl MoonBitModuleContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add top-level code
+    pub fn add_code(&mut self, code: impl Into<String>) {
+        self.code.push(code.into());
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.code.is_empty()
+    }
+}
+
+/// The family of MoonBit contribution types.
+pub struct MoonBitContributions;
+
+impl ContributionTypes for MoonBitContributions {
+    type Type = MoonBitTypeContribution;
+    type Field = MoonBitFieldContribution;
+    type VariantCase = MoonBitVariantCaseContribution;
+    type Function = MoonBitFunctionContribution;
+    type Module = MoonBitModuleContribution;
+    // Annotations naming a parameter are only offered to `visit_function`.
+    type Param = ();
+}
+
+/// MoonBit-specific visitor type alias.
+pub type MoonBitVisitor = dyn Visitor<Contributions = MoonBitContributions>;
+
+/// Renders `attributes` as one `#...` attribute per line, each followed by a
+/// newline, or nothing if there are none.
+pub(crate) fn attribute_lines(attributes: &[String]) -> String {
+    attributes.iter().map(|attr| format!("#{attr}\n")).collect()
+}
+
+/// The names of all built-in annotation visitors.
+pub const BUILTIN_ANNOTATIONS: &[&str] = &["derive", "deprecated"];
+
+/// Returns the built-in visitor for the annotation target `name`, if any.
+pub fn builtin_visitor(name: &str) -> Option<Box<MoonBitVisitor>> {
+    Some(match name {
+        "derive" => Box::new(DeriveVisitor),
+        "deprecated" => Box::new(DeprecatedVisitor),
+        _ => return None,
+    })
+}
+
+/// `#derive(ToJson, Hash)` adds traits to the `derive(...)` of a type, on
+/// top of those enabled for all types.
+pub struct DeriveVisitor;
+
+impl DeriveVisitor {
+    fn contribution(annotation: &AnnotationArgs) -> Option<MoonBitTypeContribution> {
+        let mut contrib = MoonBitTypeContribution::new();
+        for derive in annotation.positional() {
+            contrib.add_derive(derive.as_str()?);
+        }
+        Some(contrib)
+    }
+}
+
+impl Visitor for DeriveVisitor {
+    type Contributions = MoonBitContributions;
+
+    fn target(&self) -> &str {
+        "derive"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().rest(ArgKind::Ident))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::contribution(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::contribution(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::contribution(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::contribution(annotation)
+    }
+}
+
+/// `#deprecated` or `#deprecated("use x instead")` marks types and
+/// functions `#deprecated`.
+pub struct DeprecatedVisitor;
+
+impl DeprecatedVisitor {
+    fn attribute(annotation: &AnnotationArgs) -> String {
+        match annotation.positional().next() {
+            Some(msg @ AnnotationValue::String(_)) => format!("deprecated({msg})"),
+            _ => "deprecated".to_string(),
+        }
+    }
+
+    fn type_attribute(annotation: &AnnotationArgs) -> Option<MoonBitTypeContribution> {
+        let mut contrib = MoonBitTypeContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = MoonBitContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Function,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().optional_arg(ArgKind::String))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        Self::type_attribute(annotation)
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitFunctionContribution> {
+        let mut contrib = MoonBitFunctionContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
@@ -1,3 +1,7 @@
+use annotation_visitor::{
+    attribute_lines, MoonBitFunctionContribution, MoonBitModuleContribution,
+    MoonBitTypeContribution, MoonBitVisitor,
+};
 use anyhow::Result;
 use core::panic;
 use heck::{ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
@@ -10,15 +14,21 @@ use std::{
 use wit_bindgen_core::{
     abi::{self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmSignature, WasmType},
     dealias, uwrite, uwriteln,
+    visitor::{
+        check_world_annotations, register_builtins, VisitAnnotations, VisitorList, VisitorMap,
+    },
     wit_parser::{
-        Alignment, ArchitectureSize, Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle,
-        Int, InterfaceId, Record, Resolve, Result_, SizeAlign, Tuple, Type, TypeDef, TypeDefKind,
-        TypeId, TypeOwner, Variant, WorldId, WorldKey,
+        Alignment, ArchitectureSize, Case, Docs, Enum, Flags, FlagsRepr, Function, FunctionKind,
+        Handle, Int, InterfaceId, Record, Resolve, Result_, SizeAlign, Tuple, Type, TypeDef,
+        TypeDefKind, TypeId, TypeOwner, Variant, WorldId, WorldKey,
     },
-    AsyncFilterSet, Direction, Files, InterfaceGenerator as CoreInterfaceGenerator, Ns, Source,
+    AnnotationArgs, AsyncFilterSet, Diagnostic, Diagnostics, Direction, Files,
+    InterfaceGenerator as CoreInterfaceGenerator, Location, Ns, Source, VisitContext,
     WorldGenerator,
 };
 
+pub mod annotation_visitor;
+
 // Assumptions:
 // - Data: u8 -> Byte, s8 | s16 | s32 -> Int, u16 | u32 -> UInt, s64 -> Int64, u64 -> UInt64, f32 | f64 -> Double, address -> Int
 // - Encoding: UTF16
@@ -77,17 +87,52 @@ pub struct Opts {
 
     #[cfg_attr(feature = "clap", clap(flatten))]
     pub async_: AsyncFilterSet,
+
+    /// Fail generation on annotation problems, such as annotations without a
+    /// registered visitor, instead of reporting them as warnings.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
+    pub strict_annotations: bool,
+
+    /// Built-in annotation visitors to enable, out of `derive` and
+    /// `deprecated`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_annotation, value_delimiter = ','))]
+    pub annotations: Vec<String>,
+
+    /// Visitors for the annotations in the WIT, see [`VisitorList`].
+    #[cfg_attr(feature = "clap", arg(skip))]
+    pub visitors: VisitorList<MoonBitVisitor>,
 }
 
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
-        Box::new(MoonBit {
+        let mut r = MoonBit {
             opts: self.clone(),
             ..MoonBit::default()
-        })
+        };
+        for visitor in self.visitors.create() {
+            r.visitor_map.register(visitor);
+        }
+        r.diagnostics = Diagnostics::new(self.strict_annotations);
+        register_builtins(
+            &mut r.visitor_map,
+            &mut r.diagnostics,
+            &self.annotations,
+            annotation_visitor::BUILTIN_ANNOTATIONS,
+            annotation_visitor::builtin_visitor,
+        );
+        Box::new(r)
     }
 }
 
+#[cfg(feature = "clap")]
+fn parse_annotation(s: &str) -> Result<String, String> {
+    wit_bindgen_core::visitor::parse_builtin(
+        s,
+        annotation_visitor::BUILTIN_ANNOTATIONS,
+        annotation_visitor::builtin_visitor,
+    )
+}
+
 struct MoonbitSignature {
     name: String,
     params: Vec<(String, Type)>,
@@ -134,6 +179,10 @@ pub struct MoonBit {
 
     futures: HashMap<String, HashSet<TypeId>>,
     is_async: bool,
+
+    world_id: Option<WorldId>,
+    visitor_map: VisitorMap<MoonBitVisitor>,
+    diagnostics: Diagnostics,
 }
 
 impl MoonBit {
@@ -142,6 +191,7 @@ impl MoonBit {
         resolve: &'a Resolve,
         name: &'a str,
         module: &'a str,
+        interface: Option<InterfaceId>,
         direction: Direction,
     ) -> InterfaceGenerator<'a> {
         InterfaceGenerator {
@@ -152,14 +202,28 @@ impl MoonBit {
             resolve,
             name,
             module,
+            interface,
             direction,
         }
     }
 }
 
+impl VisitAnnotations for MoonBit {
+    type Visitor = MoonBitVisitor;
+
+    fn visitors_and_diagnostics(&mut self) -> (&mut VisitorMap<MoonBitVisitor>, &mut Diagnostics) {
+        (&mut self.visitor_map, &mut self.diagnostics)
+    }
+}
+
 impl WorldGenerator for MoonBit {
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        check_world_annotations(&self.visitor_map, &mut self.diagnostics, resolve, world)
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         self.name = world_name(resolve, world);
+        self.world_id = Some(world);
         self.sizes.fill(resolve);
     }
 
@@ -184,7 +248,8 @@ impl WorldGenerator for MoonBit {
         }
 
         let module = &resolve.name_world_key(key);
-        let mut gen = self.interface(resolve, &name, module, Direction::Import);
+        let mut gen = self.interface(resolve, &name, module, Some(id), Direction::Import);
+        gen.visit_interface(id);
         gen.types(id);
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
@@ -204,7 +269,7 @@ impl WorldGenerator for MoonBit {
         _files: &mut Files,
     ) {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name, "$root", Direction::Import);
+        let mut gen = self.interface(resolve, &name, "$root", None, Direction::Import);
 
         for (_, func) in funcs {
             gen.import(None, func); // None is "$root"
@@ -234,7 +299,8 @@ impl WorldGenerator for MoonBit {
         }
 
         let module = &resolve.name_world_key(key);
-        let mut gen = self.interface(resolve, &name, module, Direction::Export);
+        let mut gen = self.interface(resolve, &name, module, Some(id), Direction::Export);
+        gen.visit_interface(id);
         gen.types(id);
 
         for (_, func) in resolve.interfaces[id].functions.iter() {
@@ -253,7 +319,7 @@ impl WorldGenerator for MoonBit {
         _files: &mut Files,
     ) -> Result<()> {
         let name = format!("{}.{}", self.opts.gen_dir, world_name(resolve, world));
-        let mut gen = self.interface(resolve, &name, "$root", Direction::Export);
+        let mut gen = self.interface(resolve, &name, "$root", None, Direction::Export);
 
         for (_, func) in funcs {
             gen.export(None, func, Some(name.clone()));
@@ -271,7 +337,7 @@ impl WorldGenerator for MoonBit {
         _files: &mut Files,
     ) {
         let name = world_name(resolve, world);
-        let mut gen = self.interface(resolve, &name, "$root", Direction::Import);
+        let mut gen = self.interface(resolve, &name, "$root", None, Direction::Import);
 
         for (ty_name, ty) in types {
             gen.define_type(ty_name, *ty);
@@ -281,6 +347,20 @@ impl WorldGenerator for MoonBit {
     }
 
     fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        let world = &resolve.worlds[id];
+        let location = Location::for_world(resolve, id);
+        let cx = VisitContext::new(resolve, id);
+        let mut world_module = MoonBitModuleContribution::new();
+        for contrib in self.visit_annotations(
+            &location,
+            world.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_world(args, world, &cx),
+        ) {
+            world_module.code.extend(contrib.code);
+        }
+        self.diagnostics.bail_on_errors()?;
+
         let project_name = self
             .opts
             .project_name
@@ -360,6 +440,9 @@ impl WorldGenerator for MoonBit {
             uwriteln!(src, "{}", f.src);
             uwriteln!(stub, "{}", f.stub);
         });
+        for code in world_module.code.iter() {
+            uwriteln!(src, "{code}");
+        }
 
         files.push(&format!("{directory}/top.mbt"), indent(&src).as_bytes());
         if !self.opts.ignore_stub {
@@ -465,7 +548,7 @@ impl WorldGenerator for MoonBit {
         let export_dir = self.opts.gen_dir.clone();
 
         // Export project entry point
-        let mut gen = self.interface(resolve, export_dir.as_str(), "", Direction::Export);
+        let mut gen = self.interface(resolve, export_dir.as_str(), "", None, Direction::Export);
         let ffi_qualifier = gen.qualify_package(FFI_DIR);
 
         let mut body = Source::default();
@@ -550,6 +633,10 @@ impl WorldGenerator for MoonBit {
 
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
 
 struct InterfaceGenerator<'a> {
@@ -561,10 +648,11 @@ struct InterfaceGenerator<'a> {
     // The current interface getting generated
     name: &'a str,
     module: &'a str,
+    interface: Option<InterfaceId>,
     direction: Direction,
 }
 
-impl InterfaceGenerator<'_> {
+impl<'a> InterfaceGenerator<'a> {
     fn qualify_package(&mut self, name: &str) -> String {
         if name != self.name {
             let imports = self
@@ -609,6 +697,110 @@ impl InterfaceGenerator<'_> {
         String::new()
     }
 
+    /// Visits the annotations on the interface `id`, adding their code to the
+    /// package being generated.
+    fn visit_interface(&mut self, id: InterfaceId) {
+        let interface = &self.resolve.interfaces[id];
+        let location = Location::for_interface(self.resolve, id);
+        let cx = self.visit_context(None);
+        for contrib in self.gen.visit_annotations(
+            &location,
+            interface.annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_interface(args, Some(interface), &cx),
+        ) {
+            for code in contrib.code {
+                uwriteln!(self.src, "{code}");
+            }
+        }
+    }
+
+    /// The context visitors get for an item defined by this generator, called
+    /// `name` in the generated code.
+    fn visit_context(&self, name: Option<String>) -> VisitContext<'a> {
+        VisitContext {
+            interface: self.interface,
+            direction: Some(self.direction),
+            name,
+            module_path: Some(self.name.to_string()),
+            ..VisitContext::new(self.resolve, self.gen.world_id.unwrap())
+        }
+    }
+
+    /// Collects the contributions of the visitors for the annotations on the
+    /// type `id`.
+    fn type_contribution(
+        &mut self,
+        id: TypeId,
+        mut visit: impl FnMut(
+            &mut MoonBitVisitor,
+            &AnnotationArgs,
+            &VisitContext<'_>,
+        ) -> Option<MoonBitTypeContribution>,
+    ) -> MoonBitTypeContribution {
+        let location = Location::for_type(self.resolve, id);
+        let name = self.resolve.types[id].name.as_ref();
+        let cx = self.visit_context(name.map(|n| n.to_moonbit_type_ident()));
+        let mut contribution = MoonBitTypeContribution::new();
+        for contrib in self.gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visit(visitor, args, &cx),
+        ) {
+            contribution.derives.extend(contrib.derives);
+            contribution.attributes.extend(contrib.attributes);
+            contribution.code.extend(contrib.code);
+        }
+        contribution
+    }
+
+    /// Collects the contributions of the visitors for the annotations on
+    /// `func`.
+    fn function_contribution(&mut self, func: &Function) -> MoonBitFunctionContribution {
+        let location = match self.interface {
+            Some(id) => Location::for_interface(self.resolve, id),
+            None => Location::for_world(self.resolve, self.gen.world_id.unwrap()),
+        }
+        .member(&func.name);
+        let cx = self.visit_context(Some(func.item_name().to_moonbit_ident()));
+        let mut contribution = MoonBitFunctionContribution::new();
+        for contrib in self.gen.visit_annotations(
+            &location,
+            func.annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_function(args, func, &cx),
+        ) {
+            contribution.attributes.extend(contrib.attributes);
+        }
+        contribution
+    }
+
+    /// The `derive(...)` list of a type: the traits enabled for all types by
+    /// the options, followed by those contributed for this type.
+    fn derives(&self, contribution: &MoonBitTypeContribution) -> String {
+        let mut deriviation: Vec<&str> = Vec::new();
+        if self.gen.opts.derive_show {
+            deriviation.push("Show")
+        }
+        if self.gen.opts.derive_eq {
+            deriviation.push("Eq")
+        }
+        for derive in contribution.derives.iter() {
+            if !deriviation.contains(&derive.as_str()) {
+                deriviation.push(derive.as_str());
+            }
+        }
+        deriviation.join(", ")
+    }
+
+    /// Adds the code contributed for a type after its definition.
+    fn print_type_code(&mut self, contribution: &MoonBitTypeContribution) {
+        for code in contribution.code.iter() {
+            uwriteln!(self.src, "{code}");
+        }
+    }
+
     fn add_interface_fragment(self) {
         match self.direction {
             Direction::Import => {
@@ -747,6 +939,8 @@ impl InterfaceGenerator<'_> {
             r#"fn wasmImport{camel_name}({params}) {result_type} = "{module}" "{async_prefix}{name}""#
         );
 
+        let attributes = attribute_lines(&self.function_contribution(func).attributes);
+
         print_docs(&mut self.src, &func.docs);
 
         if async_ {
@@ -756,7 +950,7 @@ impl InterfaceGenerator<'_> {
         uwrite!(
             self.src,
             r#"
-            {sig} {{
+            {attributes}{sig} {{
                 {cleanup_list}
                 {src}
             }}
@@ -896,6 +1090,7 @@ impl InterfaceGenerator<'_> {
             self.resolve,
             export_dir.as_str(),
             self.module,
+            self.interface,
             Direction::Export,
         );
 
@@ -1074,11 +1269,13 @@ impl InterfaceGenerator<'_> {
                 .insert(func_name, format!("cabi_post_{export_name}"));
         }
 
+        let attributes = attribute_lines(&self.function_contribution(func).attributes);
+
         print_docs(&mut self.stub, &func.docs);
         uwrite!(
             self.stub,
             r#"
-            {func_sig} {{
+            {attributes}{func_sig} {{
                 ...
             }}
             "#
@@ -1600,7 +1797,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.resolve
     }
 
-    fn type_record(&mut self, _id: TypeId, name: &str, record: &Record, docs: &Docs) {
+    fn type_record(&mut self, id: TypeId, name: &str, record: &Record, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_record(args, record, id, cx)
+        });
+
         print_docs(&mut self.src, docs);
 
         let name = name.to_moonbit_type_ident();
@@ -1608,9 +1809,22 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let parameters = record
             .fields
             .iter()
-            .map(|field| {
+            .enumerate()
+            .map(|(i, field)| {
+                let location = Location::for_type(self.resolve, id).member(&field.name);
+                let cx = self.visit_context(Some(field.name.to_moonbit_ident()));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    field.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_field(args, field, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
                 format!(
-                    "{} : {}",
+                    "{}{} : {}",
+                    attribute_lines(&attributes),
                     field.name.to_moonbit_ident(),
                     self.type_name(&field.ty, true),
                 )
@@ -1618,50 +1832,43 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .collect::<Vec<_>>()
             .join("; ");
 
-        let mut deriviation: Vec<_> = Vec::new();
-        if self.gen.opts.derive_show {
-            deriviation.push("Show")
-        }
-        if self.gen.opts.derive_eq {
-            deriviation.push("Eq")
-        }
+        let attributes = attribute_lines(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            pub(all) struct {name} {{
+            {attributes}pub(all) struct {name} {{
                 {parameters}
             }} derive({})
             ",
-            deriviation.join(", ")
+            self.derives(&contribution)
         );
+        self.print_type_code(&contribution);
     }
 
-    fn type_resource(&mut self, _id: TypeId, name: &str, docs: &Docs) {
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        let contribution =
+            self.type_contribution(id, |visitor, args, cx| visitor.visit_resource(args, id, cx));
+
         print_docs(&mut self.src, docs);
         let type_name = name;
         let name = name.to_moonbit_type_ident();
 
-        let mut deriviation: Vec<_> = Vec::new();
-        if self.gen.opts.derive_show {
-            deriviation.push("Show")
-        }
-        if self.gen.opts.derive_eq {
-            deriviation.push("Eq")
-        }
         let declaration = if self.gen.opts.derive_error && name.contains("Error") {
             "suberror"
         } else {
             "struct"
         };
+        let attributes = attribute_lines(&contribution.attributes);
 
         uwrite!(
             self.src,
             r#"
-            pub(all) {declaration} {name}(Int) derive({})
+            {attributes}pub(all) {declaration} {name}(Int) derive({})
             "#,
-            deriviation.join(", "),
+            self.derives(&contribution),
         );
+        self.print_type_code(&contribution);
 
         let module = self.module;
 
@@ -1723,9 +1930,13 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
 
             let export_dir = self.gen.opts.gen_dir.clone();
 
-            let mut gen =
-                self.gen
-                    .interface(self.resolve, export_dir.as_str(), "", Direction::Export);
+            let mut gen = self.gen.interface(
+                self.resolve,
+                export_dir.as_str(),
+                "",
+                None,
+                Direction::Export,
+            );
 
             uwrite!(
                 self.ffi,
@@ -1743,7 +1954,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         }
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_flags(args, flags, id, cx)
+        });
+
         print_docs(&mut self.src, docs);
 
         let name = name.to_moonbit_type_ident();
@@ -1758,7 +1973,25 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let cases = flags
             .flags
             .iter()
-            .map(|flag| flag.name.to_shouty_snake_case())
+            .enumerate()
+            .map(|(i, flag)| {
+                let location = Location::for_type(self.resolve, id).member(&flag.name);
+                let cx = self.visit_context(Some(flag.name.to_shouty_snake_case()));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    flag.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_flag(args, flag, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                format!(
+                    "{}{}",
+                    attribute_lines(&attributes),
+                    flag.name.to_shouty_snake_case()
+                )
+            })
             .collect::<Vec<_>>()
             .join("; ");
 
@@ -1783,23 +2016,17 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             .collect::<Vec<_>>()
             .join("\n    ");
 
-        let mut deriviation: Vec<_> = Vec::new();
-        if self.gen.opts.derive_show {
-            deriviation.push("Show")
-        }
-        if self.gen.opts.derive_eq {
-            deriviation.push("Eq")
-        }
         let declaration = if self.gen.opts.derive_error && name.contains("Error") {
             "suberror"
         } else {
             "struct"
         };
+        let attributes = attribute_lines(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            pub(all) {declaration} {name}({ty}) derive({})
+            {attributes}pub(all) {declaration} {name}({ty}) derive({})
             pub fn {name}::default() -> {name} {{
                 {}
             }}
@@ -1824,7 +2051,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
               (flag.land(other.value()) == other.value())
             }}
             ",
-            deriviation.join(", "),
+            self.derives(&contribution),
             match ty {
                 "Byte" => "b'\\x00'",
                 "UInt" => "0U",
@@ -1832,13 +2059,18 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
                 _ => unreachable!(),
             }
         );
+        self.print_type_code(&contribution);
     }
 
     fn type_tuple(&mut self, _id: TypeId, _name: &str, _tuple: &Tuple, _docs: &Docs) {
         // Not needed. They will become `(T1, T2, ...)` in Moonbit
     }
 
-    fn type_variant(&mut self, _id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+    fn type_variant(&mut self, id: TypeId, name: &str, variant: &Variant, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_variant(args, variant, id, cx)
+        });
+
         print_docs(&mut self.src, docs);
 
         let name = name.to_moonbit_type_ident();
@@ -1846,40 +2078,48 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let cases = variant
             .cases
             .iter()
-            .map(|case| {
+            .enumerate()
+            .map(|(i, case)| {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(case.name.to_upper_camel_case()));
+                let mut attributes = Vec::new();
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                let attributes = attribute_lines(&attributes);
                 let name = case.name.to_upper_camel_case();
                 if let Some(ty) = case.ty {
                     let ty = self.type_name(&ty, true);
-                    format!("{name}({ty})")
+                    format!("{attributes}{name}({ty})")
                 } else {
-                    name.to_string()
+                    format!("{attributes}{name}")
                 }
             })
             .collect::<Vec<_>>()
             .join("\n  ");
 
-        let mut deriviation: Vec<_> = Vec::new();
-        if self.gen.opts.derive_show {
-            deriviation.push("Show")
-        }
-        if self.gen.opts.derive_eq {
-            deriviation.push("Eq")
-        }
         let declaration = if self.gen.opts.derive_error && name.contains("Error") {
             "suberror"
         } else {
             "enum"
         };
+        let attributes = attribute_lines(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            pub(all) {declaration} {name} {{
+            {attributes}pub(all) {declaration} {name} {{
               {cases}
             }} derive({})
             ",
-            deriviation.join(", ")
+            self.derives(&contribution)
         );
+        self.print_type_code(&contribution);
     }
 
     fn type_option(&mut self, _id: TypeId, _name: &str, _payload: &Type, _docs: &Docs) {
@@ -1890,7 +2130,11 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         // Not needed. They will become `Result[Ok, Err]` in Moonbit
     }
 
-    fn type_enum(&mut self, _id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+    fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs) {
+        let contribution = self.type_contribution(id, |visitor, args, cx| {
+            visitor.visit_enum(args, enum_, id, cx)
+        });
+
         print_docs(&mut self.src, docs);
 
         let name = name.to_moonbit_type_ident();
@@ -1899,32 +2143,52 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         let cases = enum_
             .cases
             .iter()
-            .map(|case| case.name.to_shouty_snake_case())
+            .enumerate()
+            .map(|(i, case)| {
+                let location = Location::for_type(self.resolve, id).member(&case.name);
+                let cx = self.visit_context(Some(case.name.to_shouty_snake_case()));
+                let mut attributes = Vec::new();
+                // Enum cases are visited as variant cases without a payload
+                let variant_case = Case {
+                    name: case.name.clone(),
+                    docs: case.docs.clone(),
+                    ty: None,
+                    annotations: case.annotations.clone(),
+                };
+                for contrib in self.gen.visit_annotations(
+                    &location,
+                    case.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_variant_case(args, &variant_case, i, &cx),
+                ) {
+                    attributes.extend(contrib.attributes);
+                }
+                format!(
+                    "{}{}",
+                    attribute_lines(&attributes),
+                    case.name.to_shouty_snake_case()
+                )
+            })
             .collect::<Vec<_>>()
             .join("; ");
 
-        let mut deriviation: Vec<_> = Vec::new();
-        if self.gen.opts.derive_show {
-            deriviation.push("Show")
-        }
-        if self.gen.opts.derive_eq {
-            deriviation.push("Eq")
-        }
         let declaration = if self.gen.opts.derive_error && name.contains("Error") {
             "suberror"
         } else {
             "enum"
         };
+        let attributes = attribute_lines(&contribution.attributes);
 
         uwrite!(
             self.src,
             "
-            pub(all) {declaration} {name} {{
+            {attributes}pub(all) {declaration} {name} {{
                 {cases}
             }} derive({})
             ",
-            deriviation.join(", ")
+            self.derives(&contribution)
         );
+        self.print_type_code(&contribution);
 
         // Case to integer
         let cases = enum_
@@ -0,0 +1,200 @@
+// Runs the MoonBit generator with visitors registered and checks that their
+// contributions show up in the generated packages.
+
+use wit_bindgen_core::visitor::VisitorList;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, Files, VisitContext, Visitor};
+use wit_bindgen_moonbit::annotation_visitor::*;
+use wit_bindgen_moonbit::Opts;
+
+/// Generates bindings for the only world in `wit` and returns all generated
+/// `.mbt` files concatenated, with all whitespace runs collapsed to a single
+/// space.
+fn generate(wit: &str, opts: Opts) -> String {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    files
+        .iter()
+        .filter(|(name, _)| name.ends_with(".mbt"))
+        .map(|(_, src)| std::str::from_utf8(src).unwrap())
+        .collect::<Vec<_>>()
+        .join("\n")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds top-level code after types and to packages.
+struct CodeVisitor;
+
+impl Visitor for CodeVisitor {
+    type Contributions = MoonBitContributions;
+
+    fn target(&self) -> &str {
+        "code"
+    }
+
+    fn visit_record(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitTypeContribution> {
+        let mut contrib = MoonBitTypeContribution::new();
+        contrib.add_code("pub fn Point::origin() -> Point { { x: 0, y: 0 } }");
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitModuleContribution> {
+        let mut contrib = MoonBitModuleContribution::new();
+        contrib.add_code("pub let api_version : Int = 2");
+        Some(contrib)
+    }
+
+    fn visit_world(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<MoonBitModuleContribution> {
+        let mut contrib = MoonBitModuleContribution::new();
+        contrib.add_code("pub let world_version : Int = 3");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_per_type_derives() {
+    let src = generate(
+        r#"
+            package test:mbt;
+
+            interface api {
+                #derive(ToJson, FromJson)
+                record point {
+                    x: u32,
+                    y: u32,
+                }
+
+                #derive(Eq)
+                enum color {
+                    red,
+                    green,
+                }
+
+                resource blob;
+            }
+
+            world test {
+                import api;
+            }
+        "#,
+        Opts {
+            derive_show: true,
+            visitors: VisitorList::new(|| vec![Box::new(DeriveVisitor) as Box<MoonBitVisitor>]),
+            ..Default::default()
+        },
+    );
+
+    assert!(
+        src.contains("pub(all) struct Point { x : UInt; y : UInt } derive(Show, ToJson, FromJson)"),
+        "record derives missing:\n{src}"
+    );
+    assert!(
+        src.contains("pub(all) enum Color { RED; GREEN } derive(Show, Eq)"),
+        "enum derives missing:\n{src}"
+    );
+    assert!(
+        src.contains("pub(all) struct Blob(Int) derive(Show)"),
+        "resource should only have the global derives:\n{src}"
+    );
+}
+
+#[test]
+fn test_attributes() {
+    let src = generate(
+        r#"
+            package test:mbt;
+
+            interface api {
+                #deprecated("use point3")
+                record point {
+                    x: u32,
+                    y: u32,
+                }
+
+                #deprecated
+                distance: func(a: point, b: point) -> u32;
+            }
+
+            world test {
+                import api;
+                export api;
+            }
+        "#,
+        Opts {
+            gen_dir: "gen".to_string(),
+            visitors: VisitorList::new(|| vec![Box::new(DeprecatedVisitor) as Box<MoonBitVisitor>]),
+            ..Default::default()
+        },
+    );
+
+    assert!(
+        src.contains("#deprecated(\"use point3\") pub(all) struct Point {"),
+        "type attribute missing:\n{src}"
+    );
+    // Once on the import wrapper and once on the export stub.
+    assert_eq!(
+        src.matches("#deprecated pub fn distance(").count(),
+        2,
+        "function attribute missing:\n{src}"
+    );
+}
+
+#[test]
+fn test_top_level_code() {
+    let src = generate(
+        r#"
+            package test:mbt;
+
+            #code
+            interface api {
+                #code
+                record point {
+                    x: u32,
+                    y: u32,
+                }
+            }
+
+            #code
+            world test {
+                import api;
+            }
+        "#,
+        Opts {
+            visitors: VisitorList::new(|| vec![Box::new(CodeVisitor) as Box<MoonBitVisitor>]),
+            ..Default::default()
+        },
+    );
+
+    let point = src.find("pub(all) struct Point {").expect(&src);
+    let origin = src.find("pub fn Point::origin() -> Point {").expect(&src);
+    assert!(point < origin, "type code should follow the type:\n{src}");
+    assert!(
+        src.contains("pub let api_version : Int = 2"),
+        "interface code missing:\n{src}"
+    );
+    assert!(
+        src.contains("pub let world_version : Int = 3"),
+        "world code missing:\n{src}"
+    );
+}
@@ -0,0 +1,980 @@
+//! Visitors for the standard annotations, selectable by name through
+//! `Opts::annotations` so they're usable from `generate!` and the CLI.
+
+use super::{
+    RustContributions, RustFieldContribution, RustFunctionContribution, RustModuleContribution,
+    RustParamContribution, RustTypeContribution, RustVariantCaseContribution, RustVisitor,
+};
+use wit_bindgen_core::visitor::{
+    priority, AnnotationArg, AnnotationSchema, AnnotationValue, ArgKind, ItemKind, ValueKind,
+};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{dealias, AnnotationArgs, VisitContext, Visitor};
+
+/// The names of all built-in annotation visitors.
+pub const BUILTIN_ANNOTATIONS: &[&str] = &[
+    "derive",
+    "serde",
+    "repr",
+    "cfg",
+    "deprecated",
+    "must_use",
+    "inline",
+    "assert",
+    "non_empty",
+    "min_length",
+    "max_length",
+    "range",
+    "finite",
+    "email_format",
+    "validation_error",
+    "trace",
+];
+
+/// Returns the built-in visitor for the annotation target `name`, if any.
+pub fn builtin_visitor(name: &str) -> Option<Box<RustVisitor>> {
+    Some(match name {
+        "derive" => Box::new(DeriveVisitor),
+        "serde" => Box::new(SerdeVisitor),
+        "repr" => Box::new(ReprVisitor),
+        "cfg" => Box::new(AttributeVisitor::new("cfg")),
+        "deprecated" => Box::new(DeprecatedVisitor),
+        "must_use" => Box::new(AttributeVisitor::new("must_use")),
+        "inline" => Box::new(AttributeVisitor::new("inline")),
+        "assert" => Box::new(AssertVisitor),
+        "non_empty" => Box::new(ValidationVisitor::new("non_empty")),
+        "min_length" => Box::new(ValidationVisitor::new("min_length")),
+        "max_length" => Box::new(ValidationVisitor::new("max_length")),
+        "range" => Box::new(ValidationVisitor::new("range")),
+        "finite" => Box::new(ValidationVisitor::new("finite")),
+        "email_format" => Box::new(ValidationVisitor::new("email_format")),
+        "validation_error" => Box::new(ValidationErrorVisitor),
+        "trace" => Box::new(TraceVisitor),
+        _ => return None,
+    })
+}
+
+/// Renders `#[name]`, or `#[name(args)]` when the annotation has a body.
+fn attribute(name: &str, annotation: &AnnotationArgs) -> String {
+    if annotation.trim().is_empty() {
+        format!("#[{name}]")
+    } else {
+        format!("#[{name}({})]", annotation.trim())
+    }
+}
+
+fn type_attribute(attr: String) -> Option<RustTypeContribution> {
+    let mut contrib = RustTypeContribution::new();
+    contrib.add_attribute(attr);
+    Some(contrib)
+}
+
+/// Whether the type `id` carries a `#target` annotation itself, rather than
+/// inheriting one from its interface or world.
+fn annotated(cx: &VisitContext<'_>, id: TypeId, target: &str) -> bool {
+    cx.resolve.types[id]
+        .annotations
+        .iter()
+        .any(|(t, _)| t == target)
+}
+
+/// Whether values of `ty` hold resource handles, which implement none of
+/// the common traits but `Debug`.
+fn holds_handle(resolve: &Resolve, ty: &Type) -> bool {
+    let Type::Id(id) = ty else {
+        return false;
+    };
+    match &resolve.types[*id].kind {
+        TypeDefKind::Handle(_) | TypeDefKind::Future(_) | TypeDefKind::Stream(_) => true,
+        TypeDefKind::Record(r) => r.fields.iter().any(|f| holds_handle(resolve, &f.ty)),
+        TypeDefKind::Variant(v) => v
+            .cases
+            .iter()
+            .any(|c| c.ty.as_ref().is_some_and(|ty| holds_handle(resolve, ty))),
+        TypeDefKind::Tuple(t) => t.types.iter().any(|ty| holds_handle(resolve, ty)),
+        TypeDefKind::Result(r) => [&r.ok, &r.err]
+            .into_iter()
+            .flatten()
+            .any(|ty| holds_handle(resolve, ty)),
+        TypeDefKind::Type(ty) | TypeDefKind::List(ty) | TypeDefKind::Option(ty) => {
+            holds_handle(resolve, ty)
+        }
+        _ => false,
+    }
+}
+
+/// `#derive(Debug, Hash)` adds derives to a type.
+///
+/// Records, variants and enums always implement `Debug` by hand, so a
+/// `Debug` derive is dropped for them rather than conflicting. On an alias
+/// of a scalar, e.g. `type counter = u32`, the alias becomes a newtype so
+/// the derives have something to apply to.
+///
+/// On an interface or a world, the derives are inherited by the records,
+/// variants, enums and flags in it, except for records and variants holding
+/// resource handles since those can't derive them. Resources and aliases
+/// only take their own `#derive`.
+pub struct DeriveVisitor;
+
+impl DeriveVisitor {
+    fn derives(annotation: &AnnotationArgs) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        for derive in annotation.positional() {
+            contrib.add_derive(derive.to_string());
+        }
+        Some(contrib)
+    }
+
+    fn derives_without_debug(annotation: &AnnotationArgs) -> Option<RustTypeContribution> {
+        let mut contrib = Self::derives(annotation)?;
+        contrib.derives.retain(|d| d != "Debug");
+        Some(contrib)
+    }
+
+    /// The derives of the record or variant `id`, skipping inherited ones if
+    /// it holds resource handles.
+    fn derives_for(
+        annotation: &AnnotationArgs,
+        id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        if !annotated(cx, id, "derive") && holds_handle(cx.resolve, &Type::Id(id)) {
+            return None;
+        }
+        Self::derives_without_debug(annotation)
+    }
+}
+
+impl Visitor for DeriveVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "derive"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Alias,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().rest(ArgKind::Ident))
+    }
+
+    fn inherit(&self) -> bool {
+        true
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::derives_for(annotation, type_id, cx)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::derives_for(annotation, type_id, cx)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::derives_without_debug(annotation)
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::derives(annotation)
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        resource_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        if !annotated(cx, resource_id, "derive") {
+            return None;
+        }
+        Self::derives(annotation)
+    }
+
+    fn visit_alias(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _ty: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        if !annotated(cx, type_id, "derive") {
+            return None;
+        }
+        let mut contrib = Self::derives(annotation)?;
+        contrib.set_newtype(true);
+        Some(contrib)
+    }
+}
+
+/// `#serde(...)` derives `Serialize` and `Deserialize` on records, variants
+/// and enums, and passes its arguments through as `#[serde(...)]` on types,
+/// fields and cases.
+///
+/// The generated code refers to the `serde` crate, which the user must depend
+/// on.
+pub struct SerdeVisitor;
+
+impl SerdeVisitor {
+    fn serde_type(annotation: &AnnotationArgs) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_derive("serde::Serialize");
+        contrib.add_derive("serde::Deserialize");
+        if !annotation.trim().is_empty() {
+            contrib.add_attribute(attribute("serde", annotation));
+        }
+        Some(contrib)
+    }
+}
+
+impl Visitor for SerdeVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "serde"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Field,
+            ItemKind::Case,
+        ])
+    }
+
+    // `#[serde]` is a helper attribute of the derives, so it goes after them.
+    fn priority(&self) -> i32 {
+        priority::DERIVE_HELPERS
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::serde_type(annotation)
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::serde_type(annotation)
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::serde_type(annotation)
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let mut contrib = RustFieldContribution::new();
+        contrib.add_attribute(attribute("serde", annotation));
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_attribute(attribute("serde", annotation));
+        Some(contrib)
+    }
+}
+
+/// `#repr(C)` sets the representation of records and variants.
+///
+/// Enums are already `#[repr]`'d with the integer type of their discriminant
+/// since lifting relies on it, so on an enum only that representation is
+/// accepted.
+pub struct ReprVisitor;
+
+impl Visitor for ReprVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "repr"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Record, ItemKind::Variant, ItemKind::Enum])
+    }
+
+    // Representations such as `align(8)` aren't plain identifiers.
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().arg(ArgKind::Any).rest(ArgKind::Any))
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute("repr", annotation))
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute("repr", annotation))
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        let tag = match enum_.tag() {
+            Int::U8 => "u8",
+            Int::U16 => "u16",
+            Int::U32 => "u32",
+            Int::U64 => "u64",
+        };
+        if annotation.trim() == tag {
+            Some(RustTypeContribution::new())
+        } else {
+            None
+        }
+    }
+}
+
+/// Passes an annotation through as the attribute of the same name on types,
+/// functions and modules, e.g. `#cfg(feature = "x")` or `#inline`.
+pub struct AttributeVisitor {
+    name: &'static str,
+}
+
+impl AttributeVisitor {
+    pub fn new(name: &'static str) -> AttributeVisitor {
+        AttributeVisitor { name }
+    }
+}
+
+impl Visitor for AttributeVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        self.name
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Function,
+            ItemKind::Interface,
+            ItemKind::World,
+        ])
+    }
+
+    // `cfg` predicates are free-form.
+    fn schema(&self) -> Option<AnnotationSchema> {
+        match self.name {
+            "must_use" => Some(AnnotationSchema::new()),
+            "inline" => Some(AnnotationSchema::new().optional_arg(ArgKind::Ident)),
+            _ => None,
+        }
+    }
+
+    // `#[cfg]` goes before any other attribute, so e.g. a `#[derive]` only
+    // applies to an item that is compiled in.
+    fn priority(&self) -> i32 {
+        match self.name {
+            "cfg" => priority::FIRST,
+            _ => priority::DEFAULT,
+        }
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute(self.name, annotation))
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute(self.name, annotation))
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute(self.name, annotation))
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute(self.name, annotation))
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(attribute(self.name, annotation))
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_attribute(attribute(self.name, annotation));
+        Some(contrib)
+    }
+
+    fn visit_interface(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        let mut contrib = RustModuleContribution::new();
+        contrib.add_attribute(attribute(self.name, annotation));
+        Some(contrib)
+    }
+
+    fn visit_world(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        let mut contrib = RustModuleContribution::new();
+        contrib.add_attribute(attribute(self.name, annotation));
+        Some(contrib)
+    }
+}
+
+/// `#deprecated` or `#deprecated("use x instead")` marks types, fields,
+/// cases and functions as `#[deprecated]`.
+pub struct DeprecatedVisitor;
+
+impl DeprecatedVisitor {
+    fn attribute(annotation: &AnnotationArgs) -> String {
+        match annotation.args() {
+            [AnnotationArg {
+                name: None,
+                value: note @ AnnotationValue::String(_),
+            }] => format!("#[deprecated(note = {note})]"),
+            _ => attribute("deprecated", annotation),
+        }
+    }
+}
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[
+            ItemKind::Record,
+            ItemKind::Variant,
+            ItemKind::Enum,
+            ItemKind::Flags,
+            ItemKind::Resource,
+            ItemKind::Field,
+            ItemKind::Case,
+            ItemKind::Flag,
+            ItemKind::Function,
+        ])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(
+            AnnotationSchema::new()
+                .optional_arg(ArgKind::String)
+                .key("since", ArgKind::String)
+                .key("note", ArgKind::String),
+        )
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(Self::attribute(annotation))
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(Self::attribute(annotation))
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(Self::attribute(annotation))
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(Self::attribute(annotation))
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        type_attribute(Self::attribute(annotation))
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let mut contrib = RustFieldContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_flag(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flag: &Flag,
+        _flag_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_attribute(Self::attribute(annotation));
+        Some(contrib)
+    }
+}
+
+/// `#assert(divisor != 0, "divisor cannot be zero")` checks a condition on
+/// the parameters of a function before its body runs.
+pub struct AssertVisitor;
+
+impl Visitor for AssertVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "assert"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(
+            AnnotationSchema::new()
+                .arg(ArgKind::Any)
+                .optional_arg(ArgKind::String),
+        )
+    }
+
+    // Checks run before the body prefixes of other visitors, e.g. tracing.
+    fn priority(&self) -> i32 {
+        priority::VALIDATION
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        if annotation.trim().is_empty() {
+            return None;
+        }
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_body_prefix(format!("assert!({});", annotation.trim()));
+        Some(contrib)
+    }
+}
+
+/// `#non_empty`, `#min_length(1)`, `#max_length(255)`, `#range(18, 120)`,
+/// `#finite` and `#email_format` check the fields of a record, or the
+/// payloads of a variant's cases, in the `Validate` impl generated for it.
+/// Exports check their arguments with it, and against the rules naming them
+/// on the function, e.g. `#range(age, 18, 120)`, see `#validation_error`.
+///
+/// Lengths apply to strings and lists, `#range`, whose bounds are literals
+/// of the value's type, to numbers, `#finite` to floats and `#email_format`
+/// to strings.
+pub struct ValidationVisitor {
+    rule: &'static str,
+}
+
+impl ValidationVisitor {
+    pub fn new(rule: &'static str) -> ValidationVisitor {
+        ValidationVisitor { rule }
+    }
+
+    /// The condition and message checking a value of type `ty`, or `None` if
+    /// the rule doesn't apply to it.
+    fn validation(
+        &self,
+        annotation: &AnnotationArgs,
+        resolve: &Resolve,
+        ty: &Type,
+    ) -> Option<(String, String)> {
+        let kind = ValueKind::of(resolve, ty);
+        let mut args = annotation.positional();
+        Some(match (self.rule, kind) {
+            ("non_empty", ValueKind::String | ValueKind::List) => (
+                "!value.is_empty()".to_string(),
+                "must not be empty".to_string(),
+            ),
+            ("min_length", ValueKind::String | ValueKind::List) => {
+                let min = args.next()?.as_integer()?;
+                (
+                    format!("value.len() >= {min}"),
+                    format!("must have a length of at least {min}"),
+                )
+            }
+            ("max_length", ValueKind::String | ValueKind::List) => {
+                let max = args.next()?.as_integer()?;
+                (
+                    format!("value.len() <= {max}"),
+                    format!("must have a length of at most {max}"),
+                )
+            }
+            ("range", ValueKind::Integer | ValueKind::Float) => {
+                let (min, max) = (args.next()?, args.next()?);
+                let (lo, hi) = (bound(min, kind)?, bound(max, kind)?);
+                (
+                    format!("({lo}..={hi}).contains(value)"),
+                    format!("must be in {min}..={max}"),
+                )
+            }
+            ("finite", ValueKind::Float) => (
+                "value.is_finite()".to_string(),
+                "must be finite".to_string(),
+            ),
+            ("email_format", ValueKind::String) => (
+                "value.split_once('@').is_some_and(|(user, domain)| \
+                 !user.is_empty() && domain.contains('.'))"
+                    .to_string(),
+                "must be an email address".to_string(),
+            ),
+            _ => return None,
+        })
+    }
+}
+
+/// Renders the `#range` bound `value` as a literal of the `kind` of the
+/// checked values, e.g. `0.0` for a float. Integer values only take integer
+/// bounds, which the annotation's schema checks up front.
+fn bound(value: &AnnotationValue, kind: ValueKind) -> Option<String> {
+    match (value, kind) {
+        (AnnotationValue::Integer(i), ValueKind::Integer) => Some(i.to_string()),
+        (AnnotationValue::Integer(i), ValueKind::Float) => Some(format!("{i}.0")),
+        (AnnotationValue::Float(x), ValueKind::Float) => Some(format!("{x:?}")),
+        _ => None,
+    }
+}
+
+impl Visitor for ValidationVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        self.rule
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Field, ItemKind::Case, ItemKind::Param])
+    }
+
+    fn value_kinds(&self) -> Option<&[ValueKind]> {
+        Some(match self.rule {
+            "non_empty" | "min_length" | "max_length" => &[ValueKind::String, ValueKind::List],
+            "range" => &[ValueKind::Integer, ValueKind::Float],
+            "finite" => &[ValueKind::Float],
+            "email_format" => &[ValueKind::String],
+            _ => return None,
+        })
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(match self.rule {
+            "min_length" | "max_length" => AnnotationSchema::new().arg(ArgKind::Integer),
+            "range" => AnnotationSchema::new()
+                .arg(ArgKind::Value)
+                .arg(ArgKind::Value),
+            _ => AnnotationSchema::new(),
+        })
+    }
+
+    fn priority(&self) -> i32 {
+        priority::VALIDATION
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        field: &Field,
+        _field_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let (condition, message) = self.validation(annotation, cx.resolve, &field.ty)?;
+        let mut contrib = RustFieldContribution::new();
+        contrib.add_validation(condition, message);
+        Some(contrib)
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        case: &Case,
+        _case_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let (condition, message) = self.validation(annotation, cx.resolve, case.ty.as_ref()?)?;
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_validation(condition, message);
+        Some(contrib)
+    }
+
+    fn visit_param(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        param_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustParamContribution> {
+        let (_, ty) = &func.params[param_index];
+        let (condition, message) = self.validation(annotation, cx.resolve, ty)?;
+        let mut contrib = RustParamContribution::new();
+        contrib.add_validation(condition, message);
+        Some(contrib)
+    }
+}
+
+/// `#validation_error(crate::invalid_input)` on an export returning
+/// `result<_, E>` makes arguments that fail validation return
+/// `Err(crate::invalid_input(message))` rather than trap, where
+/// `invalid_input` is a `fn(&'static str) -> E` supplied by the user.
+pub struct ValidationErrorVisitor;
+
+impl Visitor for ValidationErrorVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "validation_error"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new().arg(ArgKind::Ident))
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let Some(Type::Id(id)) = func.result else {
+            return None;
+        };
+        let returns_result = matches!(
+            cx.resolve.types[dealias(cx.resolve, id)].kind,
+            TypeDefKind::Result(_)
+        );
+        if !returns_result || annotation.trim().is_empty() {
+            return None;
+        }
+        let mut contrib = RustFunctionContribution::new();
+        contrib.set_validation_error(annotation.trim());
+        Some(contrib)
+    }
+}
+
+/// `#trace` makes a function's import wrapper or export glue report its
+/// calls, with their arguments and result, to the `CallObserver` set with
+/// `wit_bindgen::rt::set_call_observer`, like the `trace_calls` option does
+/// for all functions.
+///
+/// On an interface or a world, all functions in it are traced.
+pub struct TraceVisitor;
+
+impl Visitor for TraceVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "trace"
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&[ItemKind::Function])
+    }
+
+    fn schema(&self) -> Option<AnnotationSchema> {
+        Some(AnnotationSchema::new())
+    }
+
+    fn inherit(&self) -> bool {
+        true
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.set_trace();
+        Some(contrib)
+    }
+
+    // Interfaces and worlds accept the annotation, which takes effect on
+    // the functions inheriting it.
+    fn visit_interface(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        Some(RustModuleContribution::new())
+    }
+
+    fn visit_world(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        Some(RustModuleContribution::new())
+    }
+}
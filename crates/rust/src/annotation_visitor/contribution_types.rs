@@ -6,11 +6,33 @@ pub struct RustTypeContribution {
 
     /// Attributes to add
     pub attributes: Vec<String>,
+
+    /// Code to run when a resource handle is dropped (only used for resources)
+    pub resource_drop: Vec<String>,
+
+    /// Render the type as a newtype wrapper instead of a type alias (only
+    /// honoured for aliases of primitive scalar types)
+    pub newtype: bool,
+
+    /// Items to emit right after the type definition, e.g. trait impls.
+    /// `{type}` is replaced by the generated type, with its generics, e.g.
+    /// `PersonParam<'a>`, and `{generics}` by the generics alone, e.g.
+    /// `<'a>` (empty for types without a lifetime), so that
+    /// `impl{generics} Display for {type}` works in every mode. Ignored with
+    /// a warning for types generated as `type` aliases, e.g. lists, since
+    /// impls for those would be orphan impls.
+    pub items: Vec<String>,
 }
 
 impl RustTypeContribution {
     pub fn new() -> Self {
-        RustTypeContribution { derives: Vec::new(), attributes: Vec::new() }
+        RustTypeContribution {
+            derives: Vec::new(),
+            attributes: Vec::new(),
+            resource_drop: Vec::new(),
+            newtype: false,
+            items: Vec::new(),
+        }
     }
 
     /// Add a derive macro
@@ -23,17 +45,62 @@ impl RustTypeContribution {
         self.attributes.push(attr.into());
     }
 
+    /// Add code to run when a resource handle is dropped
+    pub fn add_resource_drop(&mut self, code: impl Into<String>) {
+        self.resource_drop.push(code.into());
+    }
+
+    /// Request that an alias is rendered as a newtype wrapper
+    pub fn set_newtype(&mut self, newtype: bool) {
+        self.newtype = newtype;
+    }
+
+    /// Add an item to emit after the type definition
+    pub fn add_item(&mut self, item: impl Into<String>) {
+        self.items.push(item.into());
+    }
+
+    /// The items with their placeholders replaced for the type `name` with
+    /// the generics `generics`.
+    pub fn expand_items(&self, name: &str, generics: &str) -> Vec<String> {
+        let ty = format!("{name}{generics}");
+        self.items
+            .iter()
+            .map(|item| item.replace("{type}", &ty).replace("{generics}", generics))
+            .collect()
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.derives.is_empty() && self.attributes.is_empty()
+        self.derives.is_empty()
+            && self.attributes.is_empty()
+            && self.resource_drop.is_empty()
+            && !self.newtype
+            && self.items.is_empty()
     }
 }
 
+/// A check in the `Validate` impl generated for a record or variant, or of an
+/// argument in the glue of an export
+#[derive(Debug, Clone)]
+pub struct RustValidation {
+    /// Condition that must hold, with the value of the field, case payload or
+    /// argument bound by reference to `value`, e.g. `!value.is_empty()`
+    pub condition: String,
+
+    /// What's wrong if the condition doesn't hold, following the name of the
+    /// field, case or parameter in the error, e.g. `must not be empty`
+    pub message: String,
+}
+
 /// Contributions for field definitions within recordsd
 #[derive(Default, Debug, Clone)]
 pub struct RustFieldContribution {
     /// Field-level attributes
     pub attributes: Vec<String>,
+
+    /// Checks of the field's value, run by the record's `Validate` impl
+    pub validations: Vec<RustValidation>,
 }
 
 impl RustFieldContribution {
@@ -46,9 +113,17 @@ impl RustFieldContribution {
         self.attributes.push(attr.into());
     }
 
+    /// Add a check of the field's value
+    pub fn add_validation(&mut self, condition: impl Into<String>, message: impl Into<String>) {
+        self.validations.push(RustValidation {
+            condition: condition.into(),
+            message: message.into(),
+        });
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.attributes.is_empty()
+        self.attributes.is_empty() && self.validations.is_empty()
     }
 }
 
@@ -57,6 +132,10 @@ impl RustFieldContribution {
 pub struct RustVariantCaseContribution {
     /// Case-level attributes
     pub attributes: Vec<String>,
+
+    /// Checks of the case's payload, run by the variant's `Validate` impl
+    /// (only used for variant cases with a payload)
+    pub validations: Vec<RustValidation>,
 }
 
 impl RustVariantCaseContribution {
@@ -69,9 +148,17 @@ impl RustVariantCaseContribution {
         self.attributes.push(attr.into());
     }
 
+    /// Add a check of the case's payload
+    pub fn add_validation(&mut self, condition: impl Into<String>, message: impl Into<String>) {
+        self.validations.push(RustValidation {
+            condition: condition.into(),
+            message: message.into(),
+        });
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.attributes.is_empty()
+        self.attributes.is_empty() && self.validations.is_empty()
     }
 }
 
@@ -86,6 +173,22 @@ pub struct RustFunctionContribution {
 
     /// Code to append before function return (can access func_return for return value)
     pub body_suffix: Vec<String>,
+
+    /// Expressions replacing the value an export's trait method returns before
+    /// it's lowered, each with the previous value bound to `func_return`,
+    /// e.g. `func_return.map_err(|e| e.to_string())` (applied in order, and
+    /// only to exports of functions with a result)
+    pub result_transforms: Vec<String>,
+
+    /// Function converting the `&'static str` describing an export argument
+    /// that fails validation into the error of the function's `result`,
+    /// e.g. `crate::invalid_input`, which is then returned instead of
+    /// trapping (only used for exports of functions returning a `result`)
+    pub validation_error: Option<String>,
+
+    /// Whether the import wrapper and export glue report calls to the
+    /// `CallObserver` set with `wit_bindgen::rt::set_call_observer`
+    pub trace: bool,
 }
 
 impl RustFunctionContribution {
@@ -108,9 +211,74 @@ impl RustFunctionContribution {
         self.body_suffix.push(code.into());
     }
 
+    /// Add an expression transforming an export's return value
+    pub fn add_result_transform(&mut self, expr: impl Into<String>) {
+        self.result_transforms.push(expr.into());
+    }
+
+    /// Set the conversion of validation failures into the function's error
+    pub fn set_validation_error(&mut self, conversion: impl Into<String>) {
+        self.validation_error = Some(conversion.into());
+    }
+
+    /// Report calls of the function to the call observer
+    pub fn set_trace(&mut self) {
+        self.trace = true;
+    }
+
+    /// Check if empty
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+            && self.body_prefix.is_empty()
+            && self.body_suffix.is_empty()
+            && self.result_transforms.is_empty()
+            && self.validation_error.is_none()
+            && !self.trace
+    }
+}
+
+/// Contributions for function parameters
+#[derive(Default, Debug, Clone)]
+pub struct RustParamContribution {
+    /// Attributes to add to the parameter of the import wrapper and of the
+    /// export trait method, e.g. `#[allow(unused_variables)]`
+    pub attributes: Vec<String>,
+
+    /// Code to run against the argument before the call, with the argument
+    /// bound to its parameter name; a `let` rebinding that name converts it
+    pub code: Vec<String>,
+
+    /// Checks of the argument, run by the glue of exports alongside its
+    /// `Validate` impl
+    pub validations: Vec<RustValidation>,
+}
+
+impl RustParamContribution {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
+    /// Add code to run against the argument
+    pub fn add_code(&mut self, code: impl Into<String>) {
+        self.code.push(code.into());
+    }
+
+    /// Add a check of the argument
+    pub fn add_validation(&mut self, condition: impl Into<String>, message: impl Into<String>) {
+        self.validations.push(RustValidation {
+            condition: condition.into(),
+            message: message.into(),
+        });
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.attributes.is_empty() && self.body_prefix.is_empty() && self.body_suffix.is_empty()
+        self.attributes.is_empty() && self.code.is_empty() && self.validations.is_empty()
     }
 }
 
@@ -122,6 +290,9 @@ pub struct RustModuleContribution {
 
     /// Additional code to add to module
     pub additional_code: Vec<String>,
+
+    /// Attributes to add to the module (or to the `export!` macro for worlds)
+    pub attributes: Vec<String>,
 }
 
 impl RustModuleContribution {
@@ -139,8 +310,121 @@ impl RustModuleContribution {
         self.additional_code.push(code.into());
     }
 
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attr: impl Into<String>) {
+        self.attributes.push(attr.into());
+    }
+
     /// Check if empty
     pub fn is_empty(&self) -> bool {
-        self.use_statements.is_empty() && self.additional_code.is_empty()
+        self.use_statements.is_empty()
+            && self.additional_code.is_empty()
+            && self.attributes.is_empty()
+    }
+}
+
+/// Checks that the Rust code in a contribution parses, so that a typo in a
+/// visitor is reported against the annotation that produced it instead of as
+/// a rustc error somewhere in the generated bindings.
+pub(crate) trait CheckSyntax {
+    fn check_syntax(&self) -> Result<(), String>;
+}
+
+fn check_derives(derives: &[String]) -> Result<(), String> {
+    for derive in derives {
+        syn::parse_str::<syn::Path>(derive)
+            .map_err(|e| format!("invalid derive `{derive}`: {e}"))?;
+    }
+    Ok(())
+}
+
+fn check_attributes(attributes: &[String]) -> Result<(), String> {
+    use syn::parse::Parser;
+
+    for attr in attributes {
+        syn::Attribute::parse_outer
+            .parse_str(attr)
+            .map_err(|e| format!("invalid attribute `{attr}`: {e}"))?;
+    }
+    Ok(())
+}
+
+fn check_statements(statements: &[String]) -> Result<(), String> {
+    for stmt in statements {
+        syn::parse_str::<syn::Block>(&format!("{{\n{stmt}\n}}"))
+            .map_err(|e| format!("invalid statements `{stmt}`: {e}"))?;
+    }
+    Ok(())
+}
+
+fn check_expressions(exprs: &[String]) -> Result<(), String> {
+    for expr in exprs {
+        syn::parse_str::<syn::Expr>(expr)
+            .map_err(|e| format!("invalid expression `{expr}`: {e}"))?;
+    }
+    Ok(())
+}
+
+fn check_validations(validations: &[RustValidation]) -> Result<(), String> {
+    let conditions = validations
+        .iter()
+        .map(|v| v.condition.clone())
+        .collect::<Vec<_>>();
+    check_expressions(&conditions)
+}
+
+fn check_items(items: &[String]) -> Result<(), String> {
+    for item in items {
+        syn::parse_file(item).map_err(|e| format!("invalid items `{item}`: {e}"))?;
+    }
+    Ok(())
+}
+
+impl CheckSyntax for RustTypeContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_derives(&self.derives)?;
+        check_attributes(&self.attributes)?;
+        check_statements(&self.resource_drop)?;
+        check_items(&self.expand_items("T", "<'a>"))
+    }
+}
+
+impl CheckSyntax for RustFieldContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_attributes(&self.attributes)?;
+        check_validations(&self.validations)
+    }
+}
+
+impl CheckSyntax for RustVariantCaseContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_attributes(&self.attributes)?;
+        check_validations(&self.validations)
+    }
+}
+
+impl CheckSyntax for RustFunctionContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_attributes(&self.attributes)?;
+        check_statements(&self.body_prefix)?;
+        check_statements(&self.body_suffix)?;
+        check_expressions(&self.result_transforms)?;
+        check_expressions(self.validation_error.as_slice())
+    }
+}
+
+impl CheckSyntax for RustParamContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_attributes(&self.attributes)?;
+        check_statements(&self.code)?;
+        check_validations(&self.validations)
+    }
+}
+
+impl CheckSyntax for RustModuleContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        check_items(&self.use_statements)?;
+        check_items(&self.additional_code)?;
+        check_attributes(&self.attributes)
     }
 }
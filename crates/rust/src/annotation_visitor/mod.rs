@@ -1,10 +1,19 @@
+mod builtin;
 mod contribution_types;
+mod template;
 use wit_bindgen_core::{ContributionTypes, Visitor};
 
+pub use builtin::{
+    builtin_visitor, AssertVisitor, AttributeVisitor, DeprecatedVisitor, DeriveVisitor,
+    ReprVisitor, SerdeVisitor, TraceVisitor, ValidationErrorVisitor, ValidationVisitor,
+    BUILTIN_ANNOTATIONS,
+};
+pub(crate) use contribution_types::CheckSyntax;
 pub use contribution_types::{
-    RustFieldContribution, RustFunctionContribution, RustModuleContribution, RustTypeContribution,
-    RustVariantCaseContribution,
+    RustFieldContribution, RustFunctionContribution, RustModuleContribution, RustParamContribution,
+    RustTypeContribution, RustValidation, RustVariantCaseContribution,
 };
+pub use template::TemplateVisitor;
 
 /// The family of Rust contribution types.
 /// This type is used with the nested associated type pattern in the Visitor trait.
@@ -16,6 +25,7 @@ impl ContributionTypes for RustContributions {
     type VariantCase = RustVariantCaseContribution;
     type Function = RustFunctionContribution;
     type Module = RustModuleContribution;
+    type Param = RustParamContribution;
 }
 
 /// Rust-specific visitor type alias.
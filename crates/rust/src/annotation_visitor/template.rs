@@ -0,0 +1,552 @@
+//! Visitors described declaratively by string templates in a TOML file.
+//!
+//! Each table of the file is named after an annotation target, with a
+//! subtable per kind of item the annotation applies to:
+//!
+//! ```toml
+//! [serde.record]
+//! derives = ["serde::Serialize", "serde::Deserialize"]
+//! attributes = ["#[serde({args})]"]
+//!
+//! [serde.field]
+//! attributes = ["#[serde({args})]"]
+//!
+//! [trace.function]
+//! body-prefix = ["log::trace!(\"{name}({:?})\", ({params}));"]
+//! ```
+//!
+//! The item kinds are `record`, `variant`, `enum`, `flags`, `resource`,
+//! `alias`, `tuple`, `list`, `option`, `result`, `field`, `case`, `flag`,
+//! `function`, `interface` and `world`. `derives` only apply to types,
+//! `items` to the types that aren't generated as `type` aliases (records,
+//! variants, enums, flags and resources), `body-prefix`, `body-suffix` and
+//! `result-transforms` to functions and `uses` to interfaces and worlds,
+//! while `attributes` apply to every kind.
+//!
+//! Placing an annotation on an item of a kind its target has no subtable
+//! for is an error, reported before anything is generated. Interfaces and
+//! worlds are accepted when the target is inherited.
+//!
+//! A target's table may also set the visitor's `priority`, an integer
+//! ordering its contributions relative to those of other visitors, see
+//! `Visitor::priority`, and `inherit`, whether the items in an annotated
+//! interface or world are rendered as if they were annotated too, see
+//! `Visitor::inherit`:
+//!
+//! ```toml
+//! [trace]
+//! priority = 100
+//! inherit = true
+//! ```
+//!
+//! Templates may contain these placeholders:
+//!
+//! * `{args}` - the annotation's arguments, verbatim.
+//! * `{args.KEY}` - the named argument `KEY`, or `{args.0}` for the first
+//!   positional argument. If the annotation has no such argument the item
+//!   is skipped.
+//! * `{name}` - the WIT name of the item.
+//! * `{params}` - the comma separated Rust names of a function's
+//!   parameters, excluding `self`.
+//!
+//! `{type}` and `{generics}` are left in `items` for the generator to
+//! replace with the generated type, see `RustTypeContribution::items`:
+//!
+//! ```toml
+//! [display.record]
+//! items = ["impl{generics} core::fmt::Display for {type} { ... }"]
+//! ```
+//!
+//! Any other text, including braces, is copied through unchanged.
+
+use super::{
+    RustContributions, RustFieldContribution, RustFunctionContribution, RustModuleContribution,
+    RustTypeContribution, RustVariantCaseContribution,
+};
+use crate::to_rust_ident;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use wit_bindgen_core::visitor::{priority, AnnotationValue, ItemKind};
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{AnnotationArgs, VisitContext, Visitor};
+
+/// The item kind a subtable is named after.
+fn parse_kind(s: &str) -> Option<ItemKind> {
+    Some(match s {
+        "record" => ItemKind::Record,
+        "variant" => ItemKind::Variant,
+        "enum" => ItemKind::Enum,
+        "flags" => ItemKind::Flags,
+        "resource" => ItemKind::Resource,
+        "alias" => ItemKind::Alias,
+        "tuple" => ItemKind::Tuple,
+        "list" => ItemKind::List,
+        "option" => ItemKind::Option,
+        "result" => ItemKind::Result,
+        "field" => ItemKind::Field,
+        "case" => ItemKind::Case,
+        "flag" => ItemKind::Flag,
+        "function" => ItemKind::Function,
+        "interface" => ItemKind::Interface,
+        "world" => ItemKind::World,
+        _ => return None,
+    })
+}
+
+fn is_type(kind: ItemKind) -> bool {
+    matches!(
+        kind,
+        ItemKind::Record
+            | ItemKind::Variant
+            | ItemKind::Enum
+            | ItemKind::Flags
+            | ItemKind::Resource
+            | ItemKind::Alias
+            | ItemKind::Tuple
+            | ItemKind::List
+            | ItemKind::Option
+            | ItemKind::Result
+    )
+}
+
+/// Whether `kind` is generated as a type of its own rather than as a `type`
+/// alias, so that impls can be written for it.
+fn defines_type(kind: ItemKind) -> bool {
+    matches!(
+        kind,
+        ItemKind::Record
+            | ItemKind::Variant
+            | ItemKind::Enum
+            | ItemKind::Flags
+            | ItemKind::Resource
+    )
+}
+
+fn is_module(kind: ItemKind) -> bool {
+    matches!(kind, ItemKind::Interface | ItemKind::World)
+}
+
+#[derive(Debug, Default)]
+struct Template {
+    derives: Vec<String>,
+    attributes: Vec<String>,
+    items: Vec<String>,
+    body_prefix: Vec<String>,
+    body_suffix: Vec<String>,
+    result_transforms: Vec<String>,
+    uses: Vec<String>,
+}
+
+/// The values placeholders are substituted with.
+struct Scope<'a> {
+    args: &'a AnnotationArgs,
+    name: Option<&'a str>,
+    params: Option<String>,
+}
+
+impl Scope<'_> {
+    fn lookup(&self, placeholder: &str) -> Option<Option<String>> {
+        Some(match placeholder {
+            "args" => Some(self.args.trim().to_string()),
+            "name" => self.name.map(|s| s.to_string()),
+            "params" => self.params.clone(),
+            _ => {
+                let key = placeholder.strip_prefix("args.")?;
+                let value = match key.parse::<usize>() {
+                    Ok(i) => self.args.positional().nth(i),
+                    Err(_) => self.args.named(key),
+                };
+                value.map(|value| match value {
+                    // Strings are substituted unquoted so they can be
+                    // spliced into larger literals.
+                    AnnotationValue::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+            }
+        })
+    }
+
+    /// Expands the placeholders in `template`, returning `None` if one of
+    /// them has no value.
+    fn render(&self, template: &str) -> Option<String> {
+        let mut result = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let placeholder = rest[1..].find('}').map(|end| &rest[1..end + 1]);
+            match placeholder.and_then(|p| Some((p, self.lookup(p)?))) {
+                Some((placeholder, value)) => {
+                    result.push_str(&value?);
+                    rest = &rest[placeholder.len() + 2..];
+                }
+                None => {
+                    result.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        result.push_str(rest);
+        Some(result)
+    }
+
+    fn render_all(&self, templates: &[String]) -> Option<Vec<String>> {
+        templates.iter().map(|t| self.render(t)).collect()
+    }
+}
+
+/// A visitor whose contributions are rendered from string templates, loaded
+/// with [`TemplateVisitor::load`].
+#[derive(Debug)]
+pub struct TemplateVisitor {
+    target: String,
+    priority: i32,
+    inherit: bool,
+    templates: HashMap<ItemKind, Template>,
+    /// The kinds of items there are templates for, in the order of the file.
+    kinds: Vec<ItemKind>,
+}
+
+impl TemplateVisitor {
+    /// Loads one visitor per target from the TOML file at `path`.
+    pub fn load(path: &Path) -> Result<Vec<TemplateVisitor>> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read annotation config {path:?}"))?;
+        TemplateVisitor::parse(&contents)
+            .with_context(|| format!("failed to parse annotation config {path:?}"))
+    }
+
+    /// Parses one visitor per target from the contents of a TOML file.
+    pub fn parse(contents: &str) -> Result<Vec<TemplateVisitor>> {
+        let table = contents.parse::<toml::Table>()?;
+        let mut visitors = Vec::new();
+        for (target, kinds) in table.iter() {
+            let Some(kinds) = kinds.as_table() else {
+                bail!("`{target}` must be a table of item kinds");
+            };
+            let mut templates = HashMap::new();
+            let mut item_kinds = Vec::new();
+            let mut priority = priority::DEFAULT;
+            let mut inherit = false;
+            for (kind_name, template) in kinds.iter() {
+                if kind_name == "priority" {
+                    let Some(value) = template.as_integer().and_then(|p| i32::try_from(p).ok())
+                    else {
+                        bail!("`{target}.priority` must be an integer");
+                    };
+                    priority = value;
+                    continue;
+                }
+                if kind_name == "inherit" {
+                    let Some(value) = template.as_bool() else {
+                        bail!("`{target}.inherit` must be a boolean");
+                    };
+                    inherit = value;
+                    continue;
+                }
+                let Some(kind) = parse_kind(kind_name) else {
+                    bail!("`{target}.{kind_name}` is not a known item kind");
+                };
+                let template = parse_template(kind, template)
+                    .with_context(|| format!("invalid templates for `{target}.{kind_name}`"))?;
+                templates.insert(kind, template);
+                item_kinds.push(kind);
+            }
+            visitors.push(TemplateVisitor {
+                target: target.clone(),
+                priority,
+                inherit,
+                templates,
+                kinds: item_kinds,
+            });
+        }
+        Ok(visitors)
+    }
+
+    fn type_contribution(&self, kind: ItemKind, scope: Scope<'_>) -> Option<RustTypeContribution> {
+        let template = self.templates.get(&kind)?;
+        Some(RustTypeContribution {
+            derives: scope.render_all(&template.derives)?,
+            attributes: scope.render_all(&template.attributes)?,
+            items: scope.render_all(&template.items)?,
+            ..RustTypeContribution::new()
+        })
+    }
+
+    fn module_contribution(
+        &self,
+        kind: ItemKind,
+        scope: Scope<'_>,
+    ) -> Option<RustModuleContribution> {
+        let template = self.templates.get(&kind)?;
+        Some(RustModuleContribution {
+            use_statements: scope.render_all(&template.uses)?,
+            attributes: scope.render_all(&template.attributes)?,
+            ..RustModuleContribution::new()
+        })
+    }
+
+    fn attributes(&self, kind: ItemKind, scope: Scope<'_>) -> Option<Vec<String>> {
+        scope.render_all(&self.templates.get(&kind)?.attributes)
+    }
+}
+
+fn parse_template(kind: ItemKind, value: &toml::Value) -> Result<Template> {
+    let Some(table) = value.as_table() else {
+        bail!("expected a table");
+    };
+    let mut template = Template::default();
+    for (key, value) in table.iter() {
+        let (list, applies) = match key.as_str() {
+            "derives" => (&mut template.derives, is_type(kind)),
+            "attributes" => (&mut template.attributes, true),
+            "items" => (&mut template.items, defines_type(kind)),
+            "body-prefix" => (&mut template.body_prefix, kind == ItemKind::Function),
+            "body-suffix" => (&mut template.body_suffix, kind == ItemKind::Function),
+            "result-transforms" => (&mut template.result_transforms, kind == ItemKind::Function),
+            "uses" => (&mut template.uses, is_module(kind)),
+            _ => bail!("unknown key `{key}`"),
+        };
+        if !applies {
+            bail!("`{key}` does not apply to this item kind");
+        }
+        let Some(values) = value.as_array() else {
+            bail!("`{key}` must be an array of strings");
+        };
+        for value in values {
+            let Some(value) = value.as_str() else {
+                bail!("`{key}` must be an array of strings");
+            };
+            if kind != ItemKind::Function && value.contains("{params}") {
+                bail!("`{{params}}` is only available for functions");
+            }
+            list.push(value.to_string());
+        }
+    }
+    Ok(template)
+}
+
+fn type_scope<'a>(args: &'a AnnotationArgs, cx: &'a VisitContext<'_>, id: TypeId) -> Scope<'a> {
+    Scope {
+        args,
+        name: cx.resolve.types[id].name.as_deref(),
+        params: None,
+    }
+}
+
+fn named_scope<'a>(args: &'a AnnotationArgs, name: &'a str) -> Scope<'a> {
+    Scope {
+        args,
+        name: Some(name),
+        params: None,
+    }
+}
+
+impl Visitor for TemplateVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        &self.target
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    fn inherit(&self) -> bool {
+        self.inherit
+    }
+
+    fn item_kinds(&self) -> Option<&[ItemKind]> {
+        Some(&self.kinds)
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Record, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_variant(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _variant: &Variant,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Variant, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_enum(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _enum_: &Enum,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Enum, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Flags, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_resource(
+        &mut self,
+        annotation: &AnnotationArgs,
+        resource_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Resource, type_scope(annotation, cx, resource_id))
+    }
+
+    fn visit_alias(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _ty: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Alias, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_tuple(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _tuple: &Tuple,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Tuple, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_list(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _element: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::List, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_option(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _payload: &Type,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Option, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_result(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _result: &Result_,
+        type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        self.type_contribution(ItemKind::Result, type_scope(annotation, cx, type_id))
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let scope = named_scope(annotation, &field.name);
+        Some(RustFieldContribution {
+            attributes: self.attributes(ItemKind::Field, scope)?,
+            ..RustFieldContribution::new()
+        })
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        annotation: &AnnotationArgs,
+        case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let scope = named_scope(annotation, &case.name);
+        Some(RustVariantCaseContribution {
+            attributes: self.attributes(ItemKind::Case, scope)?,
+            ..RustVariantCaseContribution::new()
+        })
+    }
+
+    fn visit_flag(
+        &mut self,
+        annotation: &AnnotationArgs,
+        flag: &Flag,
+        _flag_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let scope = named_scope(annotation, &flag.name);
+        Some(RustVariantCaseContribution {
+            attributes: self.attributes(ItemKind::Flag, scope)?,
+            ..RustVariantCaseContribution::new()
+        })
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let template = self.templates.get(&ItemKind::Function)?;
+        let params = func
+            .params
+            .iter()
+            .filter(|(name, _)| name != "self")
+            .map(|(name, _)| to_rust_ident(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let scope = Scope {
+            args: annotation,
+            name: Some(&func.name),
+            params: Some(params),
+        };
+        Some(RustFunctionContribution {
+            attributes: scope.render_all(&template.attributes)?,
+            body_prefix: scope.render_all(&template.body_prefix)?,
+            body_suffix: scope.render_all(&template.body_suffix)?,
+            result_transforms: scope.render_all(&template.result_transforms)?,
+            ..RustFunctionContribution::new()
+        })
+    }
+
+    fn visit_interface(
+        &mut self,
+        annotation: &AnnotationArgs,
+        interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        let name = interface.and_then(|i| i.name.as_deref()).unwrap_or("");
+        self.module_contribution(ItemKind::Interface, named_scope(annotation, name))
+    }
+
+    fn visit_world(
+        &mut self,
+        annotation: &AnnotationArgs,
+        world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        self.module_contribution(ItemKind::World, named_scope(annotation, &world.name))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::annotation_visitor::RustFunctionContribution;
+use crate::annotation_visitor::{RustFunctionContribution, RustParamContribution};
 use crate::{
     classify_constructor_return_type, int_repr, to_rust_ident, ConstructorReturnType, Identifier,
     InterfaceGenerator, RustFlagsRepr,
@@ -23,6 +23,11 @@ pub(super) struct FunctionBindgen<'a, 'b> {
     pub handle_decls: Vec<String>,
     always_owned: bool,
     func_contributions: &'b [crate::annotation_visitor::RustFunctionContribution],
+    /// Contributions for each parameter, indexed like `Function::params`.
+    param_contributions: &'b [Vec<RustParamContribution>],
+    /// Declaration of the `TRACE_CALL` constant for exports whose calls are
+    /// reported to the call observer.
+    pub trace_call_site: Option<String>,
 }
 
 pub const POINTER_SIZE_EXPRESSION: &str = "::core::mem::size_of::<*const u8>()";
@@ -34,6 +39,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         wasm_import_module: &'b str,
         always_owned: bool,
         func_contributions: &'b [RustFunctionContribution],
+        param_contributions: &'b [Vec<RustParamContribution>],
     ) -> FunctionBindgen<'a, 'b> {
         FunctionBindgen {
             r#gen,
@@ -49,6 +55,8 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             handle_decls: Vec::new(),
             always_owned,
             func_contributions,
+            param_contributions,
+            trace_call_site: None,
         }
     }
 
@@ -221,6 +229,23 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         }
     }
 
+    fn lower_alias(&mut self, _resolve: &Resolve, id: TypeId, operand: String) -> String {
+        if self.r#gen.r#gen.newtypes.contains(&id) {
+            format!("({operand}).0")
+        } else {
+            operand
+        }
+    }
+
+    fn lift_alias(&mut self, _resolve: &Resolve, id: TypeId, operand: String) -> String {
+        if self.r#gen.r#gen.newtypes.contains(&id) {
+            let name = self.r#gen.type_path(id, true);
+            format!("{name}({operand})")
+        } else {
+            operand
+        }
+    }
+
     fn return_pointer(&mut self, size: ArchitectureSize, align: Alignment) -> String {
         let tmp = self.tmp();
 
@@ -886,6 +911,17 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     })
                     .collect();
 
+                // Emit visitor-contributed parameter code, which runs against
+                // the bound arguments in parameter order
+                for contribs in self.param_contributions {
+                    for contrib in contribs {
+                        for code in &contrib.code {
+                            self.src.push_str(code);
+                            self.src.push_str("\n");
+                        }
+                    }
+                }
+
                 // Emit visitor-contributed body prefix code (after lifting, before trait call)
                 for contrib in self.func_contributions {
                     for code in &contrib.body_prefix {
@@ -894,6 +930,102 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     }
                 }
 
+                // Bind the trait method's return value to `func_return` so that
+                // visitor-contributed result transforms can replace it before
+                // it's lowered, for both sync and async exports
+                let func_contributions = self.func_contributions;
+                let result_transforms = func_contributions
+                    .iter()
+                    .flat_map(|c| c.result_transforms.iter())
+                    .collect::<Vec<_>>();
+                let trace = self.trace_call_site.take();
+                let bind_result =
+                    func.result.is_some() && (!result_transforms.is_empty() || trace.is_some());
+
+                // Check the arguments against the validations contributed
+                // for them and with their `Validate` impls. A failure traps,
+                // unless a visitor supplied a conversion into the error of
+                // the function's `result`, which is then returned instead of
+                // calling the trait method.
+                let rt = self.r#gen.r#gen.runtime_path().to_string();
+                let mut checks = Vec::new();
+                for (((name, ty), var), contribs) in func
+                    .params
+                    .iter()
+                    .zip(&operand_vars)
+                    .zip(self.param_contributions)
+                {
+                    let validations = contribs.iter().flat_map(|c| &c.validations);
+                    let mut check = String::new();
+                    for validation in validations {
+                        let message = format!("{name} {}", validation.message);
+                        uwrite!(
+                            check,
+                            "if !({}) {{ Err({message:?}) }} else ",
+                            validation.condition
+                        );
+                    }
+                    if !check.is_empty() {
+                        checks.push(format!("{{ let value = &{var}; {check}{{ Ok(()) }} }}"));
+                    }
+                    if self.r#gen.is_validated(ty) {
+                        checks.push(format!("{rt}::Validate::validate(&{var})"));
+                    }
+                }
+                let checks = checks
+                    .into_iter()
+                    .reduce(|checks, check| format!("{checks}.and_then(|()| {check})"));
+                let returns_result = match &func.result {
+                    Some(Type::Id(id)) => matches!(
+                        resolve.types[dealias(resolve, *id)].kind,
+                        TypeDefKind::Result(_)
+                    ),
+                    _ => false,
+                };
+                let validation_error = func_contributions
+                    .iter()
+                    .find_map(|c| c.validation_error.as_ref())
+                    .filter(|_| returns_result && checks.is_some());
+                match (&checks, validation_error) {
+                    (Some(checks), Some(_)) => {
+                        uwriteln!(self.src, "let validation_failure = {checks}.err();");
+                    }
+                    (Some(checks), None) => {
+                        uwriteln!(self.src, "if let Err(e) = {checks} {{");
+                        uwriteln!(
+                            self.src,
+                            "panic!(\"invalid argument to `{}`: {{}}\", e);",
+                            func.name
+                        );
+                        uwriteln!(self.src, "}}");
+                    }
+                    (None, _) => {}
+                }
+
+                // Report the call to the call observer once the arguments have
+                // been checked
+                if let Some(site) = &trace {
+                    let args = func
+                        .params
+                        .iter()
+                        .zip(&operand_vars)
+                        .map(|((_, ty), var)| self.r#gen.trace_value(ty, var))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    uwriteln!(self.src, "{site}");
+                    uwriteln!(self.src, "{rt}::trace_enter(&TRACE_CALL, &[{args}]);");
+                }
+
+                if bind_result {
+                    self.push_str("let func_return = ");
+                }
+                if let Some(conversion) = validation_error {
+                    uwrite!(
+                        self.src,
+                        "if let Some(e) = validation_failure {{ Err({conversion}(e)) }} else {{ "
+                    );
+                }
+
                 let constructor_type = match &func.kind {
                     FunctionKind::Freestanding | FunctionKind::AsyncFreestanding => {
                         self.push_str(&format!("T::{}", to_rust_ident(func.item_name())));
@@ -965,6 +1097,25 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     }
                     None => {}
                 }
+                if validation_error.is_some() {
+                    self.push_str(" }");
+                }
+                if bind_result {
+                    self.push_str(";\n");
+                    // The observer sees the result of the trait method, of the
+                    // function's type, before visitors transform it
+                    if let (Some(ty), true) = (&func.result, trace.is_some()) {
+                        let result = self.r#gen.trace_value(ty, "func_return");
+                        uwriteln!(self.src, "{rt}::trace_exit(&TRACE_CALL, {result});");
+                    }
+                    for expr in result_transforms {
+                        uwriteln!(self.src, "let func_return = {expr};");
+                    }
+                    self.push_str("func_return");
+                } else if trace.is_some() {
+                    self.push_str(";\n");
+                    uwriteln!(self.src, "{rt}::trace_exit(&TRACE_CALL, &());");
+                }
                 self.push_str("\n};\n");
             }
 
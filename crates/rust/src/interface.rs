@@ -6,8 +6,8 @@ use crate::{
 };
 
 use crate::annotation_visitor::{
-    RustFieldContribution, RustFunctionContribution, RustModuleContribution, RustTypeContribution,
-    RustVariantCaseContribution,
+    CheckSyntax, RustFieldContribution, RustFunctionContribution, RustModuleContribution,
+    RustParamContribution, RustTypeContribution, RustValidation, RustVariantCaseContribution,
 };
 
 use anyhow::Result;
@@ -17,9 +17,11 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write as _;
 use std::mem;
 use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
-use wit_bindgen_core::visitor::FindVisitorWithWarning;
+use wit_bindgen_core::visitor::{param_target, priority};
+use wit_bindgen_core::Location;
 use wit_bindgen_core::{
-    dealias, uwrite, uwriteln, wit_parser::*, AnonymousTypeGenerator, Source, TypeInfo,
+    dealias, uwrite, uwriteln, wit_parser::*, AnonymousTypeGenerator, Direction, Source, TypeInfo,
+    VisitContext,
 };
 
 pub struct InterfaceGenerator<'a> {
@@ -33,6 +35,9 @@ pub struct InterfaceGenerator<'a> {
     pub return_pointer_area_size: ArchitectureSize,
     pub return_pointer_area_align: Alignment,
     pub(super) needs_runtime_module: bool,
+    /// Visitor contributions for the resources defined by this generator,
+    /// reused when emitting the export-side `Guest*` traits.
+    pub(super) resource_contributions: HashMap<TypeId, Vec<RustTypeContribution>>,
 }
 
 /// A description of the "mode" in which a type is printed.
@@ -141,6 +146,74 @@ enum PayloadFor {
     Stream,
 }
 
+/// Takes the derive helper attributes out of the contributions for a type,
+/// i.e. the attributes of visitors at [`priority::DERIVE_HELPERS`] or above,
+/// which go after the type's `#[derive]` since the derive macros read them.
+fn take_derive_helpers(
+    contributions: Vec<(i32, RustTypeContribution)>,
+) -> (Vec<RustTypeContribution>, Vec<String>) {
+    let mut helpers = Vec::new();
+    let contributions = contributions
+        .into_iter()
+        .map(|(visitor_priority, mut contrib)| {
+            if visitor_priority >= priority::DERIVE_HELPERS {
+                helpers.append(&mut contrib.attributes);
+            }
+            contrib
+        })
+        .collect();
+    (contributions, helpers)
+}
+
+/// Collects the `#[cfg]`s among the visitor-contributed attributes of a
+/// function, one per line. They also gate the glue generated for an exported
+/// function, which would otherwise call the trait method they remove.
+fn cfg_attributes(func_contributions: &[RustFunctionContribution]) -> String {
+    func_contributions
+        .iter()
+        .flat_map(|c| &c.attributes)
+        .filter(|attr| attr.starts_with("#[cfg("))
+        .map(|attr| format!("{attr}\n"))
+        .collect()
+}
+
+/// Whether the visitor-contributed attribute `attr` of a function can be
+/// placed on a trait method without a body: `#[inline]` is ignored there
+/// with a warning.
+fn applies_to_prototype(attr: &str) -> bool {
+    !(attr == "#[inline]" || attr.starts_with("#[inline("))
+}
+
+/// Collects the visitor-contributed attributes of each parameter, for
+/// [`FnSig::param_attributes`].
+fn param_attributes(param_contributions: &[Vec<RustParamContribution>]) -> Vec<Vec<String>> {
+    param_contributions
+        .iter()
+        .map(|contribs| {
+            contribs
+                .iter()
+                .flat_map(|c| c.attributes.iter().cloned())
+                .collect()
+        })
+        .collect()
+}
+
+/// A contribution for an annotation on a function, which applies to one of
+/// the function's parameters if the annotation names it.
+enum FunctionAnnotationContribution {
+    Function(RustFunctionContribution),
+    Param(usize, RustParamContribution),
+}
+
+impl CheckSyntax for FunctionAnnotationContribution {
+    fn check_syntax(&self) -> Result<(), String> {
+        match self {
+            FunctionAnnotationContribution::Function(contrib) => contrib.check_syntax(),
+            FunctionAnnotationContribution::Param(_, contrib) => contrib.check_syntax(),
+        }
+    }
+}
+
 impl<'i> InterfaceGenerator<'i> {
     pub(super) fn generate_exports<'a>(
         &mut self,
@@ -153,23 +226,6 @@ impl<'i> InterfaceGenerator<'i> {
 
         traits.insert(None, ("Guest".to_string(), Vec::new()));
 
-        // Collect function contributions from visitors
-        let mut func_contributions: HashMap<String, Vec<RustFunctionContribution>> = HashMap::new();
-
-        if let Some((id, _)) = interface {
-            for (func_name, func) in &self.resolve.interfaces[id].functions {
-                let mut contributions = vec![];
-                for (target, value) in func.annotations.iter() {
-                    if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(target, None) {
-                        if let Some(contrib) = visitor.visit_function(value, func) {
-                            contributions.push(contrib);
-                        }
-                    }
-                }
-                func_contributions.insert(func_name.clone(), contributions);
-            }
-        }
-
         if let Some((id, _)) = interface {
             for (name, id) in self.resolve.interfaces[id].types.iter() {
                 match self.resolve.types[*id].kind {
@@ -192,20 +248,18 @@ impl<'i> InterfaceGenerator<'i> {
                 .is_async(self.resolve, interface.map(|p| p.1), func, false);
             let resource = func.kind.resource();
 
-            funcs_to_export.push((func, resource, async_));
             let (trait_name, methods) = traits.get_mut(&resource).unwrap();
 
-            let contributions = func_contributions
-                .get(&func.name)
-                .map(|v| v.as_slice())
-                .unwrap_or(&[]);
+            let (contributions, param_contributions) = self.function_contributions(func);
+            funcs_to_export.push((func, resource, async_, cfg_attributes(&contributions)));
 
             self.generate_guest_export(
                 func,
                 interface.map(|(_, k)| k),
                 &trait_name,
                 async_,
-                contributions,
+                &contributions,
+                &param_contributions,
             );
 
             let prev = mem::take(&mut self.src);
@@ -213,18 +267,16 @@ impl<'i> InterfaceGenerator<'i> {
                 async_,
                 use_item_name: true,
                 private: true,
+                param_attributes: param_attributes(&param_contributions),
                 ..Default::default()
             };
             sig.update_for_func(&func);
 
             // Emit visitor-contributed attributes for trait method
-            if let Some(contribs) = func_contributions.get(&func.name) {
-                for contrib in contribs {
-                    for attr in &contrib.attributes {
-                        self.src.push_str(attr);
-                        self.src.push_str("\n");
-                    }
-                }
+            let attrs = contributions.iter().flat_map(|c| &c.attributes);
+            for attr in attrs.filter(|a| applies_to_prototype(a)) {
+                self.src.push_str(attr);
+                self.src.push_str("\n");
             }
 
             self.print_signature(func, true, &sig);
@@ -245,8 +297,25 @@ impl<'i> InterfaceGenerator<'i> {
         }
 
         for (resource, (trait_name, methods)) in traits.iter() {
-            uwriteln!(self.src, "pub trait {trait_name}: 'static {{");
             let resource = resource.unwrap();
+
+            // Emit visitor-contributed attributes for the resource's trait
+            let attrs = self
+                .resource_contributions
+                .get(&resource)
+                .map(|contribs| {
+                    contribs
+                        .iter()
+                        .flat_map(|c| c.attributes.iter().cloned())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            for attr in attrs {
+                self.src.push_str(&attr);
+                self.src.push_str("\n");
+            }
+
+            uwriteln!(self.src, "pub trait {trait_name}: 'static {{");
             let resource_name = self.resolve.types[resource].name.as_ref().unwrap();
             let (_, interface_name) = interface.unwrap();
             let module = self.resolve.name_world_key(interface_name);
@@ -329,7 +398,7 @@ macro_rules! {macro_name} {{
 "
         );
 
-        for (func, resource, async_) in funcs_to_export {
+        for (func, resource, async_, cfgs) in funcs_to_export {
             let ty = match resource {
                 None => "$ty".to_string(),
                 Some(id) => {
@@ -341,7 +410,7 @@ macro_rules! {macro_name} {{
                     format!("<$ty as $($path_to_types)*::Guest>::{name}")
                 }
             };
-            self.generate_raw_cabi_export(func, &ty, "$($path_to_types)*", async_);
+            self.generate_raw_cabi_export(func, &ty, "$($path_to_types)*", async_, &cfgs);
         }
         let export_prefix = self.r#gen.opts.export_prefix.as_deref().unwrap_or("");
         for name in resources_to_drop {
@@ -403,39 +472,107 @@ macro_rules! {macro_name} {{
         funcs: impl Iterator<Item = &'a Function>,
         interface: Option<&WorldKey>,
     ) {
-        // Collect function contributions from visitors
-        let mut func_contributions: HashMap<String, Vec<RustFunctionContribution>> = HashMap::new();
-
-        if let Some(key) = interface {
-            if let WorldKey::Interface(id) = key {
-                for (func_name, func) in &self.resolve.interfaces[*id].functions {
-                    let mut contributions = vec![];
-                    for (target, value) in func.annotations.iter() {
-                        if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(target, None) {
-                            if let Some(contrib) = visitor.visit_function(value, func) {
-                                contributions.push(contrib);
-                            }
-                        }
-                    }
-                    func_contributions.insert(func_name.clone(), contributions);
-                }
-            }
-        }
-
         for func in funcs {
-            let contributions = func_contributions
-                .get(&func.name)
-                .map(|v| v.as_slice())
-                .unwrap_or(&[]);
+            let (contributions, param_contributions) = self.function_contributions(func);
 
             self.generate_guest_import(
                 func,
                 interface,
-                contributions,
+                &contributions,
+                &param_contributions,
             );
         }
     }
 
+    /// The location of the interface or world being generated, used to point
+    /// diagnostics about annotations at the WIT source.
+    fn location(&self) -> Location {
+        match self.identifier {
+            Identifier::Interface(id, _) => Location::for_interface(self.resolve, id),
+            Identifier::World(id) => Location::for_world(self.resolve, id),
+            Identifier::StreamOrFuturePayload => Location::default(),
+        }
+    }
+
+    /// The context visitors get for an item defined by this generator, called
+    /// `name` in the generated code.
+    fn visit_context(&self, name: Option<String>) -> VisitContext<'i> {
+        let (interface, module_path) = match self.identifier {
+            Identifier::Interface(id, key) => {
+                let path = crate::compute_module_path(key, self.resolve, !self.in_import);
+                (Some(id), Some(path.join("::")))
+            }
+            Identifier::World(_) | Identifier::StreamOrFuturePayload => (None, None),
+        };
+        VisitContext {
+            interface,
+            direction: Some(if self.in_import {
+                Direction::Import
+            } else {
+                Direction::Export
+            }),
+            name,
+            module_path,
+            ..VisitContext::new(self.resolve, self.r#gen.world.unwrap())
+        }
+    }
+
+    /// The context visitors get for a type generated as `name`.
+    ///
+    /// Types generated in two modes are visited once per mode, so `name` is
+    /// e.g. `FooParam` or `FooResult` rather than `Foo`.
+    fn type_visit_context(&self, name: &str) -> VisitContext<'i> {
+        self.visit_context(Some(name.to_string()))
+    }
+
+    /// Collects visitor contributions for `func`, and for each of its
+    /// parameters.
+    ///
+    /// This works the same for functions of an interface and for functions
+    /// imported or exported directly by a world.
+    fn function_contributions(
+        &mut self,
+        func: &Function,
+    ) -> (Vec<RustFunctionContribution>, Vec<Vec<RustParamContribution>>) {
+        let mut contributions = vec![];
+        let mut param_contributions = vec![Vec::new(); func.params.len()];
+        let location = self.location().member(&func.name);
+        let cx = self.visit_context(Some(to_rust_ident(func.item_name())));
+        let param_cxs = func
+            .params
+            .iter()
+            .map(|(name, _)| self.visit_context(Some(to_rust_ident(name))))
+            .collect::<Vec<_>>();
+        let contribs = self.r#gen.visit_annotations(
+            &location,
+            func.annotations.iter(),
+            Some(&cx),
+            |visitor, args| {
+                // Annotations naming a parameter go to `visit_param` first,
+                // and to `visit_function` if the visitor declines them.
+                if let Some((index, rest)) = param_target(func, args) {
+                    if let Some(contrib) =
+                        visitor.visit_param(&rest, func, index, &param_cxs[index])
+                    {
+                        return Some(FunctionAnnotationContribution::Param(index, contrib));
+                    }
+                }
+                visitor
+                    .visit_function(args, func, &cx)
+                    .map(FunctionAnnotationContribution::Function)
+            },
+        );
+        for contrib in contribs {
+            match contrib {
+                FunctionAnnotationContribution::Function(contrib) => contributions.push(contrib),
+                FunctionAnnotationContribution::Param(index, contrib) => {
+                    param_contributions[index].push(contrib)
+                }
+            }
+        }
+        (contributions, param_contributions)
+    }
+
     pub fn align_area(&mut self, alignment: Alignment) {
         match alignment {
             Alignment::Pointer => uwriteln!(
@@ -513,6 +650,7 @@ macro_rules! {macro_name} {{
 
     pub fn finish_append_submodule(mut self, snake: &str, module_path: Vec<String>, docs: &Docs) {
         let mut module = self.finish();
+        let mut module_attrs = String::new();
 
         // Apply visitor contributions to the module if any are registered
         // Extract the current interface being generated, if we're in an interface context
@@ -524,24 +662,34 @@ macro_rules! {macro_name} {{
         if let Some(interface) = interface_obj {
             // Create a container to accumulate all visitor contributions for this module
             let mut visitor_contribution = RustModuleContribution::new();
+            let location = self.location();
+            let cx = self.visit_context(Some(snake.to_string()));
+
+            // Let the visitors registered for the annotations attached to this
+            // interface inspect it and generate code contributions
+            // (Annotations are key-value pairs where the key identifies which visitors should handle it)
+            for contrib in self.r#gen.visit_annotations(
+                &location,
+                interface.annotations.iter(),
+                None,
+                |visitor, args| visitor.visit_interface(args, Some(interface), &cx),
+            ) {
+                // Merge this visitor's use statements into our accumulated set
+                visitor_contribution
+                    .use_statements
+                    .extend(contrib.use_statements);
+                // Merge this visitor's additional code into our accumulated set
+                visitor_contribution
+                    .additional_code
+                    .extend(contrib.additional_code);
+                // Merge this visitor's attributes for the `pub mod` item
+                visitor_contribution
+                    .attributes
+                    .extend(contrib.attributes);
+            }
 
-            // Iterate through all annotations attached to this interface
-            // (Annotations are key-value pairs where the key identifies which visitor should handle it)
-            for (target, value) in interface.annotations.iter() {
-                // Look up the visitor registered for this annotation target
-                if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(target, None) {
-                    // Let the visitor inspect the interface and generate code contributions
-                    if let Some(contrib) = visitor.visit_interface(value, Some(interface)) {
-                        // Merge this visitor's use statements into our accumulated set
-                        visitor_contribution
-                            .use_statements
-                            .extend(contrib.use_statements);
-                        // Merge this visitor's additional code into our accumulated set
-                        visitor_contribution
-                            .additional_code
-                            .extend(contrib.additional_code);
-                    }
-                }
+            for attr in &visitor_contribution.attributes {
+                module_attrs.push_str(&format!("{}\n", attr));
             }
 
             // Apply visitor contributions to module (need to merge their contributions into the module string)
@@ -580,6 +728,7 @@ macro_rules! {macro_name} {{
         let module = format!(
             "\
                 {docs}
+                {module_attrs}
                 #[allow(dead_code, async_fn_in_trait, unused_imports, clippy::all)]
                 pub mod {snake} {{
                     {used_static}
@@ -807,11 +956,74 @@ pub mod vtable{ordinal} {{
         map.insert(name, code);
     }
 
+    /// Declares the `TRACE_CALL` constant identifying calls of `func` to the
+    /// call observer, if they're traced, either because of the `trace_calls`
+    /// option or of a visitor.
+    fn trace_call_site(
+        &self,
+        func: &Function,
+        interface: Option<&WorldKey>,
+        export: bool,
+        func_contributions: &[RustFunctionContribution],
+    ) -> Option<String> {
+        if !self.r#gen.opts.trace_calls && !func_contributions.iter().any(|c| c.trace) {
+            return None;
+        }
+        let rt = self.r#gen.runtime_path();
+        let interface = match interface {
+            Some(key) => format!("Some({:?})", self.resolve.name_world_key(key)),
+            None => "None".to_string(),
+        };
+        Some(format!(
+            "const TRACE_CALL: {rt}::CallSite = {rt}::CallSite {{ interface: {interface}, function: {:?}, export: {export} }};",
+            func.name
+        ))
+    }
+
+    /// The argument or result `value` of type `ty` as it's reported to the
+    /// call observer, which renders it with `Debug`. Values of types remapped
+    /// with `with`, which might not implement it, are reported as
+    /// `Opaque`.
+    pub(super) fn trace_value(&self, ty: &Type, value: &str) -> String {
+        if self.implements_debug(ty) {
+            format!("&{value}")
+        } else {
+            format!("&{}::Opaque", self.r#gen.runtime_path())
+        }
+    }
+
+    fn implements_debug(&self, ty: &Type) -> bool {
+        let Type::Id(id) = ty else {
+            return true;
+        };
+        let ty = &self.resolve.types[*id];
+        if ty.name.is_some() {
+            let name = full_wit_type_name(self.resolve, *id);
+            if let Some(TypeGeneration::Remap(_)) = self.r#gen.with.get(&name) {
+                return false;
+            }
+        }
+        match &ty.kind {
+            TypeDefKind::Type(ty)
+            | TypeDefKind::List(ty)
+            | TypeDefKind::FixedSizeList(ty, _)
+            | TypeDefKind::Option(ty) => self.implements_debug(ty),
+            TypeDefKind::Tuple(tuple) => tuple.types.iter().all(|ty| self.implements_debug(ty)),
+            TypeDefKind::Result(result) => result
+                .ok
+                .iter()
+                .chain(&result.err)
+                .all(|ty| self.implements_debug(ty)),
+            _ => true,
+        }
+    }
+
     fn generate_guest_import(
         &mut self,
         func: &Function,
         interface: Option<&WorldKey>,
         func_contributions: &[RustFunctionContribution],
+        param_contributions: &[Vec<RustParamContribution>],
     ) {
         if self.r#gen.skip.contains(&func.name) {
             return;
@@ -822,6 +1034,7 @@ pub mod vtable{ordinal} {{
         let async_ = self.r#gen.is_async(self.resolve, interface, func, true);
         let mut sig = FnSig {
             async_,
+            param_attributes: param_attributes(param_contributions),
             ..Default::default()
         };
         if let Some(id) = func.kind.resource() {
@@ -844,6 +1057,16 @@ pub mod vtable{ordinal} {{
         let params = self.print_signature(func, async_, &sig);
         self.src.push_str("{\n");
 
+        // Emit visitor-contributed parameter code, which runs against the
+        // arguments before anything else
+        for contribs in param_contributions {
+            for contrib in contribs {
+                for code in &contrib.code {
+                    uwriteln!(self.src, "    {}", code);
+                }
+            }
+        }
+
         // Emit visitor-contributed body prefix code
         for contrib in func_contributions {
             for code in &contrib.body_prefix {
@@ -851,6 +1074,22 @@ pub mod vtable{ordinal} {{
             }
         }
 
+        // Report the call to the call observer, with the arguments as they're
+        // passed to the import
+        let trace = self.trace_call_site(func, interface, false, func_contributions);
+        let rt = self.r#gen.runtime_path().to_string();
+        if let Some(site) = &trace {
+            let args = func
+                .params
+                .iter()
+                .zip(&params)
+                .map(|((_, ty), param)| self.trace_value(ty, param))
+                .collect::<Vec<_>>()
+                .join(", ");
+            uwriteln!(self.src, "{site}");
+            uwriteln!(self.src, "{rt}::trace_enter(&TRACE_CALL, &[{args}]);");
+        }
+
         // If function has a return value, capture it for body_suffix access
         let has_return = func.result.is_some();
         if has_return {
@@ -871,6 +1110,13 @@ pub mod vtable{ordinal} {{
         if has_return {
             self.src.push_str(";\n");
         }
+        if trace.is_some() {
+            let result = match &func.result {
+                Some(ty) => self.trace_value(ty, "func_return"),
+                None => "&()".to_string(),
+            };
+            uwriteln!(self.src, "{rt}::trace_exit(&TRACE_CALL, {result});");
+        }
         for contrib in func_contributions {
             for code in &contrib.body_suffix {
                 uwriteln!(self.src, "    {}", code);
@@ -894,6 +1140,7 @@ pub mod vtable{ordinal} {{
             module,
             true,
             &[],
+            &[],
         );
         abi::lower_to_memory(f.r#gen.resolve, &mut f, address.into(), value.into(), ty);
         format!("unsafe {{ {} }}", String::from(f.src))
@@ -912,6 +1159,7 @@ pub mod vtable{ordinal} {{
             module,
             true,
             &[],
+            &[],
         );
         abi::deallocate_lists_in_types(f.r#gen.resolve, types, operands, indirect, &mut f);
         format!("unsafe {{ {} }}", String::from(f.src))
@@ -930,6 +1178,7 @@ pub mod vtable{ordinal} {{
             module,
             true,
             &[],
+            &[],
         );
         abi::deallocate_lists_and_own_in_types(f.r#gen.resolve, types, operands, indirect, &mut f);
         format!("unsafe {{ {} }}", String::from(f.src))
@@ -942,6 +1191,7 @@ pub mod vtable{ordinal} {{
             module,
             true,
             &[],
+            &[],
         );
         let result = abi::lift_from_memory(f.r#gen.resolve, &mut f, address.into(), ty);
         format!("unsafe {{ {}\n{result} }}", String::from(f.src))
@@ -959,6 +1209,7 @@ pub mod vtable{ordinal} {{
             module,
             false,
             &[],
+            &[],
         );
         abi::call(
             f.r#gen.resolve,
@@ -1181,6 +1432,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 module,
                 true,
                 &[],
+                &[],
             );
             let mut results = Vec::new();
             for (i, (_, ty)) in func.params.iter().enumerate() {
@@ -1239,24 +1491,22 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
         trait_name: &str,
         async_: bool,
         func_contributions: &[RustFunctionContribution],
+        param_contributions: &[Vec<RustParamContribution>],
     ) {
         let name_snake = func.name.to_snake_case().replace('.', "_");
 
         self.generate_payloads("[export]", func, interface);
 
-        // Emit visitor-contributed attributes
-        for contrib in func_contributions {
-            for attr in &contrib.attributes {
-                self.src.push_str(attr);
-                self.src.push_str("\n");
-            }
-        }
-
+        // Only the `#[cfg]`s of the visitor-contributed attributes apply to
+        // the glue; the others are emitted on the trait method, which the
+        // glue calls even if it's deprecated.
+        let cfgs = cfg_attributes(func_contributions);
         uwrite!(
             self.src,
             "\
+                {cfgs}\
                 #[doc(hidden)]
-                #[allow(non_snake_case, unused_unsafe)]
+                #[allow(non_snake_case, unused_unsafe, deprecated)]
                 pub unsafe fn _export_{name_snake}_cabi<T: {trait_name}>\
             ",
         );
@@ -1279,13 +1529,16 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             );
         }
 
+        let trace_call_site = self.trace_call_site(func, interface, true, func_contributions);
         let mut f = FunctionBindgen::new(
             self,
             params,
             self.wasm_import_module,
             false,
             func_contributions,
+            param_contributions,
         );
+        f.trace_call_site = trace_call_site;
         let variant = if async_ {
             AbiVariant::GuestExportAsync
         } else {
@@ -1334,6 +1587,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             uwrite!(
                 self.src,
                 "\
+                    {cfgs}\
                     #[doc(hidden)]
                     #[allow(non_snake_case)]
                     pub unsafe fn __callback_{name_snake}(event0: u32, event1: u32, event2: u32) -> u32 {{
@@ -1347,6 +1601,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             uwrite!(
                 self.src,
                 "\
+                    {cfgs}\
                     #[doc(hidden)]
                     #[allow(non_snake_case)]
                     pub unsafe fn __post_return_{name_snake}<T: {trait_name}>\
@@ -1361,6 +1616,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 self.wasm_import_module,
                 false,
                 &[],
+                &[],
             );
             abi::post_return(f.r#gen.resolve, func, &mut f);
             let FunctionBindgen {
@@ -1382,6 +1638,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
         ty: &str,
         path_to_self: &str,
         async_: bool,
+        cfgs: &str,
     ) {
         let name_snake = func.name.to_snake_case().replace('.', "_");
         let wasm_module_export_name = match self.identifier {
@@ -1399,6 +1656,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
         uwrite!(
             self.src,
             "\
+                {cfgs}\
                 #[unsafe(export_name = \"{export_prefix}{export_name}\")]
                 unsafe extern \"C\" fn export_{name_snake}\
 ",
@@ -1418,6 +1676,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             uwrite!(
                 self.src,
                 "\
+                    {cfgs}\
                     #[unsafe(export_name = \"{export_prefix}[callback]{export_name}\")]
                     unsafe extern \"C\" fn _callback_{name_snake}(event0: u32, event1: u32, event2: u32) -> u32 {{
                         unsafe {{
@@ -1430,6 +1689,7 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             uwrite!(
                 self.src,
                 "\
+                    {cfgs}\
                     #[unsafe(export_name = \"{export_prefix}cabi_post_{export_name}\")]
                     unsafe extern \"C\" fn _post_return_{name_snake}\
 "
@@ -1676,6 +1936,10 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 continue;
             }
             let name = to_rust_ident(name);
+            for attr in sig.param_attributes.get(i).into_iter().flatten() {
+                self.push_str(attr);
+                self.push_str(" ");
+            }
             self.push_str(&name);
             self.push_str(": ");
 
@@ -2171,15 +2435,15 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             .cloned()
             .collect();
         for (name, mode) in self.modes_of(id) {
-            let mut type_contributions: Vec<RustTypeContribution> = vec![];
-
-            for (annotation_target, annotation_value) in self.resolve.types[id].annotations.iter() {
-                if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(annotation_target, None) {
-                    if let Some(contribution) = visitor.visit_record(annotation_value, record, id) {
-                        type_contributions.push(contribution);
-                    }
-                }
-            }
+            let location = Location::for_type(self.resolve, id);
+            let cx = self.type_visit_context(&name);
+            let (type_contributions, helpers) =
+                take_derive_helpers(self.r#gen.visit_annotations_with_priority(
+                    &location,
+                    self.resolve.types[id].annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_record(args, record, id, &cx),
+                ));
 
             self.rustdoc(docs);
             let mut derives = BTreeSet::new();
@@ -2203,21 +2467,20 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             }
 
             if info.is_copy() {
-                self.push_str("#[repr(C)]\n");
                 derives.extend(["Copy", "Clone"].into_iter().map(|s| s.to_string()));
             } else if info.is_clone() {
                 derives.insert("Clone".to_string());
             }
 
             // Emit visitor-contributed custom attributes:
-            // These appear before the #[derive(...)] attribute on the struct definition.
-            // Examples include #[serde(rename_all = "camelCase")] or other custom proc-macro
-            // attributes that visitors want to apply to customize code generation.
-            for contrib in type_contributions.iter() {
-                for attr in &contrib.attributes {
-                    self.push_str(attr);
-                    self.push_str("\n");
-                }
+            // These appear before the #[repr(C)] and #[derive(...)] attributes on the
+            // struct definition, so that a #[cfg] comes first. Derive helper attributes
+            // such as #[serde(rename_all = "camelCase")] follow the #[derive(...)] that
+            // declares them instead.
+            self.push_type_attributes(&type_contributions);
+
+            if info.is_copy() {
+                self.push_str("#[repr(C)]\n");
             }
 
             if !derives.is_empty() {
@@ -2225,21 +2488,41 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
                 self.push_str(")]\n")
             }
+            for attr in &helpers {
+                self.push_str(attr);
+                self.push_str("\n");
+            }
             self.push_str(&format!("pub struct {}", name));
             self.print_generics(mode.lifetime);
             self.push_str(" {\n");
 
+            let mut validate = String::new();
             for (field_idx, field) in record.fields.iter().enumerate() {
                 let mut field_contributions: Vec<RustFieldContribution> = vec![];
 
-                for (annotation_target, annotation_value) in field.annotations.iter() {
-                    if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(annotation_target, None) {
-                        if let Some(contrib) =
-                            visitor.visit_field(annotation_value, field, field_idx)
-                        {
-                            field_contributions.push(contrib);
-                        }
-                    }
+                let location = Location::for_type(self.resolve, id).member(&field.name);
+                let cx = self.visit_context(Some(to_rust_ident(&field.name)));
+                for contrib in self.r#gen.visit_annotations(
+                    &location,
+                    field.annotations.iter(),
+                    Some(&cx),
+                    |visitor, args| visitor.visit_field(args, field, field_idx, &cx),
+                ) {
+                    field_contributions.push(contrib);
+                }
+
+                let checks = self.validation_checks(
+                    &format!(
+                        "{}.{}",
+                        self.resolve.types[id].name.as_ref().unwrap(),
+                        field.name
+                    ),
+                    &field.ty,
+                    field_contributions.iter().flat_map(|c| &c.validations),
+                );
+                if !checks.is_empty() {
+                    let field_name = to_rust_ident(&field.name);
+                    uwriteln!(validate, "{{\nlet value = &self.{field_name};\n{checks}}}");
                 }
 
                 self.rustdoc(&field.docs);
@@ -2306,6 +2589,12 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 self.push_str(&name);
                 self.push_str(" {}\n");
             }
+
+            self.push_type_items(&type_contributions, &name, mode);
+            // Exports only lift owned values, so only those are validated.
+            if mode.lifetime.is_none() {
+                self.push_validate_impl(id, &name, &validate);
+            }
         }
     }
 
@@ -2344,19 +2633,18 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             .collect();
         for (name, mode) in self.modes_of(id) {
             let mut type_contributions: Vec<RustTypeContribution> = vec![];
+            let mut helpers = vec![];
 
             if let Some(variant) = _variant {
-                for (annotation_target, annotation_value) in
-                    self.resolve.types[id].annotations.iter()
-                {
-                    if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(annotation_target, None) {
-                        if let Some(contribution) =
-                            visitor.visit_variant(annotation_value, variant, id)
-                        {
-                            type_contributions.push(contribution);
-                        }
-                    }
-                }
+                let location = Location::for_type(self.resolve, id);
+                let cx = self.type_visit_context(&name);
+                (type_contributions, helpers) =
+                    take_derive_helpers(self.r#gen.visit_annotations_with_priority(
+                        &location,
+                        self.resolve.types[id].annotations.iter(),
+                        Some(&cx),
+                        |visitor, args| visitor.visit_variant(args, variant, id, &cx),
+                    ));
             }
 
             self.rustdoc(docs);
@@ -2389,42 +2677,59 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             // Emit visitor-contributed enum-level attributes:
             // These appear before the #[derive(...)] on the enum definition itself.
             // Can include attributes like #[repr(C)], #[non_exhaustive], or custom
-            // proc-macro attributes that apply to the entire enum type.
-            for contrib in type_contributions.iter() {
-                for attr in &contrib.attributes {
-                    self.push_str(attr);
-                    self.push_str("\n");
-                }
-            }
+            // proc-macro attributes that apply to the entire enum type. Derive helper
+            // attributes follow the #[derive(...)] instead.
+            self.push_type_attributes(&type_contributions);
 
             if !derives.is_empty() {
                 self.push_str("#[derive(");
                 self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
                 self.push_str(")]\n");
             }
+            for attr in &helpers {
+                self.push_str(attr);
+                self.push_str("\n");
+            }
 
             // emit derives and enum declaration
             self.push_str(&format!("pub enum {name}"));
             self.print_generics(mode.lifetime);
             self.push_str(" {\n");
 
+            let mut validate = String::new();
             for (_case_idx, (case_name, case_docs, payload)) in
                 cases.clone().into_iter().enumerate()
             {
                 let mut case_contributions: Vec<RustVariantCaseContribution> = vec![];
 
                 if let Some(v) = _variant {
-                    for (annotation_target, annotation_value) in
-                        self.resolve.types[id].annotations.iter()
-                    {
-                        if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(annotation_target, None) {
-                            if let Some(contribution) = visitor.visit_variant_case(
-                                annotation_value,
-                                &v.cases[_case_idx],
-                                _case_idx,
-                            ) {
-                                case_contributions.push(contribution);
-                            }
+                    let case = &v.cases[_case_idx];
+                    let location = Location::for_type(self.resolve, id).member(&case.name);
+                    let cx = self.visit_context(Some(case_name.clone()));
+                    for contribution in self.r#gen.visit_annotations(
+                        &location,
+                        case.annotations.iter(),
+                        Some(&cx),
+                        |visitor, args| visitor.visit_variant_case(args, case, _case_idx, &cx),
+                    ) {
+                        case_contributions.push(contribution);
+                    }
+
+                    if let Some(ty) = payload {
+                        let checks = self.validation_checks(
+                            &format!(
+                                "{}.{}",
+                                self.resolve.types[id].name.as_ref().unwrap(),
+                                case.name
+                            ),
+                            ty,
+                            case_contributions.iter().flat_map(|c| &c.validations),
+                        );
+                        if !checks.is_empty() {
+                            uwriteln!(
+                                validate,
+                                "if let {name}::{case_name}(value) = self {{\n{checks}}}"
+                            );
                         }
                     }
                 }
@@ -2452,18 +2757,6 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 }
                 self.push_str(",\n");
             }
-
-            for (case_name, docs, payload) in cases.clone() {
-                self.rustdoc(docs);
-                self.push_str(&case_name);
-                if let Some(ty) = payload {
-                    self.push_str("(");
-                    let mode = self.filter_mode(ty, mode);
-                    self.print_ty(ty, mode);
-                    self.push_str(")")
-                }
-                self.push_str(",\n");
-            }
             self.push_str("}\n");
 
             self.print_rust_enum_debug(
@@ -2500,6 +2793,11 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                 self.print_generics(mode.lifetime);
                 self.push_str(" {}\n");
             }
+
+            self.push_type_items(&type_contributions, &name, mode);
+            if mode.lifetime.is_none() {
+                self.push_validate_impl(id, &name, &validate);
+            }
         }
     }
 
@@ -2542,19 +2840,53 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
     }
 
     fn print_typedef_option(&mut self, id: TypeId, payload: &Type, docs: &Docs) {
+        let mut has_items = false;
         for (name, mode) in self.modes_of(id) {
+            let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+            let location = Location::for_type(self.resolve, id);
+            let cx = self.type_visit_context(&name);
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_option(args, payload, id, &cx),
+            ) {
+                type_contributions.push(contribution);
+            }
+
             self.rustdoc(docs);
+            self.push_type_attributes(&type_contributions);
             self.push_str(&format!("pub type {}", name));
             self.print_generics(mode.lifetime);
             self.push_str("= Option<");
             self.print_ty(payload, mode);
             self.push_str(">;\n");
+            has_items |= type_contributions.iter().any(|c| !c.items.is_empty());
+        }
+        if has_items {
+            self.warn_alias_items(id);
         }
     }
 
     fn print_typedef_result(&mut self, id: TypeId, result: &Result_, docs: &Docs) {
+        let mut has_items = false;
         for (name, mode) in self.modes_of(id) {
+            let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+            let location = Location::for_type(self.resolve, id);
+            let cx = self.type_visit_context(&name);
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_result(args, result, id, &cx),
+            ) {
+                type_contributions.push(contribution);
+            }
+
             self.rustdoc(docs);
+            self.push_type_attributes(&type_contributions);
             self.push_str(&format!("pub type {}", name));
             self.print_generics(mode.lifetime);
             self.push_str("= Result<");
@@ -2562,6 +2894,10 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             self.push_str(",");
             self.print_optional_ty(result.err.as_ref(), mode);
             self.push_str(">;\n");
+            has_items |= type_contributions.iter().any(|c| !c.items.is_empty());
+        }
+        if has_items {
+            self.warn_alias_items(id);
         }
     }
 
@@ -2584,20 +2920,24 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             self.push_str(&format!("{}\n", attr));
         }
 
+        let location = Location::for_type(self.resolve, id);
+        let cx = self.type_visit_context(&name);
+        let (enum_contributions, helpers) =
+            take_derive_helpers(self.r#gen.visit_annotations_with_priority(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_enum(args, enum_, id, &cx),
+            ));
+
+        // Visitor-contributed attributes go before `#[repr]` so that a
+        // `#[cfg]` comes first, and derive helpers after the `#[derive]`.
+        self.push_type_attributes(&enum_contributions);
+
         self.push_str("#[repr(");
         self.int_repr(enum_.tag());
         self.push_str(")]\n");
 
-        let mut enum_contributions: Vec<RustTypeContribution> = vec![];
-
-        for (annotation_target, annotation_value) in self.resolve.types[id].annotations.iter() {
-            if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(annotation_target, None) {
-                if let Some(contribution) = visitor.visit_enum(annotation_value, enum_, id) {
-                    enum_contributions.push(contribution);
-                }
-            }
-        }
-
         // We use a BTree set to make sure we don't have any duplicates and a stable order
         let mut derives: BTreeSet<String> = BTreeSet::new();
         if !self
@@ -2623,6 +2963,9 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
         self.push_str("#[derive(");
         self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
         self.push_str(")]\n");
+        for attr in &helpers {
+            self.push_str(&format!("{}\n", attr));
+        }
         self.push_str(&format!("pub enum {name} {{\n"));
 
         // Enumerate cases
@@ -2630,22 +2973,23 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
             self.rustdoc(&case.docs);
 
             // Emit visitor-contributed case attributes for enum cases
-            for (target, value) in case.annotations.iter() {
-                if let Some(visitor) = self.r#gen.visitor_map.find_visitor_with_warning(target, None) {
-                    // Create a Case from EnumCase for the visitor
-                    let variant_case = Case {
-                        name: case.name.clone(),
-                        docs: case.docs.clone(),
-                        ty: None,
-                        annotations: case.annotations.clone(),
-                    };
-                    if let Some(contrib) =
-                        visitor.visit_variant_case(value, &variant_case, case_idx)
-                    {
-                        for attr in &contrib.attributes {
-                            self.push_str(&format!("{}\n", attr));
-                        }
-                    }
+            let location = Location::for_type(self.resolve, id).member(&case.name);
+            // Create a Case from EnumCase for the visitor
+            let variant_case = Case {
+                name: case.name.clone(),
+                docs: case.docs.clone(),
+                ty: None,
+                annotations: case.annotations.clone(),
+            };
+            let cx = self.visit_context(Some(case.name.to_upper_camel_case()));
+            for contrib in self.r#gen.visit_annotations(
+                &location,
+                case.annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_variant_case(args, &variant_case, case_idx, &cx),
+            ) {
+                for attr in &contrib.attributes {
+                    self.push_str(&format!("{}\n", attr));
                 }
             }
 
@@ -2735,16 +3079,77 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
                     .map(|c| (c.name.to_upper_camel_case(), None)),
             )
         }
+
+        self.push_type_items(&enum_contributions, &name, TypeMode::owned());
     }
 
     fn print_typedef_alias(&mut self, id: TypeId, ty: &Type, docs: &Docs) {
+        let location = Location::for_type(self.resolve, id);
+        let mut modes = vec![];
+        let mut has_items = false;
         for (name, mode) in self.modes_of(id) {
+            let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+            let cx = self.type_visit_context(&name);
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_alias(args, ty, id, &cx),
+            ) {
+                type_contributions.push(contribution);
+            }
+            modes.push((name, mode, type_contributions));
+        }
+
+        // Only aliases of scalar primitives can become newtypes: those are
+        // `Copy` and passed by value, so wrapping and unwrapping them during
+        // lifting and lowering is free. Other aliases ignore the request with
+        // a warning.
+        let is_scalar = matches!(
+            ty,
+            Type::Bool
+                | Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+                | Type::S8
+                | Type::S16
+                | Type::S32
+                | Type::S64
+                | Type::F32
+                | Type::F64
+                | Type::Char
+        );
+        let newtype = modes
+            .iter()
+            .any(|(_, _, contributions)| contributions.iter().any(|c| c.newtype));
+        if newtype {
+            if is_scalar {
+                // Scalars are passed by value, so they have a single mode.
+                let (_, _, type_contributions) = &modes[0];
+                self.print_typedef_newtype(id, ty, docs, type_contributions);
+                return;
+            }
+            let name = self.resolve.types[id].name.as_deref().unwrap_or_default();
+            self.r#gen.diagnostics.warning(
+                &location,
+                format!("`{name}` doesn't alias a scalar type so it can't become a newtype"),
+            );
+        }
+
+        for (name, mode, type_contributions) in modes {
             self.rustdoc(docs);
+            self.push_type_attributes(&type_contributions);
             self.push_str(&format!("pub type {name}"));
             self.print_generics(mode.lifetime);
             self.push_str(" = ");
             self.print_ty(ty, mode);
             self.push_str(";\n");
+            has_items |= type_contributions.iter().any(|c| !c.items.is_empty());
+        }
+        if has_items {
+            self.warn_alias_items(id);
         }
 
         if self.is_exported_resource(id) {
@@ -2759,6 +3164,192 @@ unsafe fn call_import(&self, _params: Self::ParamsLower, _results: *mut u8) -> u
         }
     }
 
+    /// Renders the alias `id` of the scalar `ty` as a `#[repr(transparent)]`
+    /// wrapper struct with conversions to and from the wrapped type.
+    fn print_typedef_newtype(
+        &mut self,
+        id: TypeId,
+        ty: &Type,
+        docs: &Docs,
+        type_contributions: &[RustTypeContribution],
+    ) {
+        let name = self.resolve.types[id].name.as_ref().unwrap();
+        let mut derives: BTreeSet<String> = ["Clone", "Copy", "Debug"]
+            .into_iter()
+            .map(|s| s.to_string())
+            .collect();
+        if !self
+            .r#gen
+            .opts
+            .additional_derive_ignore
+            .contains(&name.to_kebab_case())
+        {
+            derives.extend(self.r#gen.opts.additional_derive_attributes.iter().cloned());
+        }
+        for contrib in type_contributions.iter() {
+            for derive in contrib.derives.iter() {
+                derives.insert(derive.clone());
+            }
+        }
+        let derives = derives.into_iter().collect::<Vec<_>>().join(", ");
+        let name = to_upper_camel_case(name);
+        let inner = self.type_name_owned(ty);
+
+        self.rustdoc(docs);
+        self.push_type_attributes(type_contributions);
+        uwriteln!(
+            self.src,
+            r#"
+                #[repr(transparent)]
+                #[derive({derives})]
+                pub struct {name}(pub {inner});
+
+                impl From<{inner}> for {name} {{
+                    fn from(value: {inner}) -> Self {{
+                        Self(value)
+                    }}
+                }}
+
+                impl From<{name}> for {inner} {{
+                    fn from(value: {name}) -> Self {{
+                        value.0
+                    }}
+                }}
+            "#
+        );
+        self.push_type_items(type_contributions, &name, TypeMode::owned());
+        self.r#gen.newtypes.insert(id);
+    }
+
+    /// Emits the attributes contributed for a typedef that is rendered as a
+    /// plain `pub type` alias.
+    fn push_type_attributes(&mut self, type_contributions: &[RustTypeContribution]) {
+        for contrib in type_contributions.iter() {
+            for attr in &contrib.attributes {
+                self.push_str(attr);
+                self.push_str("\n");
+            }
+        }
+    }
+
+    /// Reports that the items visitors contributed for the alias `id` are
+    /// ignored: it's generated as a `type` alias of another type, such as an
+    /// `Option` or a `Vec`, so impls for it would be orphan impls or conflict
+    /// with those of other aliases of the same type.
+    fn warn_alias_items(&mut self, id: TypeId) {
+        let name = self.resolve.types[id].name.as_deref().unwrap_or_default();
+        self.r#gen.diagnostics.warning(
+            &Location::for_type(self.resolve, id),
+            format!("`{name}` is a type alias so the items contributed for it are ignored"),
+        );
+    }
+
+    /// Emits the items visitors contributed for the type `name`, generated in
+    /// `mode`, right after its definition.
+    fn push_type_items(
+        &mut self,
+        type_contributions: &[RustTypeContribution],
+        name: &str,
+        mode: TypeMode,
+    ) {
+        let generics = match mode.lifetime {
+            Some(lt) => format!("<{lt}>"),
+            None => String::new(),
+        };
+        for contrib in type_contributions.iter() {
+            for item in contrib.expand_items(name, &generics) {
+                self.push_str(&item);
+                self.push_str("\n");
+            }
+        }
+    }
+
+    /// Whether values of `ty` are checked by a `Validate` impl, either their
+    /// own or that of the records and variants they contain.
+    ///
+    /// Tuples and results are checked through the runtime's impls, which need
+    /// everything else they hold to be a scalar or a string.
+    pub(super) fn is_validated(&self, ty: &Type) -> bool {
+        let Type::Id(id) = ty else {
+            return false;
+        };
+        match &self.resolve.types[*id].kind {
+            TypeDefKind::Record(_) | TypeDefKind::Variant(_) => self.r#gen.validated.contains(id),
+            TypeDefKind::Type(ty) | TypeDefKind::List(ty) | TypeDefKind::Option(ty) => {
+                self.is_validated(ty)
+            }
+            TypeDefKind::Tuple(tuple) => {
+                tuple.types.iter().any(|ty| self.is_validated(ty))
+                    && tuple.types.iter().all(|ty| self.implements_validate(ty))
+            }
+            TypeDefKind::Result(result) => {
+                let mut types = result.ok.iter().chain(&result.err);
+                types.clone().any(|ty| self.is_validated(ty))
+                    && types.all(|ty| self.implements_validate(ty))
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether values of `ty` implement `Validate`, if only with the
+    /// runtime's impls checking nothing.
+    fn implements_validate(&self, ty: &Type) -> bool {
+        let Type::Id(id) = ty else {
+            return !matches!(ty, Type::ErrorContext);
+        };
+        match &self.resolve.types[*id].kind {
+            TypeDefKind::Type(ty) | TypeDefKind::List(ty) | TypeDefKind::Option(ty) => {
+                self.implements_validate(ty)
+            }
+            TypeDefKind::Tuple(tuple) => tuple.types.iter().all(|ty| self.implements_validate(ty)),
+            TypeDefKind::Result(result) => result
+                .ok
+                .iter()
+                .chain(&result.err)
+                .all(|ty| self.implements_validate(ty)),
+            _ => self.is_validated(ty),
+        }
+    }
+
+    /// The statements checking the value of the field or case `item`, of type
+    /// `ty` and bound by reference to `value`, against `validations` and then
+    /// against the `Validate` impls of the types it contains.
+    fn validation_checks<'b>(
+        &self,
+        item: &str,
+        ty: &Type,
+        validations: impl IntoIterator<Item = &'b RustValidation>,
+    ) -> String {
+        let mut checks = String::new();
+        for validation in validations {
+            let message = format!("{item} {}", validation.message);
+            uwriteln!(checks, "if !({}) {{", validation.condition);
+            uwriteln!(checks, "return Err({message:?});");
+            uwriteln!(checks, "}}");
+        }
+        if self.is_validated(ty) {
+            let rt = self.r#gen.runtime_path();
+            uwriteln!(checks, "{rt}::Validate::validate(value)?;");
+        }
+        checks
+    }
+
+    /// Emits the `Validate` impl of the type `id`, generated as `name`, made
+    /// of `checks`, unless there's nothing to check.
+    fn push_validate_impl(&mut self, id: TypeId, name: &str, checks: &str) {
+        if checks.is_empty() {
+            return;
+        }
+        let rt = self.r#gen.runtime_path().to_string();
+        uwriteln!(self.src, "impl {rt}::Validate for {name} {{");
+        self.push_str("fn validate(&self) -> ::core::result::Result<(), &'static str> {\n");
+        self.push_str(checks);
+        self.push_str("Ok(())\n");
+        self.push_str("}\n");
+        self.push_str("}\n");
+        self.r#gen.validated.insert(id);
+    }
+
     fn param_name(&self, ty: TypeId) -> String {
         let info = self.info(ty);
         let name = to_upper_camel_case(self.resolve.types[ty].name.as_ref().unwrap());
@@ -2991,7 +3582,54 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.print_typedef_record(id, record, docs);
     }
 
-    fn type_resource(&mut self, _id: TypeId, name: &str, docs: &Docs) {
+    fn type_resource(&mut self, id: TypeId, name: &str, docs: &Docs) {
+        let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+        let location = Location::for_type(self.resolve, id);
+        let cx = self.type_visit_context(&to_upper_camel_case(name));
+        for contribution in self.r#gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_resource(args, id, &cx),
+        ) {
+            type_contributions.push(contribution);
+        }
+
+        // Visitor attributes and derives are applied to the handle struct for
+        // both imported and exported resources, and drop code is spliced into
+        // the `WasmResource::drop` implementation below.
+        let mut derives = vec!["Debug".to_string()];
+        let mut attrs = String::new();
+        let mut drop_code = String::new();
+        for contrib in type_contributions.iter() {
+            for derive in contrib.derives.iter() {
+                // A handle owns its resource, so a copy of it would drop the
+                // resource a second time.
+                if derive == "Clone" || derive == "Copy" {
+                    self.r#gen.diagnostics.warning(
+                        &location,
+                        format!("`{derive}` can't be derived for resource `{name}`, ignoring it"),
+                    );
+                    continue;
+                }
+                if !derives.contains(derive) {
+                    derives.push(derive.clone());
+                }
+            }
+            for attr in &contrib.attributes {
+                attrs.push_str(attr);
+                attrs.push_str("\n");
+            }
+            for code in &contrib.resource_drop {
+                drop_code.push_str(code);
+                drop_code.push_str("\n");
+            }
+        }
+        let derives = derives.join(", ");
+        self.resource_contributions
+            .insert(id, type_contributions.clone());
+
         self.rustdoc(docs);
         let camel = to_upper_camel_case(name);
         let resource = self.path_to_resource();
@@ -3002,7 +3640,8 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             uwriteln!(
                 self.src,
                 r#"
-                    #[derive(Debug)]
+                    {attrs}
+                    #[derive({derives})]
                     #[repr(transparent)]
                     pub struct {camel} {{
                         handle: {resource}<{camel}>,
@@ -3039,7 +3678,8 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             uwriteln!(
                 self.src,
                 r#"
-#[derive(Debug)]
+{attrs}
+#[derive({derives})]
 #[repr(transparent)]
 pub struct {camel} {{
     handle: {resource}<{camel}>,
@@ -3178,17 +3818,34 @@ impl<'a> {camel}Borrow<'a>{{
                 unsafe impl {wasm_resource} for {camel} {{
                      #[inline]
                      unsafe fn drop(_handle: u32) {{
+                         {drop_code}
                          {intrinsic}
                          unsafe {{ drop(_handle as i32); }}
                      }}
                 }}
             "#
         );
+        self.push_type_items(&type_contributions, &camel, TypeMode::owned());
     }
 
     fn type_tuple(&mut self, id: TypeId, _name: &str, tuple: &Tuple, docs: &Docs) {
+        let mut has_items = false;
         for (name, mode) in self.modes_of(id) {
+            let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+            let location = Location::for_type(self.resolve, id);
+            let cx = self.type_visit_context(&name);
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_tuple(args, tuple, id, &cx),
+            ) {
+                type_contributions.push(contribution);
+            }
+
             self.rustdoc(docs);
+            self.push_type_attributes(&type_contributions);
             self.push_str(&format!("pub type {}", name));
             self.print_generics(mode.lifetime);
             self.push_str(" = (");
@@ -3198,23 +3855,97 @@ impl<'a> {camel}Borrow<'a>{{
                 self.push_str(",");
             }
             self.push_str(");\n");
+            has_items |= type_contributions.iter().any(|c| !c.items.is_empty());
+        }
+        if has_items {
+            self.warn_alias_items(id);
         }
     }
 
-    fn type_flags(&mut self, _id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
-        // TODO flags might be more difficult to implement the visitor for
+    fn type_flags(&mut self, id: TypeId, name: &str, flags: &Flags, docs: &Docs) {
+        let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+        let location = Location::for_type(self.resolve, id);
+        let cx = self.type_visit_context(&name.to_upper_camel_case());
+        for contribution in self.r#gen.visit_annotations(
+            &location,
+            self.resolve.types[id].annotations.iter(),
+            Some(&cx),
+            |visitor, args| visitor.visit_flags(args, flags, id, &cx),
+        ) {
+            type_contributions.push(contribution);
+        }
+
         self.src.push_str(&format!(
             "{bitflags}::bitflags! {{\n",
             bitflags = self.r#gen.bitflags_path()
         ));
         self.rustdoc(docs);
+
+        // The `bitflags!` macro passes outer attributes through to the
+        // generated struct, so visitor attributes are emitted just like they
+        // are for records and variants.
+        for contrib in type_contributions.iter() {
+            for attr in &contrib.attributes {
+                self.push_str(attr);
+                self.push_str("\n");
+            }
+        }
+
+        // Visitor derives are appended to the fixed set of derives below,
+        // skipping any that the flags type already derives.
+        let mut derives: Vec<String> = [
+            "PartialEq",
+            "Eq",
+            "PartialOrd",
+            "Ord",
+            "Hash",
+            "Debug",
+            "Clone",
+            "Copy",
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect();
+        for contrib in type_contributions.iter() {
+            for derive in contrib.derives.iter() {
+                if !derives.contains(derive) {
+                    derives.push(derive.clone());
+                }
+            }
+        }
+
         let repr = RustFlagsRepr::new(flags);
         self.src.push_str(&format!(
-            "#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]\npub struct {}: {repr} {{\n",
+            "#[derive({})]\npub struct {}: {repr} {{\n",
+            derives.join(", "),
             name.to_upper_camel_case(),
         ));
         for (i, flag) in flags.flags.iter().enumerate() {
+            let mut flag_contributions: Vec<RustVariantCaseContribution> = vec![];
+
+            let location = Location::for_type(self.resolve, id).member(&flag.name);
+            let cx = self.visit_context(Some(flag.name.to_shouty_snake_case()));
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                flag.annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_flag(args, flag, i, &cx),
+            ) {
+                flag_contributions.push(contribution);
+            }
+
             self.rustdoc(&flag.docs);
+
+            // Emit flag-specific attributes, e.g. `#[deprecated]`, right above
+            // the constant generated for this flag.
+            for contrib in flag_contributions {
+                for attr in contrib.attributes {
+                    self.push_str(&attr);
+                    self.push_str("\n");
+                }
+            }
+
             self.src.push_str(&format!(
                 "const {} = 1 << {};\n",
                 flag.name.to_shouty_snake_case(),
@@ -3223,6 +3954,11 @@ impl<'a> {camel}Borrow<'a>{{
         }
         self.src.push_str("}\n");
         self.src.push_str("}\n");
+        self.push_type_items(
+            &type_contributions,
+            &name.to_upper_camel_case(),
+            TypeMode::owned(),
+        );
     }
 
     fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
@@ -3272,13 +4008,32 @@ impl<'a> {camel}Borrow<'a>{{
     }
 
     fn type_list(&mut self, id: TypeId, _name: &str, ty: &Type, docs: &Docs) {
+        let mut has_items = false;
         for (name, mode) in self.modes_of(id) {
+            let mut type_contributions: Vec<RustTypeContribution> = vec![];
+
+            let location = Location::for_type(self.resolve, id);
+            let cx = self.type_visit_context(&name);
+            for contribution in self.r#gen.visit_annotations(
+                &location,
+                self.resolve.types[id].annotations.iter(),
+                Some(&cx),
+                |visitor, args| visitor.visit_list(args, ty, id, &cx),
+            ) {
+                type_contributions.push(contribution);
+            }
+
             self.rustdoc(docs);
+            self.push_type_attributes(&type_contributions);
             self.push_str(&format!("pub type {}", name));
             self.print_generics(mode.lifetime);
             self.push_str(" = ");
             self.print_list(ty, mode);
             self.push_str(";\n");
+            has_items |= type_contributions.iter().any(|c| !c.items.is_empty());
+        }
+        if has_items {
+            self.warn_alias_items(id);
         }
     }
 
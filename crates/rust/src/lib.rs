@@ -1,17 +1,22 @@
+use crate::annotation_visitor::{CheckSyntax, RustModuleContribution, TemplateVisitor};
 use crate::interface::InterfaceGenerator;
 use anyhow::{bail, Result};
 use core::panic;
 use heck::*;
 use indexmap::{IndexMap, IndexSet};
-use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Write as _};
 use std::mem;
+use std::path::PathBuf;
 use std::str::FromStr;
 use wit_bindgen_core::abi::{Bitcast, WasmType};
+use wit_bindgen_core::visitor::{
+    check_world_annotations, register_builtins, visit_annotations_with_priority, VisitorMap,
+};
 use wit_bindgen_core::{
-    dealias, name_package_module, uwrite, uwriteln, wit_parser::*, AsyncFilterSet, Files,
-    InterfaceGenerator as _, Source, Types, WorldGenerator,
+    dealias, name_package_module, uwrite, uwriteln, wit_parser::*, AnnotationArgs, AsyncFilterSet,
+    Diagnostic, Diagnostics, Files, InterfaceGenerator as _, Location, Severity, Source, Types,
+    VisitContext, WorldGenerator,
 };
 
 mod bindgen;
@@ -57,7 +62,14 @@ struct RustWasm {
     future_payloads: IndexMap<String, String>,
     stream_payloads: IndexMap<String, String>,
 
-    visitor_map: HashMap<String, Box<RustVisitor>>,
+    visitor_map: VisitorMap<RustVisitor>,
+    /// Aliases that a visitor turned into newtype wrappers. Values of these
+    /// types are wrapped and unwrapped when lifted and lowered.
+    newtypes: HashSet<TypeId>,
+    /// Records and variants with a generated `Validate` impl, whose values
+    /// are checked when lifted as the arguments of exports.
+    validated: HashSet<TypeId>,
+    diagnostics: Diagnostics,
 }
 
 #[derive(Default)]
@@ -144,6 +156,23 @@ fn parse_with(s: &str) -> Result<(String, WithOption), String> {
     Ok((k.to_string(), v))
 }
 
+#[cfg(feature = "clap")]
+fn parse_annotation(s: &str) -> Result<String, String> {
+    wit_bindgen_core::visitor::parse_builtin(
+        s,
+        annotation_visitor::BUILTIN_ANNOTATIONS,
+        annotation_visitor::builtin_visitor,
+    )
+}
+
+#[cfg(feature = "clap")]
+fn parse_priority(s: &str) -> Result<(String, i32), String> {
+    let parsed = s
+        .split_once('=')
+        .and_then(|(target, priority)| Some((target.to_string(), priority.parse().ok()?)));
+    parsed.ok_or_else(|| format!("expected `<target>=<priority>`, e.g. `trace=-200`; got `{s}`"))
+}
+
 #[derive(Default)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
 #[cfg_attr(
@@ -277,10 +306,49 @@ pub struct Opts {
     #[cfg_attr(feature = "clap", arg(long))]
     pub disable_custom_section_link_helpers: bool,
 
+    /// Report every call through an import wrapper or export glue, with its
+    /// arguments and result, to the `CallObserver` set with
+    /// `wit_bindgen::rt::set_call_observer`, as `#trace` does for single
+    /// functions.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub trace_calls: bool,
+
     #[cfg_attr(feature = "clap", clap(flatten))]
     #[cfg_attr(feature = "serde", serde(flatten))]
     pub async_: AsyncFilterSet,
 
+    /// Fail generation on annotation problems, such as annotations without a
+    /// registered visitor, instead of reporting them as warnings.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub strict_annotations: bool,
+
+    /// Built-in annotation visitors to enable, out of `derive`, `serde`,
+    /// `repr`, `cfg`, `deprecated`, `must_use`, `inline`, `assert`, the
+    /// validation rules `non_empty`, `min_length`, `max_length`, `range`,
+    /// `finite` and `email_format`, `validation_error` and `trace`.
+    #[cfg_attr(feature = "clap", arg(long, value_parser = parse_annotation, value_delimiter = ','))]
+    pub annotations: Vec<String>,
+
+    /// Priorities for the contributions of the visitors for a target, as
+    /// `target=priority`, e.g. `trace=-200`, replacing the visitors' own.
+    ///
+    /// Contributions with a lower priority come first, see
+    /// `wit_bindgen_core::visitor::priority` for the bands in use.
+    #[cfg_attr(
+        feature = "clap",
+        arg(long, value_name = "TARGET=PRIORITY", value_parser = parse_priority, value_delimiter = ',')
+    )]
+    pub annotation_priority: Vec<(String, i32)>,
+
+    /// Path to a TOML file describing template visitors for annotations.
+    ///
+    /// See `TemplateVisitor` for the format of the file. Visitors from this
+    /// file take precedence over built-in ones with the same target.
+    #[cfg_attr(feature = "clap", arg(long, value_name = "PATH"))]
+    pub annotation_config: Option<PathBuf>,
+
+    /// Visitors for the annotations in the WIT, registered before the
+    /// built-in ones, see [`register_builtins`].
     #[cfg_attr(feature = "serde", serde(skip))]
     #[cfg_attr(feature = "clap", clap(skip))]
     pub visitors: Vec<Box<RustVisitor>>,
@@ -291,19 +359,39 @@ impl Opts {
         let mut r = RustWasm::new();
         r.skip = self.skip.iter().cloned().collect();
 
-        let mut visitor_map = HashMap::new();
+        let mut visitor_map = VisitorMap::new();
 
         for visitor in mem::take(&mut self.visitors) {
-            match visitor_map.entry(visitor.target().to_string()) {
-                Entry::Occupied(_) => panic!(
-                    "Cannot accept two visitors with the same target of {}",
-                    visitor.target().to_string()
-                ),
-                Entry::Vacant(vacant_entry) => vacant_entry.insert(visitor),
-            };
+            visitor_map.register(visitor);
+        }
+        r.diagnostics = Diagnostics::new(self.strict_annotations);
+        if let Some(path) = &self.annotation_config {
+            match TemplateVisitor::load(path) {
+                Ok(visitors) => {
+                    for visitor in visitors {
+                        visitor_map.register_default(Box::new(visitor));
+                    }
+                }
+                Err(e) => r.diagnostics.error(&Location::for_file(path), format!("{e:#}")),
+            }
+        }
+        register_builtins(
+            &mut visitor_map,
+            &mut r.diagnostics,
+            &self.annotations,
+            annotation_visitor::BUILTIN_ANNOTATIONS,
+            annotation_visitor::builtin_visitor,
+        );
+        for (target, priority) in self.annotation_priority.iter() {
+            if !visitor_map.set_priority(target, *priority) {
+                r.diagnostics.warning(
+                    &Location::default(),
+                    format!("no visitor registered for annotation target '{target}' to prioritize"),
+                );
+            }
         }
         r.visitor_map = visitor_map;
-            
+
         r.opts = self;
         Box::new(r)
     }
@@ -335,9 +423,77 @@ impl RustWasm {
             return_pointer_area_size: Default::default(),
             return_pointer_area_align: Default::default(),
             needs_runtime_module: false,
+            resource_contributions: HashMap::new(),
         }
     }
 
+    /// Dispatches the annotations found at `location` to their visitors, see
+    /// [`wit_bindgen_core::visitor::visit_annotations`].
+    ///
+    /// Contributions whose Rust code doesn't parse are dropped and reported
+    /// as errors.
+    fn visit_annotations<R: CheckSyntax, K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        location: &Location,
+        annotations: impl IntoIterator<Item = (K, V)>,
+        inherit_from: Option<&VisitContext<'_>>,
+        visit: impl FnMut(&mut RustVisitor, &AnnotationArgs) -> Option<R>,
+    ) -> Vec<R> {
+        self.visit_annotations_with_priority(location, annotations, inherit_from, visit)
+            .into_iter()
+            .map(|(_, contribution)| contribution)
+            .collect()
+    }
+
+    /// Like [`RustWasm::visit_annotations`], but with each contribution's
+    /// priority, see [`visit_annotations_with_priority`].
+    fn visit_annotations_with_priority<R: CheckSyntax, K: AsRef<str>, V: AsRef<str>>(
+        &mut self,
+        location: &Location,
+        annotations: impl IntoIterator<Item = (K, V)>,
+        inherit_from: Option<&VisitContext<'_>>,
+        mut visit: impl FnMut(&mut RustVisitor, &AnnotationArgs) -> Option<R>,
+    ) -> Vec<(i32, R)> {
+        let contributions = visit_annotations_with_priority(
+            &mut self.visitor_map,
+            &mut self.diagnostics,
+            location,
+            annotations,
+            inherit_from,
+            |visitor, args| {
+                let contribution = visit(visitor, args)?;
+                Some(match contribution.check_syntax() {
+                    Ok(()) => Ok(contribution),
+                    Err(e) => Err((visitor.target().to_string(), args.as_str().to_string(), e)),
+                })
+            },
+        );
+        let mut valid = Vec::new();
+        for (priority, contribution) in contributions {
+            let (target, value, e) = match contribution {
+                Ok(contribution) => {
+                    valid.push((priority, contribution));
+                    continue;
+                }
+                Err(malformed) => malformed,
+            };
+            let annotation = if value.is_empty() {
+                format!("#{target}")
+            } else {
+                format!("#{target}({value})")
+            };
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                location: location.clone(),
+                target: Some(target.clone()),
+                message: format!(
+                    "visitor for '#{target}' produced malformed Rust for `{annotation}`: {e}"
+                ),
+            });
+        }
+        valid
+    }
+
     fn emit_modules(&mut self, modules: Vec<(String, Vec<String>)>) {
         #[derive(Default)]
         struct Module {
@@ -800,10 +956,18 @@ impl As{upcase} for {to_convert} {{
     /// This will generate a macro which will then itself invoke all the
     /// other macros collected in `self.export_macros` prior. All these macros
     /// are woven together in this single invocation.
-    fn finish_export_macro(&mut self, resolve: &Resolve, world_id: WorldId) {
+    ///
+    /// The `attributes` contributed by visitors of the world are placed on
+    /// the macro definition and on its re-export.
+    fn finish_export_macro(&mut self, resolve: &Resolve, world_id: WorldId, attributes: &[String]) {
         if self.export_macros.is_empty() {
             return;
         }
+        let mut attrs = String::new();
+        for attr in attributes {
+            attrs.push_str(attr);
+            attrs.push_str("\n");
+        }
         let world = &resolve.worlds[world_id];
         let world_name = world.name.to_snake_case();
 
@@ -842,6 +1006,7 @@ impl As{upcase} for {to_convert} {{
 ///
 /// {export_macro_name}!(MyType);
 /// ```
+{attrs}
 #[allow(unused_macros)]
 #[doc(hidden)]
 {macro_export}
@@ -869,12 +1034,12 @@ macro_rules! __export_{world_name}_impl {{
 
         uwriteln!(
             self.src,
-            "#[doc(inline)]\n\
+            "{attrs}#[doc(inline)]\n\
             {use_vis} use __export_{world_name}_impl as {export_macro_name};"
         );
 
         if self.opts.stubs {
-            uwriteln!(self.src, "export!(Stub);");
+            uwriteln!(self.src, "{attrs}export!(Stub);");
         }
     }
 
@@ -1000,6 +1165,10 @@ macro_rules! __export_{world_name}_impl {{
 }
 
 impl WorldGenerator for RustWasm {
+    fn check_annotations(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        check_world_annotations(&self.visitor_map, &mut self.diagnostics, resolve, world)
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         wit_bindgen_core::generated_preamble(&mut self.src_preamble, env!("CARGO_PKG_VERSION"));
 
@@ -1301,13 +1470,34 @@ impl WorldGenerator for RustWasm {
     fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()> {
         let name = &resolve.worlds[world].name;
 
+        // Collect visitor contributions for the world itself. These are the
+        // world-scoped equivalent of the contributions applied to interface
+        // modules in `finish_append_submodule`.
+        let mut world_contribution = RustModuleContribution::new();
+        let location = Location::for_world(resolve, world);
+        let cx = VisitContext::new(resolve, world);
+        for contrib in self.visit_annotations(
+            &location,
+            resolve.worlds[world].annotations.iter(),
+            None,
+            |visitor, args| visitor.visit_world(args, &resolve.worlds[world], &cx),
+        ) {
+            world_contribution
+                .use_statements
+                .extend(contrib.use_statements);
+            world_contribution
+                .additional_code
+                .extend(contrib.additional_code);
+            world_contribution.attributes.extend(contrib.attributes);
+        }
+
         let imports = mem::take(&mut self.import_modules);
         self.emit_modules(imports);
         let exports = mem::take(&mut self.export_modules);
         self.emit_modules(exports);
 
         self.finish_runtime_module();
-        self.finish_export_macro(resolve, world);
+        self.finish_export_macro(resolve, world, &world_contribution.attributes);
 
         // This is a bit tricky, but we sometimes want to "split" the `world` in
         // two and only encode the imports here.
@@ -1374,7 +1564,26 @@ impl WorldGenerator for RustWasm {
             self.src.push_str("\n#[derive(Debug)]\npub struct Stub;\n");
         }
 
+        // Every annotation has been visited by now. In strict mode problems
+        // with them are errors which fail generation; the details are
+        // available through `take_diagnostics`.
+        self.diagnostics.bail_on_errors()?;
+
         let mut src = mem::take(&mut self.src);
+
+        // Place world-level use statements and code at the top of the
+        // bindings, ahead of all generated modules.
+        let mut contributions = String::new();
+        for use_stmt in &world_contribution.use_statements {
+            contributions.push_str(&format!("{}\n", use_stmt));
+        }
+        for code in &world_contribution.additional_code {
+            contributions.push_str(&format!("{}\n", code));
+        }
+        if !contributions.is_empty() {
+            *src.as_mut_string() = format!("{}\n{}", contributions, src.as_str());
+        }
+
         if self.opts.format {
             let syntax_tree = syn::parse_file(src.as_str()).unwrap();
             *src.as_mut_string() = prettyplease::unparse(&syntax_tree);
@@ -1411,6 +1620,10 @@ impl WorldGenerator for RustWasm {
 
         Ok(())
     }
+
+    fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
 }
 
 fn compute_module_path(name: &WorldKey, resolve: &Resolve, is_export: bool) -> Vec<String> {
@@ -1550,6 +1763,9 @@ struct FnSig {
     generics: Option<String>,
     self_arg: Option<String>,
     self_is_first_param: bool,
+    /// Visitor-contributed attributes for each parameter, indexed like
+    /// `Function::params`.
+    param_attributes: Vec<Vec<String>>,
 }
 
 impl FnSig {
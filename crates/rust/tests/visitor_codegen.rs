@@ -0,0 +1,1968 @@
+// Runs the Rust generator with visitors registered and checks that their
+// contributions show up in the generated bindings.
+
+use wit_bindgen_core::visitor::priority;
+use wit_bindgen_core::wit_parser::*;
+use wit_bindgen_core::{
+    dealias, AnnotationArgs, Diagnostic, Files, Severity, VisitContext, Visitor,
+};
+use wit_bindgen_rust::annotation_visitor::*;
+use wit_bindgen_rust::{Opts, Ownership, RustVisitor, WithOption};
+
+/// Generates bindings for the only world in `wit` and returns the source with
+/// all whitespace runs collapsed to a single space.
+fn generate(wit: &str, visitors: Vec<Box<RustVisitor>>) -> String {
+    generate_with(
+        wit,
+        Opts {
+            visitors,
+            ..Default::default()
+        },
+    )
+}
+
+/// Like `generate`, but with all of the generator's options given.
+fn generate_with(wit: &str, opts: Opts) -> String {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let (_, src) = files.iter().next().unwrap();
+    std::str::from_utf8(src)
+        .unwrap()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Adds each comma-separated annotation argument as a derive.
+struct DeriveVisitor;
+
+impl Visitor for DeriveVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "derive"
+    }
+
+    fn visit_flags(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _flags: &Flags,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        for derive in annotation.split(',').map(|s| s.trim()) {
+            if !derive.is_empty() {
+                contrib.add_derive(derive);
+            }
+        }
+        Some(contrib)
+    }
+}
+
+/// Marks flags as `#[deprecated]`.
+struct DeprecatedVisitor;
+
+impl Visitor for DeprecatedVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn visit_flag(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _flag: &Flag,
+        _flag_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_attribute("#[deprecated]");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_flags_visitors() {
+    let src = generate(
+        r#"
+        package test:flags;
+
+        interface perms {
+          #derive(Debug, Default)
+          flags permissions {
+            read,
+            #deprecated
+            write,
+          }
+
+          check: func(p: permissions);
+        }
+
+        world test {
+          import perms;
+        }
+        "#,
+        vec![Box::new(DeriveVisitor), Box::new(DeprecatedVisitor)],
+    );
+
+    // `Debug` is already derived so only `Default` is appended.
+    assert!(src.contains(
+        "#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy, Default)] pub struct Permissions: u8"
+    ));
+    assert!(src.contains("const READ = 1 << 0;"));
+    assert!(src.contains("#[deprecated] const WRITE = 1 << 1;"));
+}
+
+/// Marks variant cases as `#[deprecated]`.
+struct CaseDeprecatedVisitor;
+
+impl Visitor for CaseDeprecatedVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "deprecated"
+    }
+
+    fn visit_variant_case(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _case: &Case,
+        _case_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustVariantCaseContribution> {
+        let mut contrib = RustVariantCaseContribution::new();
+        contrib.add_attribute("#[deprecated]");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_variant_case_visitors() {
+    let src = generate(
+        r#"
+        package test:cases;
+
+        interface shapes {
+          variant shape {
+            circle(f64),
+            #deprecated
+            square(f64),
+          }
+
+          area: func(s: shape) -> f64;
+        }
+
+        world test {
+          import shapes;
+        }
+        "#,
+        vec![Box::new(CaseDeprecatedVisitor)],
+    );
+
+    // The annotation is read from the case rather than from the variant, and
+    // each case is emitted once.
+    assert!(src.contains("pub enum Shape { Circle(f64), #[deprecated] Square(f64), }"));
+}
+
+/// Tags resources with a doc alias and reports when handles are dropped.
+struct LeakTraceVisitor;
+
+impl Visitor for LeakTraceVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "leak_trace"
+    }
+
+    fn visit_resource(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _resource_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_attribute("#[doc(alias = \"traced\")]");
+        contrib.add_resource_drop("crate::handle_dropped(_handle);");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_resource_visitors() {
+    let src = generate(
+        r#"
+        package test:resources;
+
+        interface handles {
+          #leak_trace
+          resource blob {
+            constructor();
+          }
+        }
+
+        world test {
+          import handles;
+          export handles;
+        }
+        "#,
+        vec![Box::new(LeakTraceVisitor)],
+    );
+
+    // Both the imported and the exported handle structs are annotated.
+    assert_eq!(
+        src.matches("#[doc(alias = \"traced\")] #[derive(Debug)] #[repr(transparent)] pub struct Blob {")
+            .count(),
+        2
+    );
+    assert!(src.contains("#[doc(alias = \"traced\")] pub trait GuestBlob: 'static {"));
+    assert!(src.contains("unsafe fn drop(_handle: u32) { crate::handle_dropped(_handle);"));
+}
+
+#[test]
+fn test_resource_derives() {
+    let wit = r#"
+        package test:resources;
+
+        interface handles {
+          #derive(Clone, Copy)
+          resource blob;
+        }
+
+        world test {
+          import handles;
+        }
+    "#;
+
+    // Copying a handle would drop its resource twice, so `Clone` and `Copy`
+    // are dropped with a warning.
+    let src = generate(wit, vec![builtin_visitor("derive").unwrap()]);
+    assert!(src.contains("#[derive(Debug)] #[repr(transparent)] pub struct Blob {"));
+    let (ok, diagnostics) =
+        generate_diagnostics(wit, vec![builtin_visitor("derive").unwrap()], false);
+    assert!(ok);
+    let messages = diagnostics
+        .iter()
+        .map(|d| format!("{}: {}", d.location, d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "test:resources/handles.blob: `Clone` can't be derived for resource `blob`, ignoring it",
+            "test:resources/handles.blob: `Copy` can't be derived for resource `blob`, ignoring it",
+        ]
+    );
+}
+
+/// Gates a world behind a cfg and adds a marker constant to the bindings.
+struct WorldCfgVisitor;
+
+impl Visitor for WorldCfgVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "cfg"
+    }
+
+    fn visit_world(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _world: &World,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustModuleContribution> {
+        let mut contrib = RustModuleContribution::new();
+        contrib.add_attribute(format!("#[cfg({annotation})]"));
+        contrib.add_use("use core::fmt as _;");
+        contrib.add_code("pub const WORLD_GATED: bool = true;");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_world_visitors() {
+    let src = generate(
+        r#"
+        package test:worlds;
+
+        interface api {
+          run: func();
+        }
+
+        #cfg(feature = "test-world")
+        world test {
+          export api;
+        }
+        "#,
+        vec![Box::new(WorldCfgVisitor)],
+    );
+
+    assert!(src.contains(
+        "#[cfg(feature = \"test-world\")] #[allow(unused_macros)] #[doc(hidden)] macro_rules! __export_test_impl"
+    ));
+    assert!(src.contains(
+        "#[cfg(feature = \"test-world\")] #[doc(inline)] pub(crate) use __export_test_impl as export;"
+    ));
+
+    // World-level items are placed ahead of the generated modules.
+    let use_pos = src.find("use core::fmt as _;").unwrap();
+    let code_pos = src.find("pub const WORLD_GATED: bool = true;").unwrap();
+    let exports_pos = src.find("pub mod exports").unwrap();
+    assert!(use_pos < code_pos && code_pos < exports_pos);
+}
+
+/// Traces calls to annotated functions.
+struct TraceVisitor;
+
+impl Visitor for TraceVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "trace"
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_attribute("#[doc(alias = \"traced\")]");
+        contrib.add_body_prefix(format!("crate::trace(\"{}\");", func.name));
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_world_function_import_visitors() {
+    let src = generate(
+        r#"
+        package test:world-imports;
+
+        world test {
+          #trace
+          import ping: func() -> u32;
+        }
+        "#,
+        vec![Box::new(TraceVisitor)],
+    );
+
+    assert!(src.contains("#[doc(alias = \"traced\")] #[allow(unused_unsafe, clippy::all)]"));
+    assert!(src.contains("pub fn ping() -> u32"));
+    assert!(src.contains("crate::trace(\"ping\");"));
+}
+
+#[test]
+fn test_world_function_export_visitors() {
+    let src = generate(
+        r#"
+        package test:world-exports;
+
+        world test {
+          #trace
+          export run: func(n: u32);
+        }
+        "#,
+        vec![Box::new(TraceVisitor)],
+    );
+
+    assert!(src.contains("#[doc(alias = \"traced\")] #[allow(async_fn_in_trait)] fn run(n: u32,);"));
+    assert!(src.contains("crate::trace(\"run\");"));
+}
+
+#[test]
+fn test_export_function_attributes() {
+    let src = generate(
+        r#"
+        package test:exports;
+
+        interface api {
+          #inline
+          #deprecated
+          #cfg(feature = "run")
+          run: func(n: u32);
+        }
+
+        world test {
+          export api;
+        }
+        "#,
+        vec![
+            builtin_visitor("inline").unwrap(),
+            builtin_visitor("deprecated").unwrap(),
+            builtin_visitor("cfg").unwrap(),
+        ],
+    );
+
+    // `#[inline]` is ignored on a method without a body, so the trait method
+    // doesn't get it.
+    assert!(
+        src.contains(
+            "#[cfg(feature = \"run\")] #[deprecated] #[allow(async_fn_in_trait)] fn run(n: u32,);"
+        ),
+        "{src}"
+    );
+    // The glue calling the method goes away along with it, including the
+    // export in the `export!` macro.
+    assert!(
+        src.contains(
+            "#[cfg(feature = \"run\")] #[doc(hidden)] \
+             #[allow(non_snake_case, unused_unsafe, deprecated)] \
+             pub unsafe fn _export_run_cabi<T: Guest>"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "#[cfg(feature = \"run\")] #[unsafe(export_name = \"test:exports/api#run\")] \
+             unsafe extern \"C\" fn export_run"
+        ),
+        "{src}"
+    );
+}
+
+/// Turns aliases into newtypes deriving the comma-separated annotation
+/// arguments, and documents other named typedefs.
+struct NewtypeVisitor;
+
+impl NewtypeVisitor {
+    fn contribution(annotation: &str) -> RustTypeContribution {
+        let mut contrib = RustTypeContribution::new();
+        for derive in annotation.split(',').map(|s| s.trim()) {
+            if !derive.is_empty() {
+                contrib.add_derive(derive);
+            }
+        }
+        contrib.set_newtype(true);
+        contrib
+    }
+}
+
+impl Visitor for NewtypeVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "newtype"
+    }
+
+    fn visit_alias(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _ty: &Type,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Some(Self::contribution(annotation))
+    }
+
+    fn visit_tuple(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _tuple: &Tuple,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_attribute("#[doc(alias = \"pair\")]");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_alias_visitors() {
+    let src = generate(
+        r#"
+        package test:aliases;
+
+        interface ids {
+          #newtype(PartialEq, Eq, Hash)
+          type user-id = u64;
+
+          #newtype
+          type user-name = string;
+
+          #newtype
+          type point = tuple<u32, u32>;
+
+          lookup: func(id: user-id, name: user-name) -> user-id;
+          locate: func(at: point);
+        }
+
+        world test {
+          import ids;
+          export ids;
+        }
+        "#,
+        vec![Box::new(NewtypeVisitor)],
+    );
+
+    assert!(src.contains(
+        "#[repr(transparent)] #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)] pub struct UserId(pub u64);"
+    ));
+    assert!(src.contains("impl From<u64> for UserId {"));
+    assert!(src.contains("impl From<UserId> for u64 {"));
+
+    // Imports unwrap the newtype when lowering; exports wrap it when lifting.
+    assert!(src.contains("_rt::as_i64((id).0)"));
+    assert!(src.contains("UserId(arg0 as u64)"));
+
+    // Only scalar aliases become newtypes.
+    assert!(src.contains("pub type UserName ="));
+    assert!(!src.contains("pub struct UserName"));
+    assert!(src.contains("#[doc(alias = \"pair\")] pub type Point = (u32,u32,);"));
+}
+
+#[test]
+fn test_non_scalar_newtype_warning() {
+    let wit = r#"
+        package test:aliases;
+
+        interface ids {
+          #newtype(Hash)
+          type user-name = string;
+
+          greet: func(name: user-name);
+        }
+
+        world test {
+          import ids;
+        }
+    "#;
+
+    let (ok, diagnostics) = generate_diagnostics(wit, vec![Box::new(NewtypeVisitor)], false);
+    assert!(ok);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "warning: test:aliases/ids.user-name: `user-name` doesn't alias a scalar type so it \
+         can't become a newtype"
+    );
+}
+
+/// Renames fields using the structured `name = "..."` argument.
+struct RenameVisitor;
+
+impl Visitor for RenameVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "rename"
+    }
+
+    fn visit_field(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _field: &Field,
+        _field_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let name = annotation.named("name")?;
+        let mut contrib = RustFieldContribution::new();
+        contrib.add_attribute(format!("#[doc(alias = {name})]"));
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_structured_annotation_args() {
+    let src = generate(
+        r#"
+        package test:args;
+
+        interface records {
+          record message {
+            #rename(name = "msg", skip = false)
+            body: string,
+            #rename(Debug)
+            sender: u32,
+          }
+
+          send: func(m: message);
+        }
+
+        world test {
+          import records;
+        }
+        "#,
+        vec![Box::new(RenameVisitor)],
+    );
+
+    assert!(src.contains("#[doc(alias = \"msg\")] pub body:"));
+    // No `name` argument, so the visitor contributes nothing.
+    assert_eq!(src.matches("#[doc(alias =").count(), 1);
+}
+
+/// Runs the generator and returns whether it succeeded along with the
+/// diagnostics it reported.
+fn generate_diagnostics(
+    wit: &str,
+    visitors: Vec<Box<RustVisitor>>,
+    strict: bool,
+) -> (bool, Vec<Diagnostic>) {
+    let mut resolve = Resolve::default();
+    let pkg = resolve.push_str("test.wit", wit).unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        visitors,
+        strict_annotations: strict,
+        ..Default::default()
+    };
+    let mut generator = opts.build();
+    let result = generator.generate(&resolve, world, &mut Files::default());
+    (result.is_ok(), generator.take_diagnostics())
+}
+
+#[test]
+fn test_annotation_diagnostics() {
+    let wit = r#"
+        package test:diag;
+
+        interface types {
+          #unknown(x)
+          flags permissions { read, write }
+
+          #derive(Hash)
+          record point { x: u32 }
+
+          check: func(p: permissions, pt: point);
+        }
+
+        world test {
+          import types;
+        }
+    "#;
+
+    let (ok, diagnostics) = generate_diagnostics(wit, vec![Box::new(DeriveVisitor)], false);
+    assert!(ok);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].target.as_deref(), Some("unknown"));
+    assert_eq!(
+        diagnostics[0].location.to_string(),
+        "test:diag/types.permissions"
+    );
+
+    // Strict mode also reports annotations a visitor declined.
+    let (ok, diagnostics) = generate_diagnostics(wit, vec![Box::new(DeriveVisitor)], true);
+    assert!(!ok);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert_eq!(diagnostics[1].target.as_deref(), Some("derive"));
+    assert_eq!(diagnostics[1].location.to_string(), "test:diag/types.point");
+}
+
+#[test]
+fn test_annotation_checks() {
+    let wit = r#"
+        package test:checks;
+
+        interface api {
+          record user {
+            #range(1, 10)
+            name: string,
+            #max_length("long")
+            email: string,
+            #max_length(64)
+            nickname: string,
+            #range(1.5, 10)
+            level: u8,
+          }
+
+          #repr(u8)
+          ping: func();
+
+          #trace
+          #derive(Debug)
+          pong: func();
+        }
+
+        world test {
+          import api;
+        }
+    "#;
+    let visitors = ["range", "max_length", "repr", "trace", "derive"]
+        .iter()
+        .map(|name| builtin_visitor(name).unwrap())
+        .collect();
+
+    // Annotations their visitors declare they don't accept fail generation
+    // up front, even outside of strict mode.
+    let (ok, diagnostics) = generate_diagnostics(wit, visitors, false);
+    assert!(!ok);
+    let messages = diagnostics
+        .iter()
+        .map(|d| format!("{}: {}", d.location, d.message))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [
+            "test:checks/api.user.name: annotation '#range' is not allowed on this field, \
+             which holds string values; it applies to: integer, float",
+            "test:checks/api.user.email: invalid arguments for annotation '#max_length': \
+             argument 1 must be an integer, found a string `\"long\"`",
+            "test:checks/api.user.level: invalid arguments for annotation '#range': \
+             argument 1 must be an integer, found a float `1.5`",
+            "test:checks/api.ping: annotation '#repr' is not allowed on this function; \
+             it applies to: record, variant, enum",
+            "test:checks/api.pong: annotation '#derive' is not allowed on this function; \
+             it applies to: record, variant, enum, flags, resource, alias, interface, world",
+        ]
+    );
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+}
+
+#[test]
+fn test_builtin_visitors() {
+    let visitors = BUILTIN_ANNOTATIONS
+        .iter()
+        .map(|name| builtin_visitor(name).unwrap())
+        .collect();
+    // The record and the enum aren't used by any function.
+    let src = generate_with(
+        include_str!("annotations-basic.wit"),
+        Opts {
+            generate_unused_types: true,
+            visitors,
+            ..Default::default()
+        },
+    );
+
+    // `#derive` on a scalar alias makes it a newtype.
+    assert!(src.contains("#[derive(Clone, Copy, Debug, Hash)]"));
+    assert!(src.contains("pub struct Counter("));
+
+    assert!(src.contains("serde::Deserialize"));
+    assert!(src.contains("#[serde(rename_all = \"camelCase\")]"));
+    assert!(src.contains("#[serde(rename = \"msg\")] pub message:"));
+
+    assert!(src.contains("#[inline]"));
+    assert!(src.contains("#[must_use]"));
+    assert!(src.contains("#[cfg(feature = \"status-check\")]"));
+    assert!(src.contains("#[cfg(feature = \"test-world\")]"));
+    assert!(src.contains("assert!(divisor != 0, \"divisor cannot be zero\");"));
+    assert!(src.contains("assert!(amount > 0, \"amount must be greater than zero\");"));
+}
+
+#[test]
+fn test_enum_attributes() {
+    let src = generate(
+        r#"
+        package test:enums;
+
+        interface colors {
+          #deprecated("use hue")
+          #cfg(feature = "legacy")
+          enum color {
+            red,
+            green,
+          }
+
+          paint: func(c: color);
+        }
+
+        world test {
+          import colors;
+        }
+        "#,
+        vec![
+            builtin_visitor("deprecated").unwrap(),
+            builtin_visitor("cfg").unwrap(),
+        ],
+    );
+
+    // The `#[cfg]` goes first, whatever the order of the annotations.
+    assert!(
+        src.contains(
+            "#[cfg(feature = \"legacy\")] #[deprecated(note = \"use hue\")] #[repr(u8)] #[derive("
+        ),
+        "{src}"
+    );
+}
+
+#[test]
+fn test_type_attribute_order() {
+    let src = generate(
+        r#"
+        package test:order;
+
+        interface shapes {
+          #serde(rename_all = "camelCase")
+          #deprecated
+          #cfg(feature = "shapes")
+          record point {
+            x: u32,
+            y: u32,
+          }
+
+          #serde(tag = "kind")
+          #cfg(feature = "shapes")
+          variant shape {
+            dot(point),
+            circle(u32),
+          }
+
+          draw: func(s: shape);
+        }
+
+        world test {
+          import shapes;
+        }
+        "#,
+        vec![
+            builtin_visitor("serde").unwrap(),
+            builtin_visitor("deprecated").unwrap(),
+            builtin_visitor("cfg").unwrap(),
+        ],
+    );
+
+    // `#[cfg]` goes first, before the `#[repr(C)]` of `Copy` records too, and
+    // derive helpers like `#[serde]` go after the `#[derive]` declaring them.
+    let attrs = attributes_of(&src, "pub struct Point");
+    let position = |attr: &str| attrs.iter().position(|a| a.starts_with(attr)).expect(&src);
+    assert_eq!(position("#[cfg("), 0, "{src}");
+    assert!(position("#[deprecated]") < position("#[repr(C)]"), "{src}");
+    assert!(position("#[repr(C)]") < position("#[derive("), "{src}");
+    assert_eq!(position("#[serde("), attrs.len() - 1, "{src}");
+
+    let attrs = attributes_of(&src, "pub enum Shape");
+    let position = |attr: &str| attrs.iter().position(|a| a.starts_with(attr)).expect(&src);
+    assert_eq!(position("#[cfg("), 0, "{src}");
+    assert!(position("#[derive(") < position("#[serde("), "{src}");
+}
+
+/// Returns the attributes right before the first `item` in `src`, as
+/// returned by `generate`, in order.
+fn attributes_of<'a>(src: &'a str, item: &str) -> Vec<&'a str> {
+    let mut rest = &src[..src.find(item).expect(src)];
+    let mut attrs = vec![];
+    while let Some(prefix) = rest.strip_suffix("] ") {
+        let start = prefix.rfind("#[").expect(src);
+        attrs.push(&rest[start..=prefix.len()]);
+        rest = &rest[..start];
+    }
+    attrs.reverse();
+    attrs
+}
+
+#[test]
+fn test_unknown_builtin_visitor() {
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push_str("test.wit", "package test:opts; world test {}")
+        .unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        annotations: vec!["derive".to_string(), "frobnicate".to_string()],
+        ..Default::default()
+    };
+
+    // A bad entry fails generation with an error rather than a panic.
+    let mut generator = opts.build();
+    let result = generator.generate(&resolve, world, &mut Files::default());
+    assert!(result.is_err());
+    let diagnostics = generator.take_diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+    assert!(diagnostics[0]
+        .to_string()
+        .starts_with("error: unknown built-in annotation visitor `frobnicate`; expected one of:"));
+}
+
+const TEMPLATES: &str = r##"
+[serde.record]
+derives = ["serde::Serialize", "serde::Deserialize"]
+attributes = ["#[serde({args})]", "#[doc(alias = \"{name}\")]"]
+
+[serde.field]
+attributes = ["#[serde({args})]"]
+
+[trace.function]
+body-prefix = ["log::{args.level}!(\"{name}: {:?}\", ({params}));"]
+
+[trace.interface]
+uses = ["use log;"]
+"##;
+
+#[test]
+fn test_template_visitors() {
+    let visitors = TemplateVisitor::parse(TEMPLATES)
+        .unwrap()
+        .into_iter()
+        .map(|v| Box::new(v) as Box<RustVisitor>)
+        .collect();
+    let src = generate(
+        r#"
+        package test:templates;
+
+        #trace
+        interface api {
+          #serde(rename_all = "camelCase")
+          record user-info {
+            #serde(rename = "id")
+            user-id: u64,
+          }
+
+          #trace(level = "debug")
+          lookup: func(user-id: u64, include-deleted: bool) -> user-info;
+
+          // No `level` argument, so this function is left alone.
+          #trace
+          ping: func();
+        }
+
+        world test {
+          import api;
+        }
+        "#,
+        visitors,
+    );
+
+    assert!(src.contains("serde::Serialize"));
+    assert!(src.contains("#[serde(rename_all = \"camelCase\")] #[doc(alias = \"user-info\")]"));
+    assert!(src.contains("#[serde(rename = \"id\")] pub user_id: u64"));
+    assert!(src.contains("log::debug!(\"lookup: {:?}\", (user_id, include_deleted));"));
+    assert_eq!(src.matches("log::").count(), 1);
+    assert!(src.contains("use log;"));
+}
+
+#[test]
+fn test_template_config_errors() {
+    let err = TemplateVisitor::parse("[serde.struct]\nattributes = []").unwrap_err();
+    assert!(format!("{err:#}").contains("not a known item kind"));
+
+    let err = TemplateVisitor::parse("[serde.field]\nderives = [\"Hash\"]").unwrap_err();
+    assert!(format!("{err:#}").contains("does not apply"));
+
+    let err = TemplateVisitor::parse("[trace]\npriority = \"high\"").unwrap_err();
+    assert!(format!("{err:#}").contains("must be an integer"));
+
+    let err =
+        TemplateVisitor::parse("[show.field]\nitems = [\"impl X for {type} {}\"]").unwrap_err();
+    assert!(format!("{err:#}").contains("does not apply"));
+
+    let err =
+        TemplateVisitor::parse("[show.list]\nitems = [\"impl X for {type} {}\"]").unwrap_err();
+    assert!(format!("{err:#}").contains("does not apply"));
+
+    let err = TemplateVisitor::parse("[trace]\ninherit = 1").unwrap_err();
+    assert!(format!("{err:#}").contains("must be a boolean"));
+}
+
+#[test]
+fn test_inherited_annotations() {
+    let mut visitors = TemplateVisitor::parse(
+        r#"
+        [trace]
+        inherit = true
+
+        [trace.function]
+        body-prefix = ["log::trace!(\"{name}\");"]
+        "#,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|v| Box::new(v) as Box<RustVisitor>)
+    .collect::<Vec<_>>();
+    visitors.push(builtin_visitor("derive").unwrap());
+    let src = generate(
+        r#"
+        package test:inherit;
+
+        #derive(Hash)
+        #trace
+        interface api {
+          record point {
+            x: u32,
+            y: u32,
+          }
+
+          // The type's own annotation replaces the interface's.
+          #derive(Default)
+          record size {
+            w: u32,
+            h: u32,
+          }
+
+          #no_inherit
+          record raw {
+            bits: u32,
+          }
+
+          // Aliases only take their own `#derive`.
+          type counter = u32;
+
+          // Types holding resource handles don't inherit derives.
+          resource blob;
+          record entry {
+            name: string,
+            data: blob,
+          }
+
+          area: func(s: size) -> u32;
+          mark: func(p: point, r: raw, c: counter, e: entry);
+
+          #no_inherit(trace)
+          now: func() -> u64;
+        }
+
+        world test {
+          import api;
+        }
+        "#,
+        visitors,
+    );
+
+    assert!(src.contains("#[derive(Clone, Copy, Hash)] pub struct Point {"));
+    assert!(src.contains("#[derive(Clone, Copy, Default)] pub struct Size {"));
+    assert!(src.contains("#[derive(Clone, Copy)] pub struct Raw {"));
+    assert!(src.contains("pub type Counter = u32;"));
+    let entry = src.find("pub struct Entry {").expect(&src);
+    assert!(!src[..entry].ends_with("#[derive(Hash)] "), "{src}");
+    assert_eq!(src.matches("Hash)]").count(), 1, "{src}");
+    assert!(src.contains("log::trace!(\"area\");"));
+    assert!(!src.contains("log::trace!(\"now\");"));
+}
+
+/// Contributes code with a typo in it.
+struct TypoVisitor;
+
+impl Visitor for TypoVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "typo"
+    }
+
+    fn visit_record(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_attribute(format!("#[doc = {annotation}"));
+        Some(contrib)
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_body_prefix("let x = ;");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_malformed_contributions() {
+    let (ok, diagnostics) = generate_diagnostics(
+        r#"
+        package test:typo;
+
+        interface api {
+          #typo("point")
+          record point { x: u32 }
+
+          #typo
+          run: func(p: point);
+        }
+
+        world test {
+          import api;
+        }
+        "#,
+        vec![Box::new(TypoVisitor)],
+        false,
+    );
+
+    // Malformed code is always an error, even outside of strict mode.
+    assert!(!ok);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert_eq!(diagnostics[0].location.to_string(), "test:typo/api.point");
+    assert!(diagnostics[0]
+        .message
+        .contains("produced malformed Rust for `#typo(\"point\")`"));
+    assert!(diagnostics[0].message.contains("invalid attribute `#[doc = \"point\"`"));
+    assert_eq!(diagnostics[1].location.to_string(), "test:typo/api.run");
+    assert!(diagnostics[1].message.contains("invalid statements `let x = ;`"));
+}
+
+/// Checks that a parameter is within the annotation's bounds.
+struct RangeVisitor;
+
+impl Visitor for RangeVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "range"
+    }
+
+    fn visit_param(
+        &mut self,
+        annotation: &AnnotationArgs,
+        func: &Function,
+        param_index: usize,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustParamContribution> {
+        let mut bounds = annotation.positional();
+        let min = bounds.next()?.as_integer()?;
+        let max = bounds.next()?.as_integer()?;
+        let name = &func.params[param_index].0;
+        let mut contrib = RustParamContribution::new();
+        contrib.add_attribute("#[allow(unused_variables)]");
+        contrib.add_code(format!("assert!(({min}..={max}).contains(&{name}));"));
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_param_visitors() {
+    let src = generate(
+        r#"
+        package test:params;
+
+        interface users {
+          #range(age, 18, 120)
+          #trace(age)
+          register: func(name: string, age: u8);
+        }
+
+        world test {
+          import users;
+          export users;
+        }
+        "#,
+        vec![Box::new(RangeVisitor), Box::new(TraceVisitor)],
+    );
+
+    // Once in the import wrapper and once in the export glue.
+    let check = "assert!((18..=120).contains(&age));";
+    assert_eq!(src.matches(check).count(), 2, "{src}");
+    // The export glue checks the lifted argument, before calling the trait.
+    let export = src.rfind(check).unwrap();
+    assert!(src[..export].rfind("let age = ").is_some(), "{src}");
+    assert!(src[export..].contains("T::register(name, age)"), "{src}");
+    // Once on the import wrapper and once on the trait method.
+    assert_eq!(
+        src.matches("#[allow(unused_variables)] age: u8,").count(),
+        2,
+        "{src}"
+    );
+    // Visitors without `visit_param` see the annotation as a function
+    // annotation.
+    assert!(src.contains("crate::trace(\"register\");"), "{src}");
+}
+
+/// Maps the error of a `result` returned by an export with the annotation's
+/// expression.
+struct MapErrVisitor;
+
+impl Visitor for MapErrVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "map_err"
+    }
+
+    fn visit_function(
+        &mut self,
+        annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_result_transform(format!("func_return.map_err({annotation})"));
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_result_transforms() {
+    let src = generate(
+        r#"
+        package test:results;
+
+        interface api {
+          #map_err(|e| e.to_lowercase())
+          parse: func(s: string) -> result<u32, string>;
+          #map_err(|e| e.to_lowercase())
+          fetch: async func(url: string) -> result<u32, string>;
+          #map_err(|e| e.to_lowercase())
+          reset: func();
+        }
+
+        world test {
+          export api;
+        }
+        "#,
+        vec![Box::new(MapErrVisitor)],
+    );
+
+    // The transform replaces the trait method's return value before it's
+    // lowered, for sync and async exports alike.
+    assert!(
+        src.contains(
+            "let func_return = T::parse(s); let func_return = func_return.map_err(|e| e.to_lowercase()); func_return };"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "let func_return = T::fetch(url).await; let func_return = func_return.map_err(|e| e.to_lowercase()); func_return };"
+        ),
+        "{src}"
+    );
+    // Functions without a result have nothing to transform.
+    assert_eq!(
+        src.matches(".map_err(|e| e.to_lowercase())").count(),
+        2,
+        "{src}"
+    );
+}
+
+/// Adds a statement to the start of a function's body, to check the order of
+/// body prefixes from several visitors.
+struct PrefixVisitor {
+    target: &'static str,
+    statement: &'static str,
+}
+
+impl Visitor for PrefixVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        self.target
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _func: &Function,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_body_prefix(self.statement);
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_visitor_priority() {
+    let src = generate(
+        r#"
+        package test:priority;
+
+        interface api {
+          #trace
+          #assert(amount > 0, "amount must be positive")
+          #inline
+          #cfg(feature = "payments")
+          pay: func(amount: u32);
+        }
+
+        world test {
+          import api;
+        }
+        "#,
+        vec![
+            Box::new(PrefixVisitor {
+                target: "trace",
+                statement: "trace_call();",
+            }),
+            Box::new(PrefixVisitor {
+                target: "trace",
+                statement: "count_call();",
+            }),
+            builtin_visitor("assert").unwrap(),
+            builtin_visitor("inline").unwrap(),
+            builtin_visitor("cfg").unwrap(),
+        ],
+    );
+
+    // `#[cfg]` goes first and validation runs before tracing, whatever the
+    // order of the annotations. Visitors sharing a target are all applied,
+    // in the order they were registered.
+    assert!(
+        src.contains("#[cfg(feature = \"payments\")] #[inline]"),
+        "{src}"
+    );
+    let check = src.find("assert!(amount > 0").expect(&src);
+    let trace = src.find("trace_call();").expect(&src);
+    let count = src.find("count_call();").expect(&src);
+    assert!(check < trace && trace < count, "{src}");
+}
+
+#[test]
+fn test_annotation_priority() {
+    let wit = r#"
+        package test:priority;
+
+        interface api {
+          #trace
+          #assert(amount > 0, "amount must be positive")
+          pay: func(amount: u32);
+        }
+
+        world test {
+          import api;
+        }
+        "#;
+    let visitors = || -> Vec<Box<RustVisitor>> {
+        vec![
+            Box::new(PrefixVisitor {
+                target: "trace",
+                statement: "trace_call();",
+            }),
+            builtin_visitor("assert").unwrap(),
+        ]
+    };
+
+    // Validation runs first by default.
+    let src = generate(wit, visitors());
+    let check = src.find("assert!(amount > 0").expect(&src);
+    let trace = src.find("trace_call();").expect(&src);
+    assert!(check < trace, "{src}");
+
+    // A priority given in the options replaces the visitor's own.
+    let src = generate_with(
+        wit,
+        Opts {
+            visitors: visitors(),
+            annotation_priority: vec![("trace".to_string(), priority::FIRST)],
+            ..Default::default()
+        },
+    );
+    let check = src.find("assert!(amount > 0").expect(&src);
+    let trace = src.find("trace_call();").expect(&src);
+    assert!(trace < check, "{src}");
+}
+
+/// Traces calls to exported functions only, naming them by their path in
+/// the generated bindings.
+struct ExportTraceVisitor;
+
+impl Visitor for ExportTraceVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "trace"
+    }
+
+    fn visit_function(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _func: &Function,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustFunctionContribution> {
+        if !cx.is_export() {
+            return None;
+        }
+        let path = format!("{}::{}", cx.module_path.as_ref()?, cx.name.as_ref()?);
+        let mut contrib = RustFunctionContribution::new();
+        contrib.add_body_prefix(format!("crate::trace(\"{path}\");"));
+        Some(contrib)
+    }
+}
+
+/// Serializes `u64` fields, including those behind aliases, as strings.
+struct SerdeWithVisitor;
+
+impl Visitor for SerdeWithVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "serde"
+    }
+
+    fn visit_field(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        field: &Field,
+        _field_index: usize,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustFieldContribution> {
+        let ty = match field.ty {
+            Type::Id(id) => match cx.resolve.types[dealias(cx.resolve, id)].kind {
+                TypeDefKind::Type(ty) => ty,
+                _ => return None,
+            },
+            ty => ty,
+        };
+        if ty != Type::U64 {
+            return None;
+        }
+        let mut contrib = RustFieldContribution::new();
+        contrib.add_attribute("#[serde(with = \"u64_as_string\")]");
+        Some(contrib)
+    }
+}
+
+#[test]
+fn test_visit_context() {
+    let src = generate(
+        r#"
+        package test:ctx;
+
+        interface api {
+          type timestamp = u64;
+
+          record event {
+            #serde
+            at: timestamp,
+            #serde
+            count: u32,
+          }
+
+          #trace
+          record-event: func(e: event);
+        }
+
+        world test {
+          import api;
+          export api;
+        }
+        "#,
+        vec![Box::new(ExportTraceVisitor), Box::new(SerdeWithVisitor)],
+    );
+
+    // Once in the import module and once in the export module.
+    assert_eq!(
+        src.matches("#[serde(with = \"u64_as_string\")] pub at: Timestamp,")
+            .count(),
+        2,
+        "{src}"
+    );
+    assert!(!src.contains("u64_as_string\")] pub count"), "{src}");
+    assert!(
+        src.contains("crate::trace(\"exports::test::ctx::api::record_event\");"),
+        "{src}"
+    );
+    // The import isn't traced.
+    assert!(!src.contains("crate::trace(\"test::"), "{src}");
+}
+
+/// Adds the name visitors see for a type as a doc alias.
+struct NameAliasVisitor;
+
+impl NameAliasVisitor {
+    fn contribution(cx: &VisitContext<'_>) -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_attribute(format!("#[doc(alias = \"{}\")]", cx.name.as_ref()?));
+        Some(contrib)
+    }
+}
+
+impl Visitor for NameAliasVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "name"
+    }
+
+    fn visit_record(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::contribution(cx)
+    }
+
+    fn visit_list(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _element: &Type,
+        _type_id: TypeId,
+        cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::contribution(cx)
+    }
+}
+
+#[test]
+fn test_type_visit_context_per_mode() {
+    let src = generate_with(
+        r#"
+        package test:ctx;
+
+        interface api {
+          #name
+          record event {
+            name: string,
+          }
+
+          #name
+          type tags = list<string>;
+
+          send: func(e: event, t: tags) -> event;
+          all-tags: func() -> tags;
+        }
+
+        world test {
+          import api;
+        }
+        "#,
+        Opts {
+            ownership: Ownership::Borrowing {
+                duplicate_if_necessary: true,
+            },
+            visitors: vec![Box::new(NameAliasVisitor)],
+            ..Default::default()
+        },
+    );
+
+    // Types generated in two modes see the name of each.
+    for name in ["EventParam", "EventResult", "TagsParam", "TagsResult"] {
+        assert!(
+            src.contains(&format!("#[doc(alias = \"{name}\")]")),
+            "{name}: {src}"
+        );
+    }
+    assert!(!src.contains("#[doc(alias = \"Event\")]"), "{src}");
+}
+
+/// Implements `Display` for types in terms of their `Debug` impl.
+struct DisplayVisitor;
+
+impl DisplayVisitor {
+    fn contribution() -> Option<RustTypeContribution> {
+        let mut contrib = RustTypeContribution::new();
+        contrib.add_item(
+            "impl{generics} ::core::fmt::Display for {type} {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    ::core::fmt::Debug::fmt(self, f)
+                }
+            }",
+        );
+        Some(contrib)
+    }
+}
+
+impl Visitor for DisplayVisitor {
+    type Contributions = RustContributions;
+
+    fn target(&self) -> &str {
+        "display"
+    }
+
+    fn visit_record(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _record: &Record,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::contribution()
+    }
+
+    fn visit_enum(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _enum: &Enum,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::contribution()
+    }
+
+    fn visit_list(
+        &mut self,
+        _annotation: &AnnotationArgs,
+        _element: &Type,
+        _type_id: TypeId,
+        _cx: &VisitContext<'_>,
+    ) -> Option<RustTypeContribution> {
+        Self::contribution()
+    }
+}
+
+#[test]
+fn test_type_items() {
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push_str(
+            "test.wit",
+            r#"
+            package test:items;
+
+            interface api {
+              #display
+              record event {
+                name: string,
+              }
+
+              #display
+              enum level {
+                low,
+                high,
+              }
+
+              send: func(e: event, l: level) -> event;
+            }
+
+            world test {
+              import api;
+            }
+            "#,
+        )
+        .unwrap();
+    let world = resolve.select_world(&[pkg], None).unwrap();
+    let opts = Opts {
+        ownership: Ownership::Borrowing {
+            duplicate_if_necessary: true,
+        },
+        visitors: vec![Box::new(DisplayVisitor)],
+        ..Default::default()
+    };
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+    let (_, src) = files.iter().next().unwrap();
+    let src = std::str::from_utf8(src)
+        .unwrap()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    // The borrowing and owning versions of the record each get the impl,
+    // with their own generics.
+    assert!(
+        src.contains("impl<'a> ::core::fmt::Display for EventParam<'a> {"),
+        "{src}"
+    );
+    assert!(
+        src.contains("impl ::core::fmt::Display for EventResult {"),
+        "{src}"
+    );
+    assert!(
+        src.contains("impl ::core::fmt::Display for Level {"),
+        "{src}"
+    );
+    let def = src.find("pub struct EventParam<'a,>").expect(&src);
+    let item = src.find("for EventParam<'a> {").expect(&src);
+    assert!(def < item, "{src}");
+}
+
+#[test]
+fn test_alias_items_warning() {
+    let wit = r#"
+        package test:items;
+
+        interface api {
+          #display
+          type names = list<string>;
+
+          send: func(n: names);
+        }
+
+        world test {
+          import api;
+        }
+    "#;
+
+    let (ok, diagnostics) = generate_diagnostics(wit, vec![Box::new(DisplayVisitor)], false);
+    assert!(ok);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "warning: test:items/api.names: `names` is a type alias so the items contributed for \
+         it are ignored"
+    );
+}
+
+#[test]
+fn test_validation_visitors() {
+    let visitors = [
+        "non_empty",
+        "max_length",
+        "range",
+        "finite",
+        "email_format",
+        "validation_error",
+    ]
+    .iter()
+    .map(|name| builtin_visitor(name).unwrap())
+    .collect();
+    let src = generate(
+        r#"
+        package test:validation;
+
+        interface api {
+          record point {
+            #finite
+            x: f32,
+            #range(0, 100)
+            y: f32,
+          }
+
+          record user {
+            #non_empty
+            #max_length(255)
+            name: string,
+            #email_format
+            email: string,
+            #range(18, 120)
+            age: u8,
+            home: option<point>,
+          }
+
+          variant shape {
+            #range(0.0, 100.0)
+            circle(f64),
+            polygon(list<point>),
+            empty,
+          }
+
+          record plain {
+            id: u32,
+          }
+
+          record pairs {
+            pair: tuple<point, u32>,
+            mixed: tuple<point, plain>,
+          }
+
+          register: func(u: user) -> u32;
+
+          pick: func(r: result<user, string>, ps: pairs) -> u32;
+
+          #validation_error(crate::invalid_shape)
+          #range(scale, 0.5, 4)
+          draw: func(s: shape, scale: f32) -> result<u32, string>;
+
+          #non_empty(tag)
+          #max_length(tag, 16)
+          label: func(tag: string) -> u32;
+
+          count: func(p: plain) -> u32;
+        }
+
+        world test {
+          export api;
+        }
+        "#,
+        visitors,
+    );
+
+    assert!(
+        src.contains(
+            "impl wit_bindgen::rt::Validate for Point { \
+             fn validate(&self) -> ::core::result::Result<(), &'static str> { \
+             { let value = &self.x; \
+             if !(value.is_finite()) { return Err(\"point.x must be finite\"); } } \
+             { let value = &self.y; \
+             if !((0.0..=100.0).contains(value)) { return Err(\"point.y must be in 0..=100\"); } } \
+             Ok(()) } }"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains("if !(!value.is_empty()) { return Err(\"user.name must not be empty\"); }"),
+        "{src}"
+    );
+    assert!(
+        src.contains("return Err(\"user.name must have a length of at most 255\");"),
+        "{src}"
+    );
+    assert!(
+        src.contains("return Err(\"user.email must be an email address\");"),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "if !((18..=120).contains(value)) { return Err(\"user.age must be in 18..=120\"); }"
+        ),
+        "{src}"
+    );
+    // Nested types are checked through their own impls.
+    assert!(
+        src.contains("{ let value = &self.home; wit_bindgen::rt::Validate::validate(value)?; }"),
+        "{src}"
+    );
+    assert!(
+        src.contains("if let Shape::Circle(value) = self { if !((0.0..=100.0).contains(value))"),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "if let Shape::Polygon(value) = self { wit_bindgen::rt::Validate::validate(value)?; }"
+        ),
+        "{src}"
+    );
+    assert!(!src.contains("Validate for Plain"), "{src}");
+    // Tuples and results are checked through the runtime's impls, as long as
+    // they hold nothing else lacking one.
+    assert!(
+        src.contains("{ let value = &self.pair; wit_bindgen::rt::Validate::validate(value)?; }"),
+        "{src}"
+    );
+    assert!(!src.contains("&self.mixed"), "{src}");
+
+    // Exports trap on invalid arguments, unless they convert the failure
+    // into their error.
+    assert!(
+        src.contains(
+            "if let Err(e) = wit_bindgen::rt::Validate::validate(&u) { \
+             panic!(\"invalid argument to `register`: {}\", e); }"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "let validation_failure = wit_bindgen::rt::Validate::validate(&s).and_then(|()| \
+             { let value = &scale; if !((0.5..=4.0).contains(value)) \
+             { Err(\"scale must be in 0.5..=4\") } else { Ok(()) } }).err();"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "if let Some(e) = validation_failure { Err(crate::invalid_shape(e)) } \
+             else { T::draw(s, scale) }"
+        ),
+        "{src}"
+    );
+    // Parameters are checked against the rules naming them.
+    assert!(
+        src.contains(
+            "if let Err(e) = { let value = &tag; \
+             if !(!value.is_empty()) { Err(\"tag must not be empty\") } \
+             else if !(value.len() <= 16) { Err(\"tag must have a length of at most 16\") } \
+             else { Ok(()) } } { panic!(\"invalid argument to `label`: {}\", e); }"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "if let Err(e) = wit_bindgen::rt::Validate::validate(&r)\
+             .and_then(|()| wit_bindgen::rt::Validate::validate(&ps)) { \
+             panic!(\"invalid argument to `pick`: {}\", e); }"
+        ),
+        "{src}"
+    );
+    assert!(!src.contains("validate(&p)"), "{src}");
+}
+
+#[test]
+fn test_call_tracing() {
+    let wit = r#"
+        package test:tracing;
+
+        #trace
+        interface api {
+          area: func(w: u32, h: u32) -> u32;
+
+          #no_inherit(trace)
+          now: func() -> u64;
+        }
+
+        interface host {
+          #trace
+          log: func(msg: string);
+
+          flush: func();
+        }
+
+        world test {
+          import host;
+          export api;
+        }
+    "#;
+    let src = generate(wit, vec![builtin_visitor("trace").unwrap()]);
+
+    assert!(
+        src.contains(
+            "const TRACE_CALL: wit_bindgen::rt::CallSite = wit_bindgen::rt::CallSite { \
+             interface: Some(\"test:tracing/api\"), function: \"area\", export: true }; \
+             wit_bindgen::rt::trace_enter(&TRACE_CALL, &[&w, &h]); \
+             let func_return = T::area(w, h); \
+             wit_bindgen::rt::trace_exit(&TRACE_CALL, &func_return); func_return"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains(
+            "const TRACE_CALL: wit_bindgen::rt::CallSite = wit_bindgen::rt::CallSite { \
+             interface: Some(\"test:tracing/host\"), function: \"log\", export: false }; \
+             wit_bindgen::rt::trace_enter(&TRACE_CALL, &[&msg]);"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains("wit_bindgen::rt::trace_exit(&TRACE_CALL, &());"),
+        "{src}"
+    );
+    // `now` opts out of the interface's annotation and `flush` isn't
+    // annotated.
+    assert_eq!(src.matches("const TRACE_CALL").count(), 2, "{src}");
+
+    // The option traces every function, without any visitor.
+    let src = generate_with(
+        wit,
+        Opts {
+            trace_calls: true,
+            ..Default::default()
+        },
+    );
+    assert_eq!(src.matches("const TRACE_CALL").count(), 4, "{src}");
+    assert!(
+        src.contains(
+            "function: \"now\", export: true }; \
+             wit_bindgen::rt::trace_enter(&TRACE_CALL, &[]);"
+        ),
+        "{src}"
+    );
+}
+
+#[test]
+fn test_call_tracing_opaque_values() {
+    let wit = r#"
+        package test:tracing;
+
+        interface api {
+          record point {
+            x: u32,
+          }
+
+          move: func(p: point, to: list<point>, n: u32) -> option<point>;
+        }
+
+        world test {
+          import api;
+        }
+    "#;
+    // Types remapped with `with` might not implement `Debug`, so they're
+    // reported as opaque, along with anything holding them.
+    let src = generate_with(
+        wit,
+        Opts {
+            trace_calls: true,
+            with: vec![(
+                "test:tracing/api/point".to_string(),
+                WithOption::Path("crate::Point".to_string()),
+            )],
+            ..Default::default()
+        },
+    );
+    assert!(
+        src.contains(
+            "wit_bindgen::rt::trace_enter(&TRACE_CALL, \
+             &[&wit_bindgen::rt::Opaque, &wit_bindgen::rt::Opaque, &n]);"
+        ),
+        "{src}"
+    );
+    assert!(
+        src.contains("wit_bindgen::rt::trace_exit(&TRACE_CALL, &wit_bindgen::rt::Opaque);"),
+        "{src}"
+    );
+}
@@ -2,7 +2,7 @@
 // contribution types, and behavior
 
 use wit_bindgen_core::wit_parser::*;
-use wit_bindgen_core::Visitor;
+use wit_bindgen_core::{AnnotationArgs, VisitContext, Visitor};
 use wit_bindgen_rust::annotation_visitor::*;
 
 // Test visitor implementations
@@ -19,8 +19,9 @@ impl Visitor for DeprecatedVisitor {
 
     fn visit_function(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _func: &Function,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustFunctionContribution> {
         let mut contrib = RustFunctionContribution::new();
         if annotation.is_empty() {
@@ -33,9 +34,10 @@ impl Visitor for DeprecatedVisitor {
 
     fn visit_variant_case(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _case: &Case,
         _case_index: usize,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustVariantCaseContribution> {
         let mut contrib = RustVariantCaseContribution::new();
         if annotation.is_empty() {
@@ -59,8 +61,9 @@ impl Visitor for TracingVisitor {
 
     fn visit_function(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         func: &Function,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustFunctionContribution> {
         let mut contrib = RustFunctionContribution::new();
         contrib.add_attribute("#[tracing::instrument]");
@@ -80,8 +83,9 @@ impl Visitor for TracingVisitor {
 
     fn visit_interface(
         &mut self,
-        _annotation: &String,
+        _annotation: &AnnotationArgs,
         _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustModuleContribution> {
         let mut contrib = RustModuleContribution::new();
         contrib.add_use("use tracing");
@@ -101,8 +105,9 @@ impl Visitor for ValidateVisitor {
 
     fn visit_function(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _func: &Function,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustFunctionContribution> {
         let mut contrib = RustFunctionContribution::new();
         if !annotation.is_empty() {
@@ -124,8 +129,9 @@ impl Visitor for SinceVisitor {
 
     fn visit_function(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _func: &Function,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustFunctionContribution> {
         let mut contrib = RustFunctionContribution::new();
         contrib.add_attribute(&format!("#[doc = \"Since version: {}\"]", annotation));
@@ -134,8 +140,9 @@ impl Visitor for SinceVisitor {
 
     fn visit_interface(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _interface: Option<&Interface>,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustModuleContribution> {
         let mut contrib = RustModuleContribution::new();
         contrib.add_code(&format!(
@@ -158,9 +165,10 @@ impl Visitor for DeriveVisitor {
 
     fn visit_enum(
         &mut self,
-        annotation: &String,
+        annotation: &AnnotationArgs,
         _enum: &Enum,
         _type_id: TypeId,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustTypeContribution> {
         let mut contrib = RustTypeContribution::new();
         for derive in annotation.split(',').map(|s| s.trim()) {
@@ -184,8 +192,9 @@ impl Visitor for LoggingVisitor {
 
     fn visit_function(
         &mut self,
-        _annotation: &String,
+        _annotation: &AnnotationArgs,
         func: &Function,
+        _cx: &VisitContext<'_>,
     ) -> Option<RustFunctionContribution> {
         let mut contrib = RustFunctionContribution::new();
 
@@ -220,6 +229,9 @@ fn test_deprecated_visitor_basic() {
         attributes: vec![],
         body_prefix: vec![],
         body_suffix: vec![],
+        result_transforms: vec![],
+        validation_error: None,
+        trace: false,
     };
 
     // Verify visitor target
@@ -300,8 +312,14 @@ fn test_body_suffix_logging() {
         annotations: Default::default(),
     };
 
+    let mut resolve = Resolve::default();
+    let pkg = resolve
+        .push_str("test.wit", "package a:b; world w {}")
+        .unwrap();
+    let cx = VisitContext::new(&resolve, resolve.select_world(&[pkg], None).unwrap());
+
     let mut visitor = LoggingVisitor;
-    let contrib = visitor.visit_function(&String::new(), &func);
+    let contrib = visitor.visit_function(&AnnotationArgs::default(), &func, &cx);
 
     assert!(contrib.is_some());
     let contrib = contrib.unwrap();
@@ -29,6 +29,7 @@ pub struct Rust;
 pub struct State {
     wit_bindgen_rlib: PathBuf,
     futures_rlib: PathBuf,
+    serde_rlib: PathBuf,
     wit_bindgen_deps: Vec<PathBuf>,
 }
 
@@ -123,6 +124,7 @@ name = "tmp"
 [dependencies]
 wit-bindgen = {{ {wit_bindgen_dep}, features = ['async-spawn'] }}
 futures = "0.3.31"
+serde = { version = "1.0", features = ['derive'] }
 
 [lib]
 path = 'lib.rs'
@@ -138,6 +140,7 @@ path = 'lib.rs'
                 .arg("build")
                 .arg("-pwit-bindgen")
                 .arg("-pfutures")
+                .arg("-pserde")
                 .arg("--target")
                 .arg(&opts.rust_target),
         )?;
@@ -149,12 +152,15 @@ path = 'lib.rs'
         let host_out_dir = wit_bindgen.join("target/debug");
         let wit_bindgen_rlib = target_out_dir.join("libwit_bindgen.rlib");
         let futures_rlib = target_out_dir.join("libfutures.rlib");
+        let serde_rlib = target_out_dir.join("libserde.rlib");
         assert!(wit_bindgen_rlib.exists());
         assert!(futures_rlib.exists());
+        assert!(serde_rlib.exists());
 
         runner.rust_state = Some(State {
             wit_bindgen_rlib,
             futures_rlib,
+            serde_rlib,
             wit_bindgen_deps: vec![target_out_dir.join("deps"), host_out_dir.join("deps")],
         });
         Ok(())
@@ -295,6 +301,7 @@ impl Runner<'_> {
             "--extern=futures={}",
             state.futures_rlib.display()
         ))
+        .arg(&format!("--extern=serde={}", state.serde_rlib.display()))
         .arg("--target")
         .arg(&opts.rust_target)
         .arg("-Dwarnings")
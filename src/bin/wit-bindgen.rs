@@ -1,9 +1,11 @@
 use anyhow::{bail, Context, Error, Result};
 use clap::Parser;
+use std::fmt::Write as _;
 use std::path::PathBuf;
 use std::str;
-use wit_bindgen_core::{wit_parser, Files, WorldGenerator};
-use wit_parser::Resolve;
+use wit_bindgen_core::visitor::{annotated_items, parse_builtin, AnnotatedItem};
+use wit_bindgen_core::{wit_parser, Diagnostics, Files, WorldGenerator};
+use wit_parser::{PackageId, Resolve};
 
 /// Helper for passing VERSION to opt.
 /// If CARGO_VERSION_INFO is set, use it, otherwise use CARGO_PKG_VERSION.
@@ -72,6 +74,13 @@ enum Opt {
         args: Common,
     },
 
+    /// Lists the annotations in WIT packages, or checks them against the
+    /// annotation visitors of a bindings generator.
+    Annotations {
+        #[clap(flatten)]
+        opts: AnnotationsOpts,
+    },
+
     // doc-comments are present on `wit_bindgen_test::Opts` for clap to use.
     Test {
         #[clap(flatten)]
@@ -129,6 +138,77 @@ struct Common {
     all_features: bool,
 }
 
+#[derive(Debug, Parser)]
+struct AnnotationsOpts {
+    /// Locations of WIT file(s) whose annotations are listed, along with
+    /// those of their `deps` folders.
+    ///
+    /// These are the same as the WIT locations bindings are generated for.
+    #[clap(value_name = "WIT", index = 1)]
+    wit: Vec<PathBuf>,
+
+    /// The world whose annotations are checked with `--lint`, selected as
+    /// when generating bindings.
+    #[clap(short, long)]
+    world: Option<String>,
+
+    /// Comma-separated list of features that should be enabled when processing
+    /// WIT files.
+    #[clap(long)]
+    features: Vec<String>,
+
+    /// Whether or not to activate all WIT features when processing WIT files.
+    #[clap(long)]
+    all_features: bool,
+
+    /// How to print the annotations: a table with a row per annotation, or
+    /// a JSON array with an object per annotation.
+    #[clap(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Check the annotations of the world against the visitors registered
+    /// with the generator for LANGUAGE instead of listing them.
+    ///
+    /// Annotations for targets without a visitor, that no visitor supports
+    /// on their item, or with malformed arguments are reported, and the
+    /// command fails if there are any.
+    #[clap(long, value_enum, value_name = "LANGUAGE")]
+    lint: Option<Language>,
+
+    /// Built-in annotation visitors to register when linting, as with the
+    /// `--annotations` option of the generator for LANGUAGE.
+    #[clap(long, value_delimiter = ',', requires = "lint")]
+    annotations: Vec<String>,
+
+    /// Path to a TOML file describing template visitors to register when
+    /// linting for Rust, as with `wit-bindgen rust --annotation-config`.
+    #[cfg(feature = "rust")]
+    #[clap(long, value_name = "PATH", requires = "lint")]
+    annotation_config: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+/// The generators whose annotation visitors `annotations --lint` can check
+/// against.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Language {
+    #[cfg(feature = "rust")]
+    Rust,
+    #[cfg(feature = "c")]
+    C,
+    #[cfg(feature = "cpp")]
+    Cpp,
+    #[cfg(feature = "csharp")]
+    Csharp,
+    #[cfg(feature = "moonbit")]
+    Moonbit,
+}
+
 fn main() -> Result<()> {
     env_logger::init();
 
@@ -150,6 +230,7 @@ fn main() -> Result<()> {
         }
         #[cfg(feature = "csharp")]
         Opt::Csharp { opts, args } => (opts.build(), args),
+        Opt::Annotations { opts } => return annotations(&opts),
         Opt::Test { opts } => return opts.run(std::env::args_os().nth(0).unwrap().as_ref()),
     };
 
@@ -212,9 +293,33 @@ fn gen_world(
     opts: &Common,
     files: &mut Files,
 ) -> Result<()> {
+    let (resolve, main_packages, source_files) =
+        load_wit(&opts.wit, &opts.features, opts.all_features)?;
+    let world = resolve.select_world(&main_packages, opts.world.as_deref())?;
+    let result = generator.generate(&resolve, world, files);
+
+    // Diagnostics go to stderr so they don't mix with generated output, and
+    // are printed even when generation failed because of them.
+    let mut diagnostics = generator.take_diagnostics();
+    Diagnostics::locate_in_files(&mut diagnostics, &source_files);
+    for diagnostic in diagnostics {
+        eprintln!("{diagnostic}");
+    }
+    result?;
+
+    Ok(())
+}
+
+/// Parses the WIT at the locations `wit`, returning the main packages and
+/// the source files they were parsed from.
+fn load_wit(
+    wit: &[PathBuf],
+    features: &[String],
+    all_features: bool,
+) -> Result<(Resolve, Vec<PackageId>, Vec<PathBuf>)> {
     let mut resolve = Resolve::default();
-    resolve.all_features = opts.all_features;
-    for features in opts.features.iter() {
+    resolve.all_features = all_features;
+    for features in features.iter() {
         for feature in features
             .split(',')
             .flat_map(|s| s.split_whitespace())
@@ -224,16 +329,169 @@ fn gen_world(
         }
     }
     let mut main_packages = Vec::new();
-    for wit in &opts.wit {
-        let (pkg, _files) = resolve.push_path(wit)?;
+    let mut source_files = Vec::new();
+    for path in wit {
+        let (pkg, sources) = resolve.push_path(path)?;
         main_packages.push(pkg);
+        source_files.extend(sources.paths().map(|p| p.to_owned()));
     }
+    Ok((resolve, main_packages, source_files))
+}
+
+fn annotations(opts: &AnnotationsOpts) -> Result<()> {
+    let (resolve, main_packages, source_files) =
+        load_wit(&opts.wit, &opts.features, opts.all_features)?;
+    let Some(language) = opts.lint else {
+        let items = annotated_items(&resolve);
+        match opts.format {
+            Format::Table => print!("{}", annotation_table(&items)),
+            Format::Json => println!("{}", annotation_json(&items)),
+        }
+        return Ok(());
+    };
+
+    // Generating the bindings in strict mode reports every annotation the
+    // generator would ignore.
+    let mut generator = lint_generator(language, opts)?;
     let world = resolve.select_world(&main_packages, opts.world.as_deref())?;
-    generator.generate(&resolve, world, files)?;
+    let result = generator.generate(&resolve, world, &mut Files::default());
+    let mut diagnostics = generator.take_diagnostics();
+    if diagnostics.is_empty() {
+        return result.map_err(attach_with_context);
+    }
+    Diagnostics::locate_in_files(&mut diagnostics, &source_files);
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{diagnostic}");
+    }
+    bail!("found {} problem(s) with annotations", diagnostics.len())
+}
+
+/// Builds the generator for `language` in strict annotation mode, with the
+/// visitors selected by `opts`.
+fn lint_generator(language: Language, opts: &AnnotationsOpts) -> Result<Box<dyn WorldGenerator>> {
+    #[cfg(feature = "rust")]
+    if !matches!(language, Language::Rust) && opts.annotation_config.is_some() {
+        bail!("`--annotation-config` only applies to `--lint rust`");
+    }
+    Ok(match language {
+        #[cfg(feature = "rust")]
+        Language::Rust => {
+            use wit_bindgen_rust::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
+            check_builtin_annotations(&opts.annotations, BUILTIN_ANNOTATIONS, builtin_visitor)?;
+            wit_bindgen_rust::Opts {
+                generate_all: true,
+                strict_annotations: true,
+                annotations: opts.annotations.clone(),
+                annotation_config: opts.annotation_config.clone(),
+                ..Default::default()
+            }
+            .build()
+        }
+        #[cfg(feature = "c")]
+        Language::C => {
+            use wit_bindgen_c::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
+            check_builtin_annotations(&opts.annotations, BUILTIN_ANNOTATIONS, builtin_visitor)?;
+            wit_bindgen_c::Opts {
+                strict_annotations: true,
+                annotations: opts.annotations.clone(),
+                ..Default::default()
+            }
+            .build()
+        }
+        #[cfg(feature = "cpp")]
+        Language::Cpp => {
+            use wit_bindgen_cpp::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
+            check_builtin_annotations(&opts.annotations, BUILTIN_ANNOTATIONS, builtin_visitor)?;
+            wit_bindgen_cpp::Opts {
+                strict_annotations: true,
+                annotations: opts.annotations.clone(),
+                ..Default::default()
+            }
+            .build(None)
+        }
+        #[cfg(feature = "csharp")]
+        Language::Csharp => {
+            use wit_bindgen_csharp::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
+            check_builtin_annotations(&opts.annotations, BUILTIN_ANNOTATIONS, builtin_visitor)?;
+            wit_bindgen_csharp::Opts {
+                strict_annotations: true,
+                annotations: opts.annotations.clone(),
+                ..Default::default()
+            }
+            .build()
+        }
+        #[cfg(feature = "moonbit")]
+        Language::Moonbit => {
+            use wit_bindgen_moonbit::annotation_visitor::{builtin_visitor, BUILTIN_ANNOTATIONS};
+            check_builtin_annotations(&opts.annotations, BUILTIN_ANNOTATIONS, builtin_visitor)?;
+            wit_bindgen_moonbit::Opts {
+                strict_annotations: true,
+                annotations: opts.annotations.clone(),
+                ..Default::default()
+            }
+            .build()
+        }
+    })
+}
 
+/// Fails if any of `names` isn't one of the generator's built-in visitors,
+/// see [`parse_builtin`].
+fn check_builtin_annotations<T: ?Sized>(
+    names: &[String],
+    builtins: &[&str],
+    lookup: fn(&str) -> Option<Box<T>>,
+) -> Result<()> {
+    for name in names {
+        parse_builtin(name, builtins, lookup).map_err(Error::msg)?;
+    }
     Ok(())
 }
 
+/// Renders `items` as a table with a row per annotation.
+fn annotation_table(items: &[AnnotatedItem]) -> String {
+    let mut rows = vec![["ITEM", "KIND", "TARGET", "VALUE"].map(String::from)];
+    rows.extend(items.iter().map(|item| {
+        [
+            item.location.to_string(),
+            item.kind.to_string(),
+            item.target.clone(),
+            item.value.clone(),
+        ]
+    }));
+    let mut widths = [0; 4];
+    for row in rows.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in rows.iter() {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths) {
+            write!(line, "{cell:width$}  ").unwrap();
+        }
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// Renders `items` as a JSON array with an object per annotation.
+fn annotation_json(items: &[AnnotatedItem]) -> String {
+    let objects = items
+        .iter()
+        .map(|item| {
+            serde_json::json!({
+                "item": item.location.to_string(),
+                "kind": item.kind.as_str(),
+                "target": item.target,
+                "value": item.value,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&objects).unwrap()
+}
+
 #[test]
 fn verify_cli() {
     use clap::CommandFactory;
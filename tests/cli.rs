@@ -0,0 +1,106 @@
+// Runs the `wit-bindgen annotations` subcommand against small WIT packages.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const WIT: &str = r#"
+package test:cli;
+
+interface api {
+  #derive(Hash)
+  record point {
+    #range(0, 10)
+    x: u32,
+  }
+
+  #deprecated("use area2")
+  area: func(p: point) -> u32;
+}
+
+world test {
+  import api;
+}
+"#;
+
+/// Writes `wit` to a file named after the test and returns its path.
+fn write_wit(test: &str, wit: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{test}.wit"));
+    std::fs::write(&path, wit).unwrap();
+    path
+}
+
+fn annotations(wit: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wit-bindgen"))
+        .arg("annotations")
+        .arg(wit)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_table() {
+    let wit = write_wit("table", WIT);
+    let output = annotations(&wit, &[]);
+    assert_eq!(
+        stdout(&output),
+        "\
+ITEM                  KIND      TARGET      VALUE
+test:cli/api.point    record    derive      Hash
+test:cli/api.point.x  field     range       0, 10
+test:cli/api.area     function  deprecated  \"use area2\"
+"
+    );
+}
+
+#[test]
+fn test_json() {
+    let wit = write_wit("json", WIT);
+    let output = annotations(&wit, &["--format", "json"]);
+    let items: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(
+        items,
+        serde_json::json!([
+            {"item": "test:cli/api.point", "kind": "record", "target": "derive", "value": "Hash"},
+            {"item": "test:cli/api.point.x", "kind": "field", "target": "range", "value": "0, 10"},
+            {
+                "item": "test:cli/api.area",
+                "kind": "function",
+                "target": "deprecated",
+                "value": "\"use area2\"",
+            },
+        ])
+    );
+}
+
+#[cfg(feature = "rust")]
+#[test]
+fn test_lint() {
+    let wit = write_wit("lint", WIT);
+    let output = annotations(
+        &wit,
+        &["--lint", "rust", "--annotations", "derive,range,deprecated"],
+    );
+    assert!(output.status.success(), "{output:?}");
+
+    // Without a visitor for `#range` the lint fails, for use in CI.
+    let output = annotations(
+        &wit,
+        &["--lint", "rust", "--annotations", "derive,deprecated"],
+    );
+    assert!(!output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("no visitor registered for annotation target 'range'"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("found 1 problem(s) with annotations"),
+        "{stderr}"
+    );
+}
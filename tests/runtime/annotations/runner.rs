@@ -0,0 +1,7 @@
+include!(env!("BINDINGS"));
+
+use crate::test::annotations::to_test::*;
+
+fn main() {
+    assert_eq!(divide(7, 2), 3);
+}
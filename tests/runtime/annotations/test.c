@@ -0,0 +1,7 @@
+//@ args = '--annotations deprecated,assert'
+
+#include <test.h>
+
+uint32_t exports_test_annotations_to_test_divide(uint32_t dividend, uint32_t divisor) {
+  return dividend / divisor;
+}
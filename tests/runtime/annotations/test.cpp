@@ -0,0 +1,7 @@
+//@ args = '--annotations deprecated,nodiscard,assert'
+
+#include <test_cpp.h>
+
+uint32_t exports::test::annotations::to_test::Divide(uint32_t dividend, uint32_t divisor) {
+  return dividend / divisor;
+}
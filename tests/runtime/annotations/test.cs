@@ -0,0 +1,12 @@
+//@ args = '--annotations deprecated,rename'
+
+namespace TestWorld.wit.exports.test.annotations
+{
+    public class ToTestImpl : ITestWorld
+    {
+        public static uint Divide(uint dividend, uint divisor)
+        {
+            return dividend / divisor;
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//@ args = [
+//@   '--annotations=derive,serde,repr,cfg,deprecated,must_use,inline,assert',
+//@   '--annotations=non_empty,min_length,max_length,range,finite,email_format',
+//@   '--annotations=validation_error,trace',
+//@ ]
+
+include!(env!("BINDINGS"));
+
+use crate::test::rust_annotations::api::*;
+
+fn main() {
+    #[allow(deprecated)]
+    let quotient = divide(7, 2);
+    assert_eq!(quotient, 3);
+
+    let mut user = User {
+        user_name: "ferris".to_string(),
+        email: "ferris@example.com".to_string(),
+        age: 30,
+    };
+    assert_eq!(register(&user, Role::Guest), Ok(2));
+    user.user_name.clear();
+    assert_eq!(
+        register(&user, Role::Admin),
+        Err("invalid input: user.user-name must not be empty".to_string())
+    );
+
+    assert_eq!(perimeter(&Shape::Polygon(vec![3.0, 4.0, 5.0])), 12.0);
+}
@@ -0,0 +1,45 @@
+//@ args = [
+//@   '--annotations=derive,serde,repr,cfg,deprecated,must_use,inline,assert',
+//@   '--annotations=non_empty,min_length,max_length,range,finite,email_format',
+//@   '--annotations=validation_error,trace',
+//@ ]
+
+include!(env!("BINDINGS"));
+
+use crate::exports::test::rust_annotations::api::{Guest, Role, Shape, User};
+use std::hash::Hash;
+
+struct Component;
+
+export!(Component);
+
+/// Turns the validation failures of `register`'s arguments into its error.
+fn invalid_input(message: &'static str) -> String {
+    format!("invalid input: {message}")
+}
+
+/// Checks that the types derive what their annotations ask for.
+fn assert_derives<T: Hash + serde::Serialize + serde::de::DeserializeOwned>() {}
+
+impl Guest for Component {
+    fn divide(dividend: u32, divisor: u32) -> u32 {
+        dividend / divisor
+    }
+
+    fn register(user: User, role: Role) -> Result<u32, String> {
+        assert_derives::<User>();
+        assert_derives::<Role>();
+        assert!(!user.user_name.is_empty());
+        Ok(match role {
+            Role::Admin => 1,
+            Role::Guest => 2,
+        })
+    }
+
+    fn perimeter(s: Shape) -> f64 {
+        match s {
+            Shape::Circle(radius) => 2.0 * radius,
+            Shape::Polygon(sides) => sides.iter().sum(),
+        }
+    }
+}
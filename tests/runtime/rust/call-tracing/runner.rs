@@ -0,0 +1,92 @@
+//@ args = [
+//@   '--trace-calls',
+//@   '--with=test:tracing/api/tag=crate::Tag',
+//@ ]
+
+include!(env!("BINDINGS"));
+
+use crate::test::tracing::api::*;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use wit_bindgen::rt::{CallObserver, CallSite};
+
+/// Doesn't implement `Debug`, so it's reported as opaque.
+pub struct Tag {
+    pub id: u32,
+}
+
+/// Records the calls through the imports of this component.
+struct Recorder(Mutex<Vec<String>>);
+
+impl CallObserver for Recorder {
+    fn enter(&self, call: &CallSite, args: &[&dyn Debug]) {
+        assert!(!call.export);
+        assert_eq!(call.interface, Some("test:tracing/api"));
+        let event = format!("enter {} {args:?}", call.function);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn exit(&self, call: &CallSite, result: &dyn Debug) {
+        let event = format!("exit {} {result:?}", call.function);
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+/// Checks that each of `events` starts with the expected prefix, as resources
+/// are rendered with their handles.
+fn assert_events(events: &[String], expected: &[&str]) {
+    assert_eq!(events.len(), expected.len(), "{events:#?}");
+    for (event, expected) in events.iter().zip(expected) {
+        assert!(event.starts_with(expected), "{event:?} vs {expected:?}");
+    }
+}
+
+fn main() {
+    assert!(wit_bindgen::rt::set_call_observer(&RECORDER).is_ok());
+
+    start_tracing();
+    let a = Point { x: 0, y: 0 };
+    let b = Point { x: 3, y: 4 };
+    assert_eq!(distance(a, b), 7);
+    assert_eq!(retag(Tag { id: 1 }).id, 2);
+    let counter = Counter::new(1);
+    assert_eq!(counter.add(2), 3);
+    let exported = traced_calls();
+
+    assert_events(
+        &RECORDER.0.lock().unwrap(),
+        &[
+            "enter start-tracing []",
+            "exit start-tracing ()",
+            "enter distance [Point { x: 0, y: 0 }, Point { x: 3, y: 4 }]",
+            "exit distance 7",
+            "enter retag [..]",
+            "exit retag ..",
+            "enter [constructor]counter [1]",
+            "exit [constructor]counter Counter {",
+            "enter [method]counter.add [Counter {",
+            "exit [method]counter.add 3",
+            "enter traced-calls []",
+            "exit traced-calls [\"exit start-tracing ()\",",
+        ],
+    );
+    // The exports see the calls once their observer is set, and are given the
+    // types they generated themselves, which implement `Debug`.
+    assert_events(
+        &exported,
+        &[
+            "exit start-tracing ()",
+            "enter distance [Point { x: 0, y: 0 }, Point { x: 3, y: 4 }]",
+            "exit distance 7",
+            "enter retag [Tag { id: 1 }]",
+            "exit retag Tag { id: 2 }",
+            "enter [constructor]counter [1]",
+            "exit [constructor]counter Counter {",
+            "enter [method]counter.add [CounterBorrow {",
+            "exit [method]counter.add 3",
+            "enter traced-calls []",
+        ],
+    );
+}
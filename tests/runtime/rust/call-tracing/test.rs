@@ -0,0 +1,65 @@
+//@ args = '--trace-calls'
+
+include!(env!("BINDINGS"));
+
+use crate::exports::test::tracing::api::{Guest, GuestCounter, Point, Tag};
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use wit_bindgen::rt::{CallObserver, CallSite};
+
+/// Records the calls into the exports of this component.
+struct Recorder(Mutex<Vec<String>>);
+
+impl CallObserver for Recorder {
+    fn enter(&self, call: &CallSite, args: &[&dyn Debug]) {
+        assert!(call.export);
+        assert_eq!(call.interface, Some("test:tracing/api"));
+        let event = format!("enter {} {args:?}", call.function);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn exit(&self, call: &CallSite, result: &dyn Debug) {
+        let event = format!("exit {} {result:?}", call.function);
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+struct Component;
+
+export!(Component);
+
+impl Guest for Component {
+    type Counter = MyCounter;
+
+    fn start_tracing() {
+        assert!(wit_bindgen::rt::set_call_observer(&RECORDER).is_ok());
+    }
+
+    fn distance(a: Point, b: Point) -> u32 {
+        a.x.abs_diff(b.x) + a.y.abs_diff(b.y)
+    }
+
+    fn retag(t: Tag) -> Tag {
+        Tag { id: t.id + 1 }
+    }
+
+    fn traced_calls() -> Vec<String> {
+        RECORDER.0.lock().unwrap().clone()
+    }
+}
+
+struct MyCounter(Cell<u32>);
+
+impl GuestCounter for MyCounter {
+    fn new(start: u32) -> MyCounter {
+        MyCounter(Cell::new(start))
+    }
+
+    fn add(&self, n: u32) -> u32 {
+        self.0.set(self.0.get() + n);
+        self.0.get()
+    }
+}